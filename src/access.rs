@@ -0,0 +1,58 @@
+use std::fs::Metadata;
+use std::path::Path;
+
+/// Describes what the current user may do with `path` as an `rwx` style triple.
+#[cfg(unix)]
+pub(crate) fn describe_access(path: &Path, _meta_data: &Metadata) -> String {
+    [(libc::R_OK, 'r'), (libc::W_OK, 'w'), (libc::X_OK, 'x')]
+        .iter()
+        .map(|(mode, granted)| {
+            if has_access(path, *mode) {
+                *granted
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+#[cfg(not(unix))]
+pub(crate) fn describe_access(_path: &Path, meta_data: &Metadata) -> String {
+    if meta_data.permissions().readonly() {
+        String::from("r--")
+    } else {
+        String::from("rw-")
+    }
+}
+
+/// Uses the effective ids, so group membership and root's privileges are taken into account.
+#[cfg(unix)]
+pub(crate) fn has_access(path: &Path, mode: libc::c_int) -> bool {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+    let Ok(c_path) = CString::new(path.as_os_str().as_bytes()) else {
+        return false;
+    };
+    unsafe { libc::faccessat(libc::AT_FDCWD, c_path.as_ptr(), mode, libc::AT_EACCESS) == 0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    #[cfg(unix)]
+    fn reports_read_write_without_execute_for_plain_files() {
+        use std::os::unix::fs::PermissionsExt;
+        let temp_dir = tempdir().unwrap();
+        let file = temp_dir.path().join("plain.txt");
+        fs::write(&file, "").unwrap();
+        fs::set_permissions(&file, fs::Permissions::from_mode(0o644)).unwrap();
+        let meta_data = fs::metadata(&file).unwrap();
+        assert_eq!(describe_access(&file, &meta_data), "rw-");
+        fs::set_permissions(&file, fs::Permissions::from_mode(0o755)).unwrap();
+        assert_eq!(describe_access(&file, &meta_data), "rwx");
+    }
+}
@@ -1,23 +1,384 @@
+use crate::color::ColorMode;
+use crate::columns::{ColumnLimits, OptionalColumn};
+use crate::compat::CompatStyle;
+use crate::completions::{self, DocumentedFlag, Shell};
+use crate::config_file::{ConfigFile, FlagTable};
+use crate::delimited::{CsvDialect, CsvQuoting};
+use crate::encoding::OutputEncoding;
+use crate::export::ExportFormat;
+use crate::highlight::{self, Highlights, Threshold};
+use crate::hooks::Hooks;
+use crate::locale::Locale;
+use crate::machine_output::{self, MachineFormat, Pagination};
+use crate::output_formatting::{self, DirGrouping, GridOrder, IconStyle, PathStyle};
+use crate::reports::Report;
+use crate::search::NamePattern;
+use crate::server::{DEFAULT_ADDRESS, DEFAULT_PORT};
+use crate::sorting::SortKey;
+use crate::template::Template;
 use dirs;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::BTreeMap;
 use std::fmt;
 use std::fmt::Formatter;
-use std::path::Path;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
 
 const F_FLAG: &str = "F";
 const L_FLAG: &str = "l";
+const EXTENDED_FLAG: &str = "extended";
+const FILE_FLAG: &str = "file";
+const GRID_FLAG: &str = "grid";
+const GRID_ACROSS_LONG_FLAG: &str = "grid-across";
+const NEGATION_PREFIX: &str = "no-";
+const GRID_DOWN_FLAG: &str = "C";
+const GRID_ACROSS_FLAG: &str = "x";
+const R_FLAG: &str = "R";
+const S_FLAG: &str = "s";
+const LOWER_R_FLAG: &str = "r";
+const A_FLAG: &str = "a";
+const UPPER_L_FLAG: &str = "L";
+const GLOB_FLAG: &str = "glob";
+const EXCLUDE_FLAG: &str = "exclude";
+const MATCH_FLAG: &str = "match";
+const FILES_FLAG: &str = "files";
+const DIRS_FLAG: &str = "dirs";
+const GIT_IGNORE_FLAG: &str = "git-ignore";
+const WATCH_FLAG: &str = "watch";
+const FOLLOW_FLAG: &str = "follow";
+const NO_PAGER_FLAG: &str = "no-pager";
+const TREE_FLAG: &str = "tree";
+const COLOR_FLAG: &str = "color";
+const ONE_FLAG: &str = "1";
+const ZERO_FLAG: &str = "0";
+const SINGLE_COLUMN_FLAG: &str = "single-column";
+const DEPTH_FLAG: &str = "depth";
+const DEREFERENCE_FLAG: &str = "dereference";
+const ALL_FLAG: &str = "all";
+const REVERSE_FLAG: &str = "reverse";
+const RECURSIVE_FLAG: &str = "recursive";
+const LARGEST_FLAG: &str = "largest";
+const OLDEST_FLAG: &str = "oldest";
+const NEWEST_FLAG: &str = "newest";
+const BY_EXTENSION_FLAG: &str = "by-extension";
+const HISTOGRAM_FLAG: &str = "histogram";
+const VERIFY_FLAG: &str = "verify";
+const CHECKSUMS_FLAG: &str = "checksums";
+const CHECK_COLLISIONS_FLAG: &str = "check-collisions";
+const SUMMARY_FLAG: &str = "summary";
+const OCTAL_FLAG: &str = "octal";
+const AUDIT_FLAG: &str = "audit";
+const MOUNT_FLAG: &str = "mount";
+const PROJECT_ID_FLAG: &str = "project-id";
+const COMPRESSION_FLAG: &str = "compression";
+const SHARED_EXTENTS_FLAG: &str = "shared-extents";
+const ACCESS_FLAG: &str = "access";
+const JSON_FLAG: &str = "json";
+const NDJSON_FLAG: &str = "ndjson";
+const EXPORT_PARQUET_FLAG: &str = "export-parquet";
+const EXPORT_ARROW_FLAG: &str = "export-arrow";
+const GROUP_DIRS_FLAG: &str = "group-dirs";
+const PORT_FLAG: &str = "port";
+const BIND_FLAG: &str = "bind";
+const SOCKET_FLAG: &str = "socket";
+const LOCALE_FLAG: &str = "locale";
+const OWNER_FLAG: &str = "owner";
+const FULL_NAME_FLAG: &str = "full-name";
+const TWO_LINE_FLAG: &str = "two-line";
+const DIRED_FLAG: &str = "dired";
+const REPEAT_HEADER_FLAG: &str = "repeat-header";
+const CSV_FLAG: &str = "csv";
+const CSV_DELIMITER_FLAG: &str = "csv-delimiter";
+const CSV_QUOTING_FLAG: &str = "csv-quoting";
+const NO_CSV_HEADER_FLAG: &str = "no-csv-header";
+const TSV_FLAG: &str = "tsv";
+const HTML_FLAG: &str = "html";
+const SEPARATOR_FLAG: &str = "separator";
+const CRLF_FLAG: &str = "crlf";
+const ENCODING_FLAG: &str = "encoding";
+const MKDIR_FLAG: &str = "mkdir";
+const FORCE_FLAG: &str = "force";
+const DUMP_CONFIG_FLAG: &str = "dump-config";
+const VERSION_FLAG: &str = "version";
+const HELP_FLAG: &str = "help";
+const COMPLETIONS_FLAG: &str = "completions";
+const ICONS_FLAG: &str = "icons";
+const WIDTH_FLAG: &str = "width";
+const STRICT_UTF8_FLAG: &str = "strict-utf8";
+const RELATIVE_TIME_FLAG: &str = "relative-time";
+const TIME_FORMAT_FLAG: &str = "time-format";
+const FORMAT_FLAG: &str = "format";
+const SORT_FLAG: &str = "sort";
+const COMPAT_FLAG: &str = "compat";
+const PROFILE_FLAG: &str = "profile";
+const PATH_STYLE_FLAG: &str = "path-style";
+const PICK_FLAG: &str = "pick";
+const PRINT0_FLAG: &str = "print0";
+const PAGE_SIZE_FLAG: &str = "page-size";
+const CURSOR_FLAG: &str = "cursor";
+const HIGHLIGHT_OLDER_THAN_FLAG: &str = "highlight-older-than";
+const HIGHLIGHT_LARGER_THAN_FLAG: &str = "highlight-larger-than";
+const INODE_ORDER_FLAG: &str = "inode-order";
+const VARIABLE_PREFIX: &str = "MINI_LS_";
+const OPTIONS_VARIABLE: &str = "MINI_LS_OPTS";
+const HELP_USAGE_WIDTH: usize = 30;
+pub(crate) const STDOUT_TARGET: &str = "-";
+const END_OF_OPTIONS: &str = "--";
+const STATS_COMMAND: &str = "stats";
+const FIND_COMMAND: &str = "find";
+const SERVE_COMMAND: &str = "serve";
+const INDEX_COMMAND: &str = "index";
+const QUERY_COMMAND: &str = "query";
+const CONFIG_COMMAND: &str = "config";
+const DOCTOR_COMMAND: &str = "doctor";
+const SUBCOMMANDS: [&str; 7] = [
+    STATS_COMMAND,
+    FIND_COMMAND,
+    SERVE_COMMAND,
+    INDEX_COMMAND,
+    QUERY_COMMAND,
+    CONFIG_COMMAND,
+    DOCTOR_COMMAND,
+];
+const DEFAULT_SOCKET_NAME: &str = "mini-ls-index.sock";
 
-#[derive(PartialEq, Eq)]
+/// GNU ls flags mapped onto their mini-ls equivalents. Sizes are always human readable, so
+/// those flags are accepted and dropped.
+const GNU_ALIASES: [(&str, &[&str]); 5] = [
+    ("-h", &[]),
+    ("--human-readable", &[]),
+    ("-t", &["--sort", "modified"]),
+    ("-S", &["--sort", "size"]),
+    ("-v", &["--sort", "version"]),
+];
+
+#[derive(PartialEq, Eq, Clone, Copy)]
 enum AllowedFlags {
     F,
     L,
+    GridDown,
+    GridAcross,
+    Recursive,
+    Largest,
+    Oldest,
+    Newest,
+    ByExtension,
+    Histogram,
+    Verify,
+    Checksums,
+    CheckCollisions,
+    Summary,
+    Octal,
+    Audit,
+    Mount,
+    ProjectId,
+    Compression,
+    SharedExtents,
+    Access,
+    Json,
+    Ndjson,
+    ExportParquet,
+    ExportArrow,
+    GroupDirs,
+    Port,
+    Bind,
+    Socket,
+    Locale,
+    Owner,
+    FullName,
+    TwoLine,
+    Dired,
+    RepeatHeader,
+    Csv,
+    CsvDelimiter,
+    CsvQuoting,
+    NoCsvHeader,
+    Tsv,
+    Html,
+    Separator,
+    Crlf,
+    Encoding,
+    Mkdir,
+    Force,
+    DumpConfig,
+    Version,
+    Sort,
+    Compat,
+    Profile,
+    PathStyle,
+    Pick,
+    Print0,
+    PageSize,
+    Cursor,
+    HighlightOlderThan,
+    HighlightLargerThan,
+    InodeOrder,
+    Reverse,
+    All,
+    Dereference,
+    Glob,
+    Exclude,
+    Match,
+    Files,
+    Dirs,
+    GitIgnore,
+    Watch,
+    Follow,
+    NoPager,
+    Tree,
+    Depth,
+    Color,
+    SingleColumn,
+    Help,
+    Completions,
+    Icons,
+    Width,
+    StrictUtf8,
+    RelativeTime,
+    TimeFormat,
+    Format,
 }
 
-impl AllowedFlags {
-    fn requires_option(switch: &AllowedFlags) -> bool {
-        matches!(switch, AllowedFlags::F)
-    }
+#[derive(PartialEq, Eq, Clone, Copy)]
+enum Arity {
+    Switch,
+    Value,
 }
 
+/// Every flag with the number of values it takes, the parser works out lookahead, `=value` and
+/// values glued onto a short flag block from the arity alone.
+const SHORT_FLAGS: [(&str, AllowedFlags, Arity); 11] = [
+    (F_FLAG, AllowedFlags::F, Arity::Value),
+    (L_FLAG, AllowedFlags::L, Arity::Switch),
+    (GRID_DOWN_FLAG, AllowedFlags::GridDown, Arity::Switch),
+    (GRID_ACROSS_FLAG, AllowedFlags::GridAcross, Arity::Switch),
+    (R_FLAG, AllowedFlags::Recursive, Arity::Switch),
+    (S_FLAG, AllowedFlags::Sort, Arity::Value),
+    (LOWER_R_FLAG, AllowedFlags::Reverse, Arity::Switch),
+    (A_FLAG, AllowedFlags::All, Arity::Switch),
+    (UPPER_L_FLAG, AllowedFlags::Dereference, Arity::Switch),
+    (ONE_FLAG, AllowedFlags::SingleColumn, Arity::Switch),
+    (ZERO_FLAG, AllowedFlags::Print0, Arity::Switch),
+];
+const LONG_FLAGS: [(&str, AllowedFlags, Arity); 83] = [
+    (EXTENDED_FLAG, AllowedFlags::L, Arity::Switch),
+    (FILE_FLAG, AllowedFlags::F, Arity::Value),
+    (GRID_FLAG, AllowedFlags::GridDown, Arity::Switch),
+    (
+        GRID_ACROSS_LONG_FLAG,
+        AllowedFlags::GridAcross,
+        Arity::Switch,
+    ),
+    (RECURSIVE_FLAG, AllowedFlags::Recursive, Arity::Switch),
+    (LARGEST_FLAG, AllowedFlags::Largest, Arity::Value),
+    (OLDEST_FLAG, AllowedFlags::Oldest, Arity::Value),
+    (NEWEST_FLAG, AllowedFlags::Newest, Arity::Value),
+    (BY_EXTENSION_FLAG, AllowedFlags::ByExtension, Arity::Switch),
+    (HISTOGRAM_FLAG, AllowedFlags::Histogram, Arity::Switch),
+    (VERIFY_FLAG, AllowedFlags::Verify, Arity::Value),
+    (CHECKSUMS_FLAG, AllowedFlags::Checksums, Arity::Switch),
+    (
+        CHECK_COLLISIONS_FLAG,
+        AllowedFlags::CheckCollisions,
+        Arity::Switch,
+    ),
+    (SUMMARY_FLAG, AllowedFlags::Summary, Arity::Switch),
+    (OCTAL_FLAG, AllowedFlags::Octal, Arity::Switch),
+    (AUDIT_FLAG, AllowedFlags::Audit, Arity::Switch),
+    (MOUNT_FLAG, AllowedFlags::Mount, Arity::Switch),
+    (PROJECT_ID_FLAG, AllowedFlags::ProjectId, Arity::Switch),
+    (COMPRESSION_FLAG, AllowedFlags::Compression, Arity::Switch),
+    (
+        SHARED_EXTENTS_FLAG,
+        AllowedFlags::SharedExtents,
+        Arity::Switch,
+    ),
+    (ACCESS_FLAG, AllowedFlags::Access, Arity::Switch),
+    (JSON_FLAG, AllowedFlags::Json, Arity::Switch),
+    (NDJSON_FLAG, AllowedFlags::Ndjson, Arity::Switch),
+    (
+        EXPORT_PARQUET_FLAG,
+        AllowedFlags::ExportParquet,
+        Arity::Value,
+    ),
+    (EXPORT_ARROW_FLAG, AllowedFlags::ExportArrow, Arity::Value),
+    (GROUP_DIRS_FLAG, AllowedFlags::GroupDirs, Arity::Value),
+    (PORT_FLAG, AllowedFlags::Port, Arity::Value),
+    (BIND_FLAG, AllowedFlags::Bind, Arity::Value),
+    (SOCKET_FLAG, AllowedFlags::Socket, Arity::Value),
+    (LOCALE_FLAG, AllowedFlags::Locale, Arity::Value),
+    (OWNER_FLAG, AllowedFlags::Owner, Arity::Switch),
+    (FULL_NAME_FLAG, AllowedFlags::FullName, Arity::Switch),
+    (TWO_LINE_FLAG, AllowedFlags::TwoLine, Arity::Switch),
+    (DIRED_FLAG, AllowedFlags::Dired, Arity::Switch),
+    (REPEAT_HEADER_FLAG, AllowedFlags::RepeatHeader, Arity::Value),
+    (CSV_FLAG, AllowedFlags::Csv, Arity::Switch),
+    (CSV_DELIMITER_FLAG, AllowedFlags::CsvDelimiter, Arity::Value),
+    (CSV_QUOTING_FLAG, AllowedFlags::CsvQuoting, Arity::Value),
+    (NO_CSV_HEADER_FLAG, AllowedFlags::NoCsvHeader, Arity::Switch),
+    (TSV_FLAG, AllowedFlags::Tsv, Arity::Switch),
+    (HTML_FLAG, AllowedFlags::Html, Arity::Switch),
+    (SEPARATOR_FLAG, AllowedFlags::Separator, Arity::Value),
+    (CRLF_FLAG, AllowedFlags::Crlf, Arity::Switch),
+    (ENCODING_FLAG, AllowedFlags::Encoding, Arity::Value),
+    (MKDIR_FLAG, AllowedFlags::Mkdir, Arity::Switch),
+    (FORCE_FLAG, AllowedFlags::Force, Arity::Switch),
+    (DUMP_CONFIG_FLAG, AllowedFlags::DumpConfig, Arity::Switch),
+    (VERSION_FLAG, AllowedFlags::Version, Arity::Switch),
+    (SORT_FLAG, AllowedFlags::Sort, Arity::Value),
+    (COMPAT_FLAG, AllowedFlags::Compat, Arity::Value),
+    (PROFILE_FLAG, AllowedFlags::Profile, Arity::Value),
+    (PATH_STYLE_FLAG, AllowedFlags::PathStyle, Arity::Value),
+    (PICK_FLAG, AllowedFlags::Pick, Arity::Switch),
+    (PRINT0_FLAG, AllowedFlags::Print0, Arity::Switch),
+    (PAGE_SIZE_FLAG, AllowedFlags::PageSize, Arity::Value),
+    (CURSOR_FLAG, AllowedFlags::Cursor, Arity::Value),
+    (
+        HIGHLIGHT_OLDER_THAN_FLAG,
+        AllowedFlags::HighlightOlderThan,
+        Arity::Value,
+    ),
+    (
+        HIGHLIGHT_LARGER_THAN_FLAG,
+        AllowedFlags::HighlightLargerThan,
+        Arity::Value,
+    ),
+    (INODE_ORDER_FLAG, AllowedFlags::InodeOrder, Arity::Switch),
+    (REVERSE_FLAG, AllowedFlags::Reverse, Arity::Switch),
+    (ALL_FLAG, AllowedFlags::All, Arity::Switch),
+    (DEREFERENCE_FLAG, AllowedFlags::Dereference, Arity::Switch),
+    (GLOB_FLAG, AllowedFlags::Glob, Arity::Value),
+    (EXCLUDE_FLAG, AllowedFlags::Exclude, Arity::Value),
+    (MATCH_FLAG, AllowedFlags::Match, Arity::Value),
+    (FILES_FLAG, AllowedFlags::Files, Arity::Switch),
+    (DIRS_FLAG, AllowedFlags::Dirs, Arity::Switch),
+    (GIT_IGNORE_FLAG, AllowedFlags::GitIgnore, Arity::Switch),
+    (WATCH_FLAG, AllowedFlags::Watch, Arity::Value),
+    (FOLLOW_FLAG, AllowedFlags::Follow, Arity::Switch),
+    (NO_PAGER_FLAG, AllowedFlags::NoPager, Arity::Switch),
+    (TREE_FLAG, AllowedFlags::Tree, Arity::Switch),
+    (DEPTH_FLAG, AllowedFlags::Depth, Arity::Value),
+    (COLOR_FLAG, AllowedFlags::Color, Arity::Value),
+    (
+        SINGLE_COLUMN_FLAG,
+        AllowedFlags::SingleColumn,
+        Arity::Switch,
+    ),
+    (HELP_FLAG, AllowedFlags::Help, Arity::Switch),
+    (COMPLETIONS_FLAG, AllowedFlags::Completions, Arity::Value),
+    (ICONS_FLAG, AllowedFlags::Icons, Arity::Value),
+    (WIDTH_FLAG, AllowedFlags::Width, Arity::Value),
+    (STRICT_UTF8_FLAG, AllowedFlags::StrictUtf8, Arity::Switch),
+    (
+        RELATIVE_TIME_FLAG,
+        AllowedFlags::RelativeTime,
+        Arity::Switch,
+    ),
+    (TIME_FORMAT_FLAG, AllowedFlags::TimeFormat, Arity::Value),
+    (FORMAT_FLAG, AllowedFlags::Format, Arity::Value),
+];
+
 enum Argument {
     Flag {
         switch: AllowedFlags,
@@ -29,12 +390,96 @@ enum Argument {
     Option {
         text: String,
     },
+    Negated {
+        switch: AllowedFlags,
+    },
 }
 
 #[derive(Debug, Clone)]
 pub enum ArgParsingError {
     MissingFileOption,
-    UnexpectedArgument { argument: String },
+    UnexpectedArgument {
+        argument: String,
+    },
+    MissingFlagOption {
+        flag: String,
+    },
+    InvalidCount {
+        flag: String,
+        value: String,
+    },
+    ConflictingReports,
+    ConflictingFormats,
+    MissingSubcommandArgument {
+        subcommand: String,
+        argument: String,
+    },
+    InvalidSubcommandArgument {
+        subcommand: String,
+        value: String,
+        choices: Vec<String>,
+    },
+    InvalidPattern {
+        pattern: String,
+    },
+    InvalidRegex {
+        pattern: String,
+        reason: String,
+    },
+    InvalidChoice {
+        flag: String,
+        value: String,
+        choices: Vec<String>,
+    },
+    InvalidPort {
+        value: String,
+    },
+    InvalidAddress {
+        value: String,
+    },
+    FlagRequiresSubcommand {
+        flag: String,
+        subcommand: String,
+    },
+    UnsupportedLocale {
+        locale: String,
+    },
+    InvalidTimeFormat {
+        format: String,
+    },
+    InvalidTemplate {
+        template: String,
+        reason: String,
+    },
+    InvalidConfigFile {
+        path: String,
+        reason: String,
+    },
+    FlagRequiresFlag {
+        flag: String,
+        required: String,
+    },
+    MissingOutputDirectory {
+        path: String,
+    },
+    OutputFileExists {
+        path: String,
+    },
+    InvalidCursor {
+        cursor: String,
+    },
+    InvalidThreshold {
+        flag: String,
+        value: String,
+        example: String,
+    },
+    UnexpectedFlagValue {
+        flag: String,
+    },
+    InvalidSetting {
+        origin: String,
+        reason: String,
+    },
 }
 
 impl fmt::Display for ArgParsingError {
@@ -44,26 +489,424 @@ impl fmt::Display for ArgParsingError {
             ArgParsingError::UnexpectedArgument { argument } => {
                 write!(f, "unexpected argument provided of {}", argument)
             }
+            ArgParsingError::MissingFlagOption { flag } => {
+                write!(f, "missing value for --{} flag", flag)
+            }
+            ArgParsingError::InvalidCount { flag, value } => {
+                write!(
+                    f,
+                    "--{} expects a positive whole number but got {}",
+                    flag, value
+                )
+            }
+            ArgParsingError::ConflictingReports => {
+                write!(f, "only one report mode can be used at once")
+            }
+            ArgParsingError::ConflictingFormats => {
+                write!(f, "only one output format can be used at once")
+            }
+            ArgParsingError::MissingSubcommandArgument {
+                subcommand,
+                argument,
+            } => write!(f, "the {} subcommand requires a {}", subcommand, argument),
+            ArgParsingError::InvalidSubcommandArgument {
+                subcommand,
+                value,
+                choices,
+            } => write!(
+                f,
+                "the {} subcommand expects one of {} but got {}",
+                subcommand,
+                choices.join(", "),
+                value
+            ),
+            ArgParsingError::InvalidPattern { pattern } => {
+                write!(f, "{} is not a valid glob pattern", pattern)
+            }
+            ArgParsingError::InvalidRegex { pattern, reason } => {
+                write!(
+                    f,
+                    "{} is not a valid regular expression: {}",
+                    pattern, reason
+                )
+            }
+            ArgParsingError::InvalidChoice {
+                flag,
+                value,
+                choices,
+            } => write!(
+                f,
+                "--{} expects one of {} but got {}",
+                flag,
+                choices.join(", "),
+                value
+            ),
+            ArgParsingError::InvalidPort { value } => write!(
+                f,
+                "--{} expects a port between 1 and 65535 but got {}",
+                PORT_FLAG, value
+            ),
+            ArgParsingError::InvalidAddress { value } => write!(
+                f,
+                "--{} expects an IP address such as 127.0.0.1 or 0.0.0.0 but got {}",
+                BIND_FLAG, value
+            ),
+            ArgParsingError::InvalidConfigFile { path, reason } => {
+                write!(f, "the config file {} could not be used: {}", path, reason)
+            }
+            ArgParsingError::UnsupportedLocale { locale } => {
+                write!(f, "{} is not a supported locale", locale)
+            }
+            ArgParsingError::InvalidTimeFormat { format } => {
+                write!(f, "{} is not a valid strftime format", format)
+            }
+            ArgParsingError::InvalidTemplate { template, reason } => {
+                write!(
+                    f,
+                    "--{} {} cannot be used: {}",
+                    FORMAT_FLAG, template, reason
+                )
+            }
+            ArgParsingError::MissingOutputDirectory { path } => write!(
+                f,
+                "the directory {} for the -F file does not exist, pass --{} to create it",
+                path, MKDIR_FLAG
+            ),
+            ArgParsingError::OutputFileExists { path } => write!(
+                f,
+                "{} already exists, pass --{} to overwrite it",
+                path, FORCE_FLAG
+            ),
+            ArgParsingError::InvalidCursor { cursor } => write!(
+                f,
+                "{} is not a cursor returned by an earlier page of this listing",
+                cursor
+            ),
+            ArgParsingError::InvalidThreshold {
+                flag,
+                value,
+                example,
+            } => write!(
+                f,
+                "--{} expects a value such as {} but got {}",
+                flag, example, value
+            ),
+            ArgParsingError::UnexpectedFlagValue { flag } => {
+                write!(f, "--{} does not take a value", flag)
+            }
+            ArgParsingError::InvalidSetting { origin, reason } => {
+                write!(f, "invalid setting in {}: {}", origin, reason)
+            }
+            ArgParsingError::FlagRequiresFlag { flag, required } => {
+                write!(
+                    f,
+                    "--{} can only be used together with --{}",
+                    flag, required
+                )
+            }
+            ArgParsingError::FlagRequiresSubcommand { flag, subcommand } => {
+                write!(
+                    f,
+                    "--{} can only be used with the {} subcommand",
+                    flag, subcommand
+                )
+            }
         }
     }
 }
 
+#[derive(Default, Serialize)]
 pub struct Config {
     pub target: String,
     pub to_file: bool,
     pub target_file: String,
     pub(crate) extended_attributes: bool,
+    pub(crate) report: Option<Report>,
+    pub(crate) type_summary: bool,
+    pub(crate) find_pattern: Option<NamePattern>,
+    pub(crate) optional_columns: Vec<OptionalColumn>,
+    pub(crate) machine_format: Option<MachineFormat>,
+    pub(crate) export: Option<(ExportFormat, PathBuf)>,
+    pub(crate) dir_grouping: DirGrouping,
+    pub(crate) serve_port: Option<u16>,
+    /// The address `serve` listens on, loopback unless `--bind` says otherwise.
+    pub(crate) serve_address: Option<IpAddr>,
+    pub(crate) index_mode: Option<IndexMode>,
+    pub(crate) locale: Locale,
+    pub(crate) column_limits: ColumnLimits,
+    pub(crate) hooks: Hooks,
+    pub(crate) two_line: bool,
+    pub(crate) grid: Option<GridOrder>,
+    pub(crate) dired: bool,
+    pub(crate) repeat_header: Option<usize>,
+    pub(crate) column_separator: Option<String>,
+    pub(crate) crlf: bool,
+    pub(crate) encoding: OutputEncoding,
+    pub(crate) create_output_dirs: bool,
+    #[serde(skip)]
+    pub(crate) dump_config: bool,
+    #[serde(skip)]
+    pub(crate) config_action: Option<ConfigAction>,
+    #[serde(skip)]
+    pub(crate) force: bool,
+    #[serde(skip)]
+    pub(crate) doctor: bool,
+    #[serde(skip)]
+    pub(crate) show_version: bool,
+    #[serde(skip)]
+    pub(crate) show_help: bool,
+    #[serde(skip)]
+    pub(crate) completions: Option<Shell>,
+    pub(crate) sort: Option<SortKey>,
+    /// Flip the sort order, sorting by name when no key was given.
+    pub(crate) reverse: bool,
+    pub(crate) compat: Option<CompatStyle>,
+    pub(crate) path_style: Option<PathStyle>,
+    #[serde(skip)]
+    pub(crate) pick: bool,
+    pub(crate) print0: bool,
+    pub(crate) pagination: Option<Pagination>,
+    pub(crate) highlights: Highlights,
+    /// Stat entries in inode number order before sorting them for display.
+    pub(crate) inode_order: bool,
+    pub(crate) recursive: bool,
+    /// List entries whose names start with `.`, and on Windows those with the hidden attribute.
+    pub(crate) all: bool,
+    /// Show what symlinks point at rather than the links themselves.
+    pub(crate) dereference: bool,
+    /// Only entries whose names match are listed, from `--glob` or a pattern given as the target.
+    pub(crate) glob: Option<NamePattern>,
+    /// Entries whose names match any of these are left out, and never descended into.
+    pub(crate) exclude: Vec<NamePattern>,
+    /// Only entries whose names match the `--match` regular expression are listed.
+    pub(crate) name_match: Option<NamePattern>,
+    /// `--files` or `--dirs`, whichever came last.
+    pub(crate) only: Option<KindFilter>,
+    /// Skip entries git ignores, along with the `.git` directory.
+    pub(crate) git_ignore: bool,
+    /// Seconds between listings, redrawn in place on a terminal, when set.
+    pub(crate) watch: Option<usize>,
+    /// Redraw on filesystem events rather than on a timer, takes precedence over `watch`.
+    pub(crate) follow: bool,
+    /// Print straight to the terminal even when the listing is taller than it.
+    pub(crate) no_pager: bool,
+    pub(crate) tree: bool,
+    /// How many levels below the target `--tree` and `-R` descend, all of them when unset. At 1
+    /// only the target's own entries are listed.
+    pub(crate) depth: Option<usize>,
+    pub(crate) color: ColorMode,
+    pub(crate) icons: IconStyle,
+    /// Columns to lay the listing out in instead of the terminal width.
+    pub(crate) width: Option<usize>,
+    /// Fail on names that are not UTF-8 rather than showing them lossily with a marker.
+    pub(crate) strict_utf8: bool,
+    /// Dates as the time since, such as `3 minutes ago`, rather than in the locale's format.
+    pub(crate) relative_time: bool,
+    /// A strftime format for the date columns in place of the locale's.
+    pub(crate) time_format: Option<String>,
+    /// Each entry rendered through a `--format` pattern instead of the table.
+    pub(crate) template: Option<Template>,
+    /// Bare names one per line for piping, ignored by `-l` as with `ls -l1`.
+    pub(crate) single_column: bool,
+    /// The layer that decided each flag, anything missing kept its built-in default.
+    pub(crate) sources: BTreeMap<String, Source>,
+}
+
+/// Which kind of entry `--files` and `--dirs` keep. Symlinks count as files, as they do for `-l`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum KindFilter {
+    Files,
+    Directories,
+}
+
+/// Where a setting came from, lowest precedence first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum Source {
+    ConfigFile,
+    Profile,
+    Environment,
+    CommandLine,
+}
+
+/// `config init` writes a commented default file, `config validate` checks the existing one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigAction {
+    Init,
+    Validate,
+}
+
+/// Either runs the long lived index daemon or sends it a single query.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum IndexMode {
+    Daemon { socket: PathBuf },
+    Query { socket: PathBuf, request: String },
+}
+
+enum Subcommand {
+    Stats,
+    Find,
+    Serve,
+    Index,
+    Query { request: String },
+    Config { action: ConfigAction },
+    Doctor,
 }
 
 impl Config {
+    /// Reads the config file from the platform's config directory and the environment of the
+    /// process on top of `args`.
     pub fn build(args: Vec<String>) -> Result<Config, ArgParsingError> {
-        let flags: Result<Vec<Argument>, ArgParsingError> = parse_flags(&args);
-        let flags = match flags {
-            Ok(flags) => flags,
-            Err(error) => return Err(error),
+        let config_path = ConfigFile::default_path();
+        Config::build_with(args, config_path.as_deref(), |name| {
+            std::env::var(name).ok()
+        })
+    }
+
+    /// As `build` with the config file at `config_path`, none when `None`, and environment
+    /// variables looked up through `variable`, so the result does not depend on the machine.
+    pub fn build_with(
+        args: Vec<String>,
+        config_path: Option<&Path>,
+        variable: impl Fn(&str) -> Option<String>,
+    ) -> Result<Config, ArgParsingError> {
+        let (subcommand, args) = split_subcommand(args)?;
+        let args = expand_gnu_aliases(args);
+        let doctor = matches!(subcommand, Some(Subcommand::Doctor));
+        let config_action = match subcommand {
+            Some(Subcommand::Config { action }) => Some(action),
+            _ => None,
+        };
+        let mut command_line = parse_flags(&args)?;
+        // help has to work whatever else is wrong, a broken config file included
+        if is_flag_present(&command_line, AllowedFlags::Help) {
+            return Ok(Config {
+                show_help: true,
+                ..Default::default()
+            });
+        }
+        if let Some(shell) = parse_shell(&command_line)? {
+            return Ok(Config {
+                completions: Some(shell),
+                ..Default::default()
+            });
+        }
+        let find_pattern = match subcommand {
+            Some(Subcommand::Find) => Some(take_find_pattern(&mut command_line)?),
+            _ => None,
+        };
+        // a broken file must not stop `config validate` from explaining what is wrong with it
+        let config_file = if config_action.is_some() {
+            ConfigFile::default()
+        } else {
+            load_config_file(config_path)?
         };
+        let (flags, sources) = resolve_layers(&config_file, command_line, &variable)?;
         let (to_file, target_file) = parse_file_output_args(&flags)?;
+        let create_output_dirs = is_flag_present(&flags, AllowedFlags::Mkdir);
+        let force = is_flag_present(&flags, AllowedFlags::Force);
+        if to_file && target_file != STDOUT_TARGET {
+            if !create_output_dirs {
+                check_output_directory(&target_file)?;
+            }
+            if !force && Path::new(&target_file).exists() {
+                return Err(ArgParsingError::OutputFileExists { path: target_file });
+            }
+        }
         let extended_attributes = parse_extended_attribute_flag(&flags);
+        let report = match (&subcommand, parse_report_flags(&flags)?) {
+            (Some(Subcommand::Stats), Some(_)) => return Err(ArgParsingError::ConflictingReports),
+            (Some(Subcommand::Stats), None) => Some(Report::Stats),
+            (_, report) => report,
+        };
+        let serving = matches!(subcommand, Some(Subcommand::Serve));
+        let socket = match parse_flag_value(&flags, AllowedFlags::Socket, SOCKET_FLAG)? {
+            Some(socket) => PathBuf::from(convert_from_short_unix_home(&socket)?),
+            None => default_socket_path(&variable),
+        };
+        let index_mode = match &subcommand {
+            Some(Subcommand::Index) => Some(IndexMode::Daemon { socket }),
+            Some(Subcommand::Query { request }) => Some(IndexMode::Query {
+                socket,
+                request: request.to_string(),
+            }),
+            _ if is_flag_present(&flags, AllowedFlags::Socket) => {
+                return Err(ArgParsingError::FlagRequiresSubcommand {
+                    flag: SOCKET_FLAG.to_string(),
+                    subcommand: format!("{} or {}", INDEX_COMMAND, QUERY_COMMAND),
+                })
+            }
+            _ => None,
+        };
+        let type_summary = is_flag_present(&flags, AllowedFlags::Summary);
+        let optional_columns = parse_optional_columns(&flags);
+        let machine_format = parse_machine_format(&flags)?;
+        let export = parse_export_flags(&flags)?;
+        let dir_grouping = parse_dir_grouping(&flags)?;
+        let port = parse_port(&flags)?;
+        let bind = parse_bind_address(&flags)?;
+        let locale = match parse_flag_value(&flags, AllowedFlags::Locale, LOCALE_FLAG)? {
+            Some(name) => {
+                Locale::parse(&name).ok_or(ArgParsingError::UnsupportedLocale { locale: name })?
+            }
+            None => Locale::from_variables(&variable),
+        };
+        let serve_port = match (serving, port) {
+            (true, port) => Some(port.unwrap_or(DEFAULT_PORT)),
+            (false, Some(_)) => {
+                return Err(ArgParsingError::FlagRequiresSubcommand {
+                    flag: PORT_FLAG.to_string(),
+                    subcommand: SERVE_COMMAND.to_string(),
+                })
+            }
+            (false, None) => None,
+        };
+        let serve_address = match (serving, bind) {
+            (true, bind) => Some(bind.unwrap_or(DEFAULT_ADDRESS)),
+            (false, Some(_)) => {
+                return Err(ArgParsingError::FlagRequiresSubcommand {
+                    flag: BIND_FLAG.to_string(),
+                    subcommand: SERVE_COMMAND.to_string(),
+                })
+            }
+            (false, None) => None,
+        };
+        let dired = is_flag_present(&flags, AllowedFlags::Dired);
+        let compat = parse_compat_style(&flags)?;
+        let pick = is_flag_present(&flags, AllowedFlags::Pick);
+        let print0 = is_flag_present(&flags, AllowedFlags::Print0);
+        let tree = is_flag_present(&flags, AllowedFlags::Tree);
+        let depth = parse_count_flag(&flags, AllowedFlags::Depth, DEPTH_FLAG)?;
+        if depth.is_some() && !tree && !is_flag_present(&flags, AllowedFlags::Recursive) {
+            return Err(ArgParsingError::FlagRequiresFlag {
+                flag: DEPTH_FLAG.to_string(),
+                required: format!("{} or --{}", TREE_FLAG, RECURSIVE_FLAG),
+            });
+        }
+        let template = match parse_flag_value(&flags, AllowedFlags::Format, FORMAT_FLAG)? {
+            Some(template) => Some(
+                Template::parse(&template)
+                    .map_err(|reason| ArgParsingError::InvalidTemplate { template, reason })?,
+            ),
+            None => None,
+        };
+        let format_count = [
+            print0 && !pick,
+            export.is_some(),
+            template.is_some(),
+            machine_format.is_some(),
+            dired,
+            compat.is_some(),
+            pick,
+        ]
+        .iter()
+        .filter(|requested| **requested)
+        .count();
+        if format_count > 1 {
+            return Err(ArgParsingError::ConflictingFormats);
+        }
         let target = flags
             .iter()
             .find(|flag| matches!(flag, Argument::TargetDir { .. }));
@@ -71,132 +914,437 @@ impl Config {
             Some(Argument::TargetDir { target }) => target.to_string(),
             _ => "./".to_string(),
         };
+        let (target, glob) = match parse_flag_value(&flags, AllowedFlags::Glob, GLOB_FLAG)? {
+            Some(pattern) => (target, Some(parse_glob(pattern)?)),
+            None => match split_glob_target(&target) {
+                Some((directory, pattern)) => (directory, Some(parse_glob(pattern)?)),
+                None => (target, None),
+            },
+        };
         Ok(Config {
             target,
             to_file,
             target_file,
             extended_attributes,
+            report,
+            type_summary,
+            find_pattern,
+            optional_columns,
+            machine_format,
+            export,
+            dir_grouping,
+            serve_port,
+            serve_address,
+            index_mode,
+            locale,
+            column_limits: config_file.columns,
+            hooks: config_file.hooks,
+            two_line: is_flag_present(&flags, AllowedFlags::TwoLine),
+            grid: parse_grid_order(&flags),
+            only: parse_kind_filter(&flags),
+            git_ignore: is_flag_present(&flags, AllowedFlags::GitIgnore),
+            watch: parse_count_flag(&flags, AllowedFlags::Watch, WATCH_FLAG)?,
+            follow: is_flag_present(&flags, AllowedFlags::Follow),
+            no_pager: is_flag_present(&flags, AllowedFlags::NoPager),
+            dired,
+            repeat_header: parse_count_flag(
+                &flags,
+                AllowedFlags::RepeatHeader,
+                REPEAT_HEADER_FLAG,
+            )?,
+            column_separator: parse_flag_value(&flags, AllowedFlags::Separator, SEPARATOR_FLAG)?,
+            crlf: is_flag_present(&flags, AllowedFlags::Crlf),
+            encoding: parse_encoding(&flags)?,
+            create_output_dirs,
+            dump_config: is_flag_present(&flags, AllowedFlags::DumpConfig),
+            config_action,
+            force,
+            doctor,
+            show_version: is_flag_present(&flags, AllowedFlags::Version),
+            show_help: false,
+            completions: None,
+            sort: parse_sort_key(&flags)?,
+            reverse: is_flag_present(&flags, AllowedFlags::Reverse),
+            compat,
+            path_style: parse_path_style(&flags)?,
+            pick,
+            print0,
+            pagination: parse_pagination(&flags, machine_format)?,
+            highlights: parse_highlights(&flags)?,
+            inode_order: is_flag_present(&flags, AllowedFlags::InodeOrder),
+            recursive: is_flag_present(&flags, AllowedFlags::Recursive),
+            all: is_flag_present(&flags, AllowedFlags::All),
+            dereference: is_flag_present(&flags, AllowedFlags::Dereference),
+            glob,
+            name_match: parse_flag_value(&flags, AllowedFlags::Match, MATCH_FLAG)?
+                .map(parse_regex)
+                .transpose()?,
+            exclude: parse_flag_values(&flags, AllowedFlags::Exclude, EXCLUDE_FLAG)?
+                .into_iter()
+                .map(parse_glob)
+                .collect::<Result<_, _>>()?,
+            tree,
+            depth,
+            color: parse_color_mode(&flags)?,
+            icons: parse_icon_style(&flags)?,
+            width: parse_count_flag(&flags, AllowedFlags::Width, WIDTH_FLAG)?,
+            strict_utf8: is_flag_present(&flags, AllowedFlags::StrictUtf8),
+            relative_time: is_flag_present(&flags, AllowedFlags::RelativeTime),
+            time_format: parse_time_format(&flags)?,
+            template,
+            single_column: is_flag_present(&flags, AllowedFlags::SingleColumn),
+            sources,
         })
     }
+
+    /// The resolved settings after the config file and flags have been merged, as TOML or as
+    /// JSON when `--json` is also given.
+    pub fn dump(&self) -> Result<String, String> {
+        match self.machine_format {
+            Some(MachineFormat::Json) => {
+                serde_json::to_string_pretty(self).map_err(|error| error.to_string())
+            }
+            _ => toml::to_string(self).map_err(|error| error.to_string()),
+        }
+    }
+}
+
+fn split_subcommand(
+    mut args: Vec<String>,
+) -> Result<(Option<Subcommand>, Vec<String>), ArgParsingError> {
+    // A command name given alone lists a local entry of that name as plain ls would, anything
+    // after it such as `stats .` runs the command and `-- stats` always lists.
+    if let [_, word] = args.as_slice() {
+        if SUBCOMMANDS.contains(&word.as_str()) && Path::new(word).exists() {
+            return Ok((None, args));
+        }
+    }
+    match args.get(1).map(|word| word.as_str()) {
+        Some(STATS_COMMAND) => {
+            args.remove(1);
+            Ok((Some(Subcommand::Stats), args))
+        }
+        Some(FIND_COMMAND) => {
+            args.remove(1);
+            Ok((Some(Subcommand::Find), args))
+        }
+        Some(SERVE_COMMAND) => {
+            args.remove(1);
+            Ok((Some(Subcommand::Serve), args))
+        }
+        Some(INDEX_COMMAND) => {
+            args.remove(1);
+            Ok((Some(Subcommand::Index), args))
+        }
+        Some(QUERY_COMMAND) => {
+            args.remove(1);
+            if args.len() < 2 {
+                return Err(ArgParsingError::MissingSubcommandArgument {
+                    subcommand: QUERY_COMMAND.to_string(),
+                    argument: String::from("request"),
+                });
+            }
+            let request = args.remove(1);
+            Ok((Some(Subcommand::Query { request }), args))
+        }
+        Some(DOCTOR_COMMAND) => {
+            args.remove(1);
+            Ok((Some(Subcommand::Doctor), args))
+        }
+        Some(CONFIG_COMMAND) => {
+            let action = match args.get(2).map(|word| word.as_str()) {
+                Some("init") => ConfigAction::Init,
+                Some("validate") => ConfigAction::Validate,
+                // `ls config` in a project with a config directory keeps listing it.
+                _ if Path::new(CONFIG_COMMAND).exists() => return Ok((None, args)),
+                Some(other) => {
+                    return Err(ArgParsingError::InvalidSubcommandArgument {
+                        subcommand: CONFIG_COMMAND.to_string(),
+                        value: other.to_string(),
+                        choices: ["init", "validate"].map(String::from).to_vec(),
+                    })
+                }
+                None => {
+                    return Err(ArgParsingError::MissingSubcommandArgument {
+                        subcommand: CONFIG_COMMAND.to_string(),
+                        argument: String::from("subcommand of init or validate"),
+                    })
+                }
+            };
+            args.drain(1..3);
+            Ok((Some(Subcommand::Config { action }), args))
+        }
+        _ => Ok((None, args)),
+    }
+}
+
+/// The first positional word after `find` is its pattern wherever the flags sit, so flags that
+/// take a value keep it and `find -l '*.rs' src` reads the same as `find '*.rs' -l src`.
+fn take_find_pattern(command_line: &mut Vec<Argument>) -> Result<NamePattern, ArgParsingError> {
+    let found = command_line
+        .iter()
+        .enumerate()
+        .find_map(|(i, argument)| match argument {
+            Argument::TargetDir { target } => Some((i, target.to_string())),
+            _ => None,
+        });
+    let Some((position, pattern)) = found else {
+        return Err(ArgParsingError::MissingSubcommandArgument {
+            subcommand: FIND_COMMAND.to_string(),
+            argument: String::from("pattern"),
+        });
+    };
+    command_line.remove(position);
+    NamePattern::parse(&pattern).map_err(|_| ArgParsingError::InvalidPattern { pattern })
+}
+
+/// Rewrites GNU ls flags, including blocks such as `-lat`, into mini-ls flags. Blocks holding
+/// anything other than known letters are left for the normal parser, e.g. `-lFlog.txt`, and so is
+/// the value of a flag that takes one, so `--format -v` keeps its template.
+fn expand_gnu_aliases(args: Vec<String>) -> Vec<String> {
+    let alias = |flag: &str| {
+        GNU_ALIASES
+            .iter()
+            .find(|(gnu_flag, _)| *gnu_flag == flag)
+            .map(|(_, expansion)| expansion.iter().map(|word| word.to_string()).collect())
+    };
+    let native = [
+        L_FLAG,
+        GRID_DOWN_FLAG,
+        GRID_ACROSS_FLAG,
+        R_FLAG,
+        LOWER_R_FLAG,
+        A_FLAG,
+        UPPER_L_FLAG,
+        ONE_FLAG,
+    ];
+    let mut expanded = Vec::new();
+    let mut awaiting_value = false;
+    let mut args = args.into_iter().enumerate();
+    for (i, arg) in args.by_ref() {
+        if std::mem::replace(&mut awaiting_value, i > 0 && awaits_value(&arg)) {
+            expanded.push(arg);
+            continue;
+        }
+        if arg == END_OF_OPTIONS {
+            expanded.push(arg);
+            break;
+        }
+        if i == 0 || !arg.starts_with('-') || arg.starts_with("--") || arg.len() < 2 {
+            expanded.extend(alias(&arg).unwrap_or_else(|| vec![arg]));
+            continue;
+        }
+        let letters: Vec<String> = arg[1..].chars().map(|letter| letter.to_string()).collect();
+        let all_known = letters.iter().all(|letter| {
+            native.contains(&letter.as_str()) || alias(&format!("-{}", letter)).is_some()
+        });
+        if !all_known {
+            expanded.push(arg);
+            continue;
+        }
+        for letter in letters {
+            let flag = format!("-{}", letter);
+            expanded.extend(alias(&flag).unwrap_or_else(|| vec![flag]));
+        }
+    }
+    expanded.extend(args.map(|(_, arg)| arg));
+    expanded
+}
+
+/// Whether `word` is a flag whose value is the next argument, such as `--format` or `-lF`.
+fn awaits_value(word: &str) -> bool {
+    let mut discovered_options = Vec::new();
+    let parsed = if word.starts_with("--") {
+        process_long_flag(word, 2, 0, &mut discovered_options)
+    } else if word.starts_with('-') && word.len() > 1 {
+        process_short_flags(word, 2, 0, &mut discovered_options)
+    } else {
+        return false;
+    };
+    parsed.is_ok() && !discovered_options.is_empty()
 }
 
+/// Everything after `--` is a target. Once a target has been seen, dash-prefixed arguments that
+/// are not known flags are taken as targets too rather than guessed at as flag blocks.
 fn parse_flags(args: &[String]) -> Result<Vec<Argument>, ArgParsingError> {
     let filtered_args: Vec<&String> = args.iter().skip(1).collect();
     let mut discovered_options = vec![];
+    let mut end_of_options = false;
+    let mut target_seen = false;
     let separated_args = filtered_args
         .iter()
         .enumerate()
-        .flat_map(|(i, arg)| match arg {
-            string if string.starts_with('-') && string.len() < 3 => {
-                process_single_flag(string, filtered_args.len(), i, &mut discovered_options)
-            }
-            string if string.starts_with('-') && string.len() >= 3 => {
-                extract_flags_from_block(string, &mut discovered_options, i, args.len())
+        .map(|(i, arg)| match arg {
+            target if end_of_options => Ok(vec![Argument::TargetDir {
+                target: target.to_string(),
+            }]),
+            string if *string == END_OF_OPTIONS && !discovered_options.contains(&i) => {
+                end_of_options = true;
+                Ok(vec![])
             }
+            // a flag's value is taken as is even when it starts with a dash, `-F -` or `--format -v`
             string if discovered_options.contains(&i) => Ok(vec![Argument::Option {
                 text: string.to_string(),
             }]),
-            target => Ok(vec![Argument::TargetDir {
-                target: (*target).to_string(),
-            }]),
+            string if string.starts_with('-') => {
+                let parsed = if string.starts_with("--") {
+                    process_long_flag(string, filtered_args.len(), i, &mut discovered_options)
+                } else {
+                    process_short_flags(string, filtered_args.len(), i, &mut discovered_options)
+                };
+                match parsed {
+                    Err(_) if target_seen => Ok(vec![Argument::TargetDir {
+                        target: string.to_string(),
+                    }]),
+                    parsed => parsed,
+                }
+            }
+            target => {
+                target_seen = true;
+                Ok(vec![Argument::TargetDir {
+                    target: (*target).to_string(),
+                }])
+            }
         })
+        .collect::<Result<Vec<Vec<Argument>>, ArgParsingError>>()?
+        .into_iter()
         .flatten()
         .collect();
     Ok(separated_args)
 }
 
-fn process_single_flag(
+fn process_long_flag(
     string: &str,
     arg_length: usize,
     index: usize,
     discovered_options: &mut Vec<usize>,
 ) -> Result<Vec<Argument>, ArgParsingError> {
-    let argument = extract_single_no_concat_switch(string)?;
-    if let Argument::Flag { switch, .. } = &argument {
-        if AllowedFlags::requires_option(switch) && index <= arg_length - 2 {
-            discovered_options.push(index + 1);
-        }
+    let flag_text = string
+        .strip_prefix("--")
+        .expect("string input missing required start chars");
+    let (flag_name, value) = match flag_text.split_once('=') {
+        Some((flag_name, value)) => (flag_name, Some(value.to_string())),
+        None => (flag_text, None),
+    };
+    let find_flag = |flag_name: &str| LONG_FLAGS.iter().find(|(name, ..)| *name == flag_name);
+    let Some((_, switch, arity)) = find_flag(flag_name) else {
+        let negated = flag_name.strip_prefix(NEGATION_PREFIX).and_then(find_flag);
+        return match negated {
+            Some(_) if value.is_some() => Err(ArgParsingError::UnexpectedFlagValue {
+                flag: flag_name.to_string(),
+            }),
+            Some((_, switch, _)) => Ok(vec![Argument::Negated { switch: *switch }]),
+            None => Err(ArgParsingError::UnexpectedArgument {
+                argument: flag_name.to_string(),
+            }),
+        };
+    };
+    if *arity == Arity::Switch && value.is_some() {
+        return Err(ArgParsingError::UnexpectedFlagValue {
+            flag: flag_name.to_string(),
+        });
     }
-    Ok::<Vec<Argument>, ArgParsingError>(vec![argument])
+    Ok(vec![flag_argument(
+        *switch,
+        *arity,
+        value,
+        index,
+        arg_length,
+        discovered_options,
+    )])
 }
 
-fn extract_single_no_concat_switch(string: &str) -> Result<Argument, ArgParsingError> {
-    let flag_char = string
+/// Short flags can share a block, `-lC`. The first one taking a value uses the rest of the block
+/// as that value, after an optional `=`, or the next argument when nothing is left.
+fn process_short_flags(
+    string: &str,
+    arg_length: usize,
+    index: usize,
+    discovered_options: &mut Vec<usize>,
+) -> Result<Vec<Argument>, ArgParsingError> {
+    let block = string
         .strip_prefix('-')
         .expect("string input missing required start char");
-    match flag_char {
-        F_FLAG => Ok(Argument::Flag {
-            switch: AllowedFlags::F,
-            flag_option_text: None,
-        }),
-        L_FLAG => Ok(Argument::Flag {
-            switch: AllowedFlags::L,
-            flag_option_text: None,
-        }),
-        argument => Err(ArgParsingError::UnexpectedArgument {
-            argument: argument.to_string(),
-        }),
+    let mut arguments = Vec::new();
+    for (i, flag_char) in block.char_indices() {
+        let flag_name = &block[i..i + flag_char.len_utf8()];
+        let Some((_, switch, arity)) = SHORT_FLAGS.iter().find(|(name, ..)| *name == flag_name)
+        else {
+            return Err(ArgParsingError::UnexpectedArgument {
+                argument: flag_name.to_string(),
+            });
+        };
+        let rest = &block[i + flag_name.len()..];
+        let rest = rest.strip_prefix('=').unwrap_or(rest);
+        let value = (*arity == Arity::Value && !rest.is_empty()).then(|| rest.to_string());
+        arguments.push(flag_argument(
+            *switch,
+            *arity,
+            value,
+            index,
+            arg_length,
+            discovered_options,
+        ));
+        if *arity == Arity::Value {
+            break;
+        }
     }
+    Ok(arguments)
 }
 
-fn extract_flags_from_block(
-    string: &str,
-    discovered_options: &mut Vec<usize>,
-    i: usize,
-    args_length: usize,
-) -> Result<Vec<Argument>, ArgParsingError> {
-    let (valid_flag_chars, flag_option_text) = split_flag_block(string);
-    Ok(valid_flag_chars
-        .iter()
-        .map(|flag_char| match flag_char {
-            flag if *flag == L_FLAG => Argument::Flag {
-                switch: AllowedFlags::L,
-                flag_option_text: None,
-            },
-            flag if *flag == F_FLAG => {
-                match flag_option_text {
-                    None if (i + 1) < args_length => {
-                        discovered_options.push(i + 1);
-                        Some(i + 1)
-                    }
-                    Some(_) => None,
-                    None => None,
-                };
-                Argument::Flag {
-                    switch: AllowedFlags::F,
-                    flag_option_text: flag_option_text.clone(),
-                }
+/// `--no-<flag>` removes every earlier occurrence of the flag along with any value that followed
+/// it, so whichever comes last on the command line wins.
+fn apply_negations(arguments: Vec<Argument>) -> Vec<Argument> {
+    let mut resolved: Vec<Argument> = Vec::new();
+    for argument in arguments {
+        let Argument::Negated { switch: negated } = argument else {
+            resolved.push(argument);
+            continue;
+        };
+        let mut awaiting_value = false;
+        resolved.retain(|earlier| match earlier {
+            Argument::Flag {
+                switch,
+                flag_option_text,
+            } if *switch == negated => {
+                awaiting_value = flag_option_text.is_none();
+                false
             }
-            _ => panic!(
-                "There is a missing match arm for all the arguments in the allowed_flags vector"
-            ),
-        })
-        .collect())
+            Argument::Option { .. } if awaiting_value => {
+                awaiting_value = false;
+                false
+            }
+            _ => {
+                awaiting_value = false;
+                true
+            }
+        });
+    }
+    resolved
 }
 
-fn split_flag_block(string: &str) -> (Vec<&str>, Option<String>) {
-    let allowed_flags = [F_FLAG, L_FLAG];
-    let flag_chars: Vec<&str> = string
-        .strip_prefix('-')
-        .expect("string - already checked for")
-        .split("")
-        .collect();
-    let valid_flag_chars: Vec<&str> = flag_chars
-        .into_iter()
-        .filter(|flag_char| allowed_flags.contains(flag_char))
-        .collect();
-    let valid_flag_block_length = valid_flag_chars.len();
-    let flag_option_text = if valid_flag_block_length == string.len() - 1 {
-        None
-    } else {
-        Some(string[valid_flag_block_length..].to_string())
-    };
-    (valid_flag_chars, flag_option_text)
+/// A flag needing a value it was not given inline takes the following argument.
+fn flag_argument(
+    switch: AllowedFlags,
+    arity: Arity,
+    value: Option<String>,
+    index: usize,
+    arg_length: usize,
+    discovered_options: &mut Vec<usize>,
+) -> Argument {
+    if arity == Arity::Value && value.is_none() && index + 1 < arg_length {
+        discovered_options.push(index + 1);
+    }
+    Argument::Flag {
+        switch,
+        flag_option_text: value,
+    }
 }
 
 fn parse_file_output_args(flags: &[Argument]) -> Result<(bool, String), ArgParsingError> {
     // typical input [Flag, Flag, Option, TargetDir]
-    for (i, arg) in flags.iter().enumerate() {
+    // searched from the end as the command line comes after the config file and environment
+    for (i, arg) in flags.iter().enumerate().rev() {
         if let Argument::Flag {
             switch: AllowedFlags::F,
             flag_option_text,
@@ -215,6 +1363,18 @@ fn parse_file_output_args(flags: &[Argument]) -> Result<(bool, String), ArgParsi
     Ok((false, "".to_string()))
 }
 
+/// Checked before the listing starts so a typo in the path does not waste a long scan.
+fn check_output_directory(target_file: &str) -> Result<(), ArgParsingError> {
+    match Path::new(target_file).parent() {
+        Some(parent) if !parent.as_os_str().is_empty() && !parent.is_dir() => {
+            Err(ArgParsingError::MissingOutputDirectory {
+                path: parent.display().to_string(),
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
 fn get_valid_file_path(
     flag_option_text: &Option<String>,
     i: usize,
@@ -271,156 +1431,2386 @@ fn parse_extended_attribute_flag(flags: &[Argument]) -> bool {
     })
 }
 
-#[cfg(test)]
-mod tests {
-    use super::Config;
-    use std::env::temp_dir;
-
-    #[test]
-    fn obtains_the_dir_from_args() {
-        let args = vec![String::from("./mini-ls"), String::from("~/dev")];
-        let config = Config::build(args).unwrap();
-        assert_eq!(config.target, "~/dev");
+fn parse_report_flags(flags: &[Argument]) -> Result<Option<Report>, ArgParsingError> {
+    let requested: Vec<Report> = [
+        parse_count_flag(flags, AllowedFlags::Largest, LARGEST_FLAG)?.map(Report::Largest),
+        parse_count_flag(flags, AllowedFlags::Oldest, OLDEST_FLAG)?.map(Report::Oldest),
+        parse_count_flag(flags, AllowedFlags::Newest, NEWEST_FLAG)?.map(Report::Newest),
+        is_flag_present(flags, AllowedFlags::ByExtension).then_some(Report::ByExtension),
+        is_flag_present(flags, AllowedFlags::Histogram).then_some(Report::Histogram),
+        parse_flag_value(flags, AllowedFlags::Verify, VERIFY_FLAG)?
+            .map(|manifest| convert_from_short_unix_home(&manifest))
+            .transpose()?
+            .map(|manifest| Report::Verify(PathBuf::from(manifest))),
+        is_flag_present(flags, AllowedFlags::Checksums).then_some(Report::Checksums),
+        is_flag_present(flags, AllowedFlags::CheckCollisions).then_some(Report::Collisions),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    if requested.len() > 1 {
+        return Err(ArgParsingError::ConflictingReports);
     }
+    Ok(requested.into_iter().next())
+}
 
-    #[test]
-    fn extracts_f_arg_to_config() {
-        let args = vec![
-            String::from("./mini-ls"),
-            String::from("-F"),
-            String::from("log.txt"),
-            String::from("~/dev"),
-        ];
-        let config = Config::build(args).unwrap();
-        assert!(config.to_file);
+fn parse_machine_format(flags: &[Argument]) -> Result<Option<MachineFormat>, ArgParsingError> {
+    let csv_dialect = parse_csv_dialect(flags)?;
+    let requested: Vec<MachineFormat> = [
+        (AllowedFlags::Json, MachineFormat::Json),
+        (AllowedFlags::Ndjson, MachineFormat::Ndjson),
+        (AllowedFlags::Csv, MachineFormat::Csv(csv_dialect)),
+        (AllowedFlags::Tsv, MachineFormat::Tsv),
+        (AllowedFlags::Html, MachineFormat::Html),
+    ]
+    .into_iter()
+    .filter(|(flag, _)| is_flag_present(flags, *flag))
+    .map(|(_, format)| format)
+    .collect();
+    match requested.as_slice() {
+        [] => Ok(None),
+        [format] => Ok(Some(*format)),
+        _ => Err(ArgParsingError::ConflictingFormats),
     }
+}
 
-    #[test]
-    fn extracts_target_dir_when_f_arg() {
-        let args = vec![
-            String::from("./mini-ls"),
-            String::from("-F"),
-            String::from("log.txt"),
-            String::from("~/dev"),
-        ];
-        let config = Config::build(args).unwrap();
-        assert_eq!(config.target, "~/dev");
+fn parse_pagination(
+    flags: &[Argument],
+    machine_format: Option<MachineFormat>,
+) -> Result<Option<Pagination>, ArgParsingError> {
+    let page_size = parse_count_flag(flags, AllowedFlags::PageSize, PAGE_SIZE_FLAG)?;
+    let cursor = parse_flag_value(flags, AllowedFlags::Cursor, CURSOR_FLAG)?;
+    let Some(page_size) = page_size else {
+        return match cursor {
+            Some(_) => Err(ArgParsingError::FlagRequiresFlag {
+                flag: CURSOR_FLAG.to_string(),
+                required: PAGE_SIZE_FLAG.to_string(),
+            }),
+            None => Ok(None),
+        };
+    };
+    if !matches!(
+        machine_format,
+        Some(MachineFormat::Json | MachineFormat::Ndjson)
+    ) {
+        return Err(ArgParsingError::FlagRequiresFlag {
+            flag: PAGE_SIZE_FLAG.to_string(),
+            required: format!("{} or --{}", JSON_FLAG, NDJSON_FLAG),
+        });
     }
+    let after = match cursor {
+        Some(cursor) => Some(
+            machine_output::decode_cursor(&cursor)
+                .ok_or(ArgParsingError::InvalidCursor { cursor })?,
+        ),
+        None => None,
+    };
+    Ok(Some(Pagination { page_size, after }))
+}
 
-    #[test]
-    fn returns_an_error_if_missing_file_for_output_with_f_flag() {
-        let temp_dir = temp_dir();
-        let temp_dir_path = temp_dir.to_path_buf();
-        let temp_path = temp_dir_path.to_str().unwrap();
-        let args = vec![
-            String::from("./mini-ls"),
-            String::from("-F"),
-            String::from(temp_path),
-        ];
-        let config = Config::build(args);
-        assert!(config.is_err());
-        let error = config.err().unwrap();
-        assert_eq!(error.to_string(), "missing file argument for -F flag")
-    }
+fn parse_highlights(flags: &[Argument]) -> Result<Highlights, ArgParsingError> {
+    Ok(Highlights {
+        older_than: parse_threshold(
+            flags,
+            AllowedFlags::HighlightOlderThan,
+            HIGHLIGHT_OLDER_THAN_FLAG,
+            "90d",
+            highlight::parse_age,
+        )?,
+        larger_than: parse_threshold(
+            flags,
+            AllowedFlags::HighlightLargerThan,
+            HIGHLIGHT_LARGER_THAN_FLAG,
+            "1G",
+            highlight::parse_size,
+        )?,
+    })
+}
 
-    #[test]
-    fn accepts_flags_concatenated_with_options() {
-        let args = vec![
-            String::from("./mini-ls"),
-            String::from("-Flog.txt"),
-            String::from("~/dev"),
-        ];
-        let config = Config::build(args).unwrap();
-        assert_eq!(config.target, "~/dev");
-        assert!(config.to_file);
-        assert_eq!(config.target_file, "log.txt");
+fn parse_threshold<T>(
+    flags: &[Argument],
+    switch: AllowedFlags,
+    flag_name: &str,
+    example: &str,
+    parse: fn(&str) -> Option<T>,
+) -> Result<Option<Threshold<T>>, ArgParsingError> {
+    let Some(text) = parse_flag_value(flags, switch, flag_name)? else {
+        return Ok(None);
+    };
+    match parse(&text) {
+        Some(limit) => Ok(Some(Threshold { limit, text })),
+        None => Err(ArgParsingError::InvalidThreshold {
+            flag: flag_name.to_string(),
+            value: text,
+            example: example.to_string(),
+        }),
     }
+}
 
-    #[test]
-    fn target_dir_is_working_dir_if_un_supplied_with_concat_args() {
-        let args = vec![String::from("./mini-ls"), String::from("-Flog.txt")];
-        let config = Config::build(args).unwrap();
-        assert_eq!(config.target, "./");
+fn parse_csv_dialect(flags: &[Argument]) -> Result<CsvDialect, ArgParsingError> {
+    let csv_requested = is_flag_present(flags, AllowedFlags::Csv);
+    for (switch, flag_name) in [
+        (AllowedFlags::CsvDelimiter, CSV_DELIMITER_FLAG),
+        (AllowedFlags::CsvQuoting, CSV_QUOTING_FLAG),
+        (AllowedFlags::NoCsvHeader, NO_CSV_HEADER_FLAG),
+    ] {
+        if !csv_requested && is_flag_present(flags, switch) {
+            return Err(ArgParsingError::FlagRequiresFlag {
+                flag: flag_name.to_string(),
+                required: CSV_FLAG.to_string(),
+            });
+        }
+    }
+    let mut dialect = CsvDialect {
+        header: !is_flag_present(flags, AllowedFlags::NoCsvHeader),
+        ..CsvDialect::default()
+    };
+    if let Some(value) = parse_flag_value(flags, AllowedFlags::CsvDelimiter, CSV_DELIMITER_FLAG)? {
+        dialect.delimiter = match value.as_str() {
+            "comma" => ',',
+            "semicolon" => ';',
+            "pipe" => '|',
+            _ => {
+                return Err(ArgParsingError::InvalidChoice {
+                    flag: CSV_DELIMITER_FLAG.to_string(),
+                    value,
+                    choices: ["comma", "semicolon", "pipe"].map(String::from).to_vec(),
+                })
+            }
+        };
+    }
+    if let Some(value) = parse_flag_value(flags, AllowedFlags::CsvQuoting, CSV_QUOTING_FLAG)? {
+        dialect.quoting = match value.as_str() {
+            "minimal" => CsvQuoting::Minimal,
+            "all" => CsvQuoting::All,
+            _ => {
+                return Err(ArgParsingError::InvalidChoice {
+                    flag: CSV_QUOTING_FLAG.to_string(),
+                    value,
+                    choices: ["minimal", "all"].map(String::from).to_vec(),
+                })
+            }
+        };
+    }
+    Ok(dialect)
+}
+
+fn parse_export_flags(
+    flags: &[Argument],
+) -> Result<Option<(ExportFormat, PathBuf)>, ArgParsingError> {
+    let mut requested = Vec::new();
+    for (switch, flag_name, format) in [
+        (
+            AllowedFlags::ExportParquet,
+            EXPORT_PARQUET_FLAG,
+            ExportFormat::Parquet,
+        ),
+        (
+            AllowedFlags::ExportArrow,
+            EXPORT_ARROW_FLAG,
+            ExportFormat::Arrow,
+        ),
+    ] {
+        if let Some(target) = parse_flag_value(flags, switch, flag_name)? {
+            let target = convert_from_short_unix_home(&target)?;
+            requested.push((format, PathBuf::from(target)));
+        }
+    }
+    if requested.len() > 1 {
+        return Err(ArgParsingError::ConflictingFormats);
+    }
+    Ok(requested.pop())
+}
+
+fn parse_dir_grouping(flags: &[Argument]) -> Result<DirGrouping, ArgParsingError> {
+    let Some(value) = parse_flag_value(flags, AllowedFlags::GroupDirs, GROUP_DIRS_FLAG)? else {
+        return Ok(DirGrouping::default());
+    };
+    match value.as_str() {
+        "first" => Ok(DirGrouping::First),
+        "last" => Ok(DirGrouping::Last),
+        "none" => Ok(DirGrouping::Mixed),
+        _ => Err(ArgParsingError::InvalidChoice {
+            flag: GROUP_DIRS_FLAG.to_string(),
+            value,
+            choices: ["first", "last", "none"].map(String::from).to_vec(),
+        }),
+    }
+}
+
+fn parse_compat_style(flags: &[Argument]) -> Result<Option<CompatStyle>, ArgParsingError> {
+    let Some(value) = parse_flag_value(flags, AllowedFlags::Compat, COMPAT_FLAG)? else {
+        return Ok(None);
+    };
+    match value.as_str() {
+        "gnu" => Ok(Some(CompatStyle::Gnu)),
+        "bsd" => Ok(Some(CompatStyle::Bsd)),
+        "dir" => Ok(Some(CompatStyle::Dir)),
+        _ => Err(ArgParsingError::InvalidChoice {
+            flag: COMPAT_FLAG.to_string(),
+            value,
+            choices: ["gnu", "bsd", "dir"].map(String::from).to_vec(),
+        }),
+    }
+}
+
+fn parse_path_style(flags: &[Argument]) -> Result<Option<PathStyle>, ArgParsingError> {
+    let Some(value) = parse_flag_value(flags, AllowedFlags::PathStyle, PATH_STYLE_FLAG)? else {
+        return Ok(None);
+    };
+    match value.as_str() {
+        "name" => Ok(Some(PathStyle::Name)),
+        "relative" => Ok(Some(PathStyle::Relative)),
+        "absolute" => Ok(Some(PathStyle::Absolute)),
+        _ => Err(ArgParsingError::InvalidChoice {
+            flag: PATH_STYLE_FLAG.to_string(),
+            value,
+            choices: ["name", "relative", "absolute"].map(String::from).to_vec(),
+        }),
+    }
+}
+
+fn parse_glob(pattern: String) -> Result<NamePattern, ArgParsingError> {
+    match glob::Pattern::new(&pattern) {
+        Ok(glob) => Ok(NamePattern::Glob(glob)),
+        Err(_) => Err(ArgParsingError::InvalidPattern { pattern }),
+    }
+}
+
+fn parse_regex(pattern: String) -> Result<NamePattern, ArgParsingError> {
+    match Regex::new(&pattern) {
+        Ok(regex) => Ok(NamePattern::Regex(regex)),
+        Err(error) => Err(ArgParsingError::InvalidRegex {
+            pattern,
+            reason: error.to_string(),
+        }),
+    }
+}
+
+/// A quoted pattern such as `'src/*.rs'` reaches us unexpanded, so when no such path exists the
+/// last component becomes the glob and the rest the directory to list.
+fn split_glob_target(target: &str) -> Option<(String, String)> {
+    let path = Path::new(target);
+    let name = path.file_name()?.to_str()?;
+    if path.exists() || !name.contains(['*', '?', '[']) {
+        return None;
+    }
+    let directory = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_str()?.to_string(),
+        _ => "./".to_string(),
+    };
+    Some((directory, name.to_string()))
+}
+
+fn parse_shell(flags: &[Argument]) -> Result<Option<Shell>, ArgParsingError> {
+    let Some(value) = parse_flag_value(flags, AllowedFlags::Completions, COMPLETIONS_FLAG)? else {
+        return Ok(None);
+    };
+    match value.as_str() {
+        "bash" => Ok(Some(Shell::Bash)),
+        "zsh" => Ok(Some(Shell::Zsh)),
+        "fish" => Ok(Some(Shell::Fish)),
+        "powershell" => Ok(Some(Shell::Powershell)),
+        _ => Err(ArgParsingError::InvalidChoice {
+            flag: COMPLETIONS_FLAG.to_string(),
+            value,
+            choices: ["bash", "zsh", "fish", "powershell"]
+                .map(String::from)
+                .to_vec(),
+        }),
+    }
+}
+
+fn parse_color_mode(flags: &[Argument]) -> Result<ColorMode, ArgParsingError> {
+    let Some(value) = parse_flag_value(flags, AllowedFlags::Color, COLOR_FLAG)? else {
+        return Ok(ColorMode::default());
+    };
+    match value.as_str() {
+        "auto" => Ok(ColorMode::Auto),
+        "always" => Ok(ColorMode::Always),
+        "never" => Ok(ColorMode::Never),
+        _ => Err(ArgParsingError::InvalidChoice {
+            flag: COLOR_FLAG.to_string(),
+            value,
+            choices: ["auto", "always", "never"].map(String::from).to_vec(),
+        }),
+    }
+}
+
+fn parse_time_format(flags: &[Argument]) -> Result<Option<String>, ArgParsingError> {
+    match parse_flag_value(flags, AllowedFlags::TimeFormat, TIME_FORMAT_FLAG)? {
+        Some(format) if !output_formatting::is_valid_time_format(&format) => {
+            Err(ArgParsingError::InvalidTimeFormat { format })
+        }
+        format => Ok(format),
+    }
+}
+
+fn parse_icon_style(flags: &[Argument]) -> Result<IconStyle, ArgParsingError> {
+    let Some(value) = parse_flag_value(flags, AllowedFlags::Icons, ICONS_FLAG)? else {
+        return Ok(IconStyle::default());
+    };
+    match value.as_str() {
+        "emoji" => Ok(IconStyle::Emoji),
+        "ascii" => Ok(IconStyle::Ascii),
+        _ => Err(ArgParsingError::InvalidChoice {
+            flag: ICONS_FLAG.to_string(),
+            value,
+            choices: ["emoji", "ascii"].map(String::from).to_vec(),
+        }),
+    }
+}
+
+fn parse_sort_key(flags: &[Argument]) -> Result<Option<SortKey>, ArgParsingError> {
+    let Some(value) = parse_flag_value(flags, AllowedFlags::Sort, SORT_FLAG)? else {
+        return Ok(None);
+    };
+    match value.as_str() {
+        "name" => Ok(Some(SortKey::Name)),
+        "size" => Ok(Some(SortKey::Size)),
+        "modified" => Ok(Some(SortKey::Modified)),
+        "created" => Ok(Some(SortKey::Created)),
+        "version" => Ok(Some(SortKey::Version)),
+        _ => Err(ArgParsingError::InvalidChoice {
+            flag: SORT_FLAG.to_string(),
+            value,
+            choices: ["name", "size", "modified", "created", "version"]
+                .map(String::from)
+                .to_vec(),
+        }),
+    }
+}
+
+fn parse_encoding(flags: &[Argument]) -> Result<OutputEncoding, ArgParsingError> {
+    let Some(value) = parse_flag_value(flags, AllowedFlags::Encoding, ENCODING_FLAG)? else {
+        return Ok(OutputEncoding::default());
+    };
+    match value.to_ascii_lowercase().as_str() {
+        "utf-8" | "utf8" => Ok(OutputEncoding::Utf8),
+        "utf-8-bom" | "utf8-bom" => Ok(OutputEncoding::Utf8Bom),
+        "utf-16le" | "utf16le" => Ok(OutputEncoding::Utf16Le),
+        _ => Err(ArgParsingError::InvalidChoice {
+            flag: ENCODING_FLAG.to_string(),
+            value,
+            choices: ["utf-8", "utf-8-bom", "utf-16le"]
+                .map(String::from)
+                .to_vec(),
+        }),
+    }
+}
+
+fn load_config_file(path: Option<&Path>) -> Result<ConfigFile, ArgParsingError> {
+    let Some(path) = path else {
+        return Ok(ConfigFile::default());
+    };
+    ConfigFile::load(path).map_err(|reason| ArgParsingError::InvalidConfigFile {
+        path: path.display().to_string(),
+        reason,
+    })
+}
+
+/// Merges every layer of settings into one argument list, lowest precedence first, so the parser's
+/// last-one-wins rule settles each value: config file defaults, the selected profile, `MINI_LS_*`
+/// variables and finally the command line.
+fn resolve_layers(
+    config_file: &ConfigFile,
+    command_line: Vec<Argument>,
+    variable: impl Fn(&str) -> Option<String>,
+) -> Result<(Vec<Argument>, BTreeMap<String, Source>), ArgParsingError> {
+    let environment = environment_arguments(variable)?;
+    let profile = match parse_flag_value(&command_line, AllowedFlags::Profile, PROFILE_FLAG)? {
+        Some(name) => Some(name),
+        None => parse_flag_value(&environment, AllowedFlags::Profile, PROFILE_FLAG)?,
+    };
+    let profile = match profile {
+        Some(name) => match config_file.profiles.get(&name) {
+            Some(table) => table_arguments(table, &format!("profile {}", name))?,
+            None => {
+                return Err(ArgParsingError::InvalidSetting {
+                    origin: format!("--{}", PROFILE_FLAG),
+                    reason: format!("the config file has no [profiles.{}] table", name),
+                })
+            }
+        },
+        None => vec![],
+    };
+    let layers = [
+        (
+            Source::ConfigFile,
+            table_arguments(&config_file.defaults, "the config file")?,
+        ),
+        (Source::Profile, profile),
+        (Source::Environment, environment),
+        (Source::CommandLine, command_line),
+    ];
+    let mut sources = BTreeMap::new();
+    let mut merged = Vec::new();
+    for (source, arguments) in layers {
+        for argument in arguments {
+            if let Argument::Flag { switch, .. } | Argument::Negated { switch } = &argument {
+                sources.insert(flag_name(*switch).to_string(), source);
+            }
+            merged.push(argument);
+        }
+    }
+    Ok((apply_negations(merged), sources))
+}
+
+fn table_arguments(table: &FlagTable, origin: &str) -> Result<Vec<Argument>, ArgParsingError> {
+    let mut words = Vec::new();
+    for (name, value) in table {
+        let invalid = |reason: String| ArgParsingError::InvalidSetting {
+            origin: origin.to_string(),
+            reason,
+        };
+        let Some((_, _, arity)) = LONG_FLAGS.iter().find(|(flag, ..)| flag == name) else {
+            return Err(invalid(format!("there is no --{} flag", name)));
+        };
+        let word = match (arity, value) {
+            (Arity::Switch, toml::Value::Boolean(true)) => format!("--{}", name),
+            (_, toml::Value::Boolean(false)) => format!("--{}{}", NEGATION_PREFIX, name),
+            (Arity::Value, toml::Value::String(text)) => format!("--{}={}", name, text),
+            (Arity::Value, toml::Value::Integer(number)) => format!("--{}={}", name, number),
+            (Arity::Switch, _) => return Err(invalid(format!("{} expects true or false", name))),
+            (Arity::Value, _) => return Err(invalid(format!("{} expects a value", name))),
+        };
+        words.push(word);
+    }
+    layer_arguments(words, origin)
+}
+
+/// `MINI_LS_OPTS` holds flags as they would be typed, e.g. `-l --sort size`, and is split with
+/// shell quoting so `--format '{name} {size}'` stays one value. It is followed by
+/// `MINI_LS_<FLAG>` for every long flag, e.g. `MINI_LS_SORT=size` or `MINI_LS_EXTENDED=1`, so a
+/// single setting overrides the flag string.
+fn environment_arguments(
+    variable: impl Fn(&str) -> Option<String>,
+) -> Result<Vec<Argument>, ArgParsingError> {
+    let mut words = match variable(OPTIONS_VARIABLE) {
+        Some(options) => {
+            let Some(options) = shlex::split(&options) else {
+                return Err(ArgParsingError::InvalidSetting {
+                    origin: OPTIONS_VARIABLE.to_string(),
+                    reason: String::from("a quote is never closed or it ends in a backslash"),
+                });
+            };
+            let args = std::iter::once(String::new()).chain(options).collect();
+            expand_gnu_aliases(args).split_off(1)
+        }
+        None => Vec::new(),
+    };
+    let options_count = words.len();
+    for (name, _, arity) in LONG_FLAGS.iter() {
+        let key = format!(
+            "{}{}",
+            VARIABLE_PREFIX,
+            name.to_uppercase().replace('-', "_")
+        );
+        let Some(value) = variable(&key) else {
+            continue;
+        };
+        let word = match (arity, value.to_ascii_lowercase().as_str()) {
+            (Arity::Value, _) => format!("--{}={}", name, value),
+            (Arity::Switch, "1" | "true" | "yes" | "on") => format!("--{}", name),
+            (Arity::Switch, "0" | "false" | "no" | "off" | "") => {
+                format!("--{}{}", NEGATION_PREFIX, name)
+            }
+            (Arity::Switch, _) => {
+                return Err(ArgParsingError::InvalidSetting {
+                    origin: key,
+                    reason: format!("expected true or false but got {}", value),
+                })
+            }
+        };
+        words.push(word);
+    }
+    let arguments = layer_arguments(words, "the environment")?;
+    let stray = arguments.iter().find_map(|argument| match argument {
+        Argument::TargetDir { target } => Some(target),
+        _ => None,
+    });
+    if let (true, Some(stray)) = (options_count > 0, stray) {
+        return Err(ArgParsingError::InvalidSetting {
+            origin: OPTIONS_VARIABLE.to_string(),
+            reason: format!(
+                "`{}` is not a flag; quote values containing spaces, e.g. --format '{{name}} \
+                 {{size}}', the target comes from the command line",
+                stray
+            ),
+        });
+    }
+    Ok(arguments)
+}
+
+fn layer_arguments(words: Vec<String>, origin: &str) -> Result<Vec<Argument>, ArgParsingError> {
+    let args: Vec<String> = std::iter::once(String::new()).chain(words).collect();
+    parse_flags(&args).map_err(|error| ArgParsingError::InvalidSetting {
+        origin: origin.to_string(),
+        reason: error.to_string(),
+    })
+}
+
+/// The subcommands with their arguments, as listed by `--help` and offered by completions.
+const COMMANDS: [(&str, &str); 7] = [
+    (
+        "find PATTERN",
+        "recursively list entries whose names match the glob or text",
+    ),
+    (
+        "stats",
+        "totals for the files and directories below the target",
+    ),
+    (
+        "serve",
+        "serve a read-only HTML index of the target over HTTP",
+    ),
+    (
+        "index",
+        "(Linux) keep an index of the target current for query",
+    ),
+    (
+        "query REQUEST",
+        "ask a running index, e.g. \"list src\" or stats",
+    ),
+    (
+        "config init|validate",
+        "write a commented config file or check the existing one",
+    ),
+    ("doctor", "report what the terminal and filesystem support"),
+];
+
+/// Every long flag with its short alias, value placeholder and description, in table order.
+fn documented_flags() -> Vec<DocumentedFlag> {
+    LONG_FLAGS
+        .iter()
+        .map(|(name, switch, arity)| {
+            let short = SHORT_FLAGS
+                .iter()
+                .find(|(_, short_switch, _)| short_switch == switch)
+                .map(|(short, ..)| *short);
+            let (value, description) = describe_flag(*switch);
+            DocumentedFlag {
+                short,
+                long: name,
+                value: match arity {
+                    Arity::Switch => None,
+                    Arity::Value => Some(value),
+                },
+                description,
+            }
+        })
+        .collect()
+}
+
+/// The `--completions` script, offering the same commands and flags as `--help`.
+pub(crate) fn completion_script(shell: Shell) -> String {
+    let commands: Vec<&str> = COMMANDS
+        .iter()
+        .filter_map(|(usage, _)| usage.split(' ').next())
+        .collect();
+    completions::script(shell, &commands, &documented_flags())
+}
+
+/// The `--help` screen, built from the flag tables so every flag is listed with its short form.
+pub(crate) fn help_text() -> String {
+    let flags: Vec<(String, &str)> = documented_flags()
+        .into_iter()
+        .map(|flag| {
+            let short = flag
+                .short
+                .map_or(String::from("    "), |short| format!("-{}, ", short));
+            let usage = match flag.value {
+                None => format!("{}--{}", short, flag.long),
+                Some(value) => format!("{}--{} {}", short, flag.long, value),
+            };
+            (usage, flag.description)
+        })
+        .collect();
+    // the few flags with long value lists get their description on the next line
+    let width = COMMANDS
+        .iter()
+        .map(|(usage, _)| usage.len())
+        .chain(flags.iter().map(|(usage, _)| usage.len()))
+        .filter(|length| *length <= HELP_USAGE_WIDTH)
+        .max()
+        .unwrap_or(HELP_USAGE_WIDTH);
+    let line = |usage: &str, description: &str| {
+        if usage.len() > width {
+            format!(
+                "  {}\n  {:width$}  {}",
+                usage,
+                "",
+                description,
+                width = width
+            )
+        } else {
+            format!("  {:<width$}  {}", usage, description, width = width)
+        }
+    };
+    let mut lines = vec![
+        String::from("Usage: mini-ls [FLAGS] [TARGET]"),
+        String::from("       mini-ls COMMAND [FLAGS] [TARGET]"),
+        String::new(),
+        String::from("Lists TARGET, the current directory when none is given."),
+        String::new(),
+        String::from("Commands:"),
+    ];
+    for (usage, description) in COMMANDS {
+        lines.push(line(usage, description));
+    }
+    lines.push(String::new());
+    lines.push(String::from("Flags:"));
+    for (usage, description) in &flags {
+        lines.push(line(usage, description));
+    }
+    lines.push(String::new());
+    lines.push(String::from(
+        "GNU ls flags -t, -S, -v and -h are accepted too. Every long flag can be negated with \
+         --no-FLAG, set under [defaults] in the config file or as MINI_LS_<FLAG>, and the last \
+         occurrence wins. A command given alone lists a local entry of the same name instead, \
+         and mini-ls -- COMMAND always lists.",
+    ));
+    lines.join("\n")
+}
+
+/// The value placeholder and the help line for each flag.
+fn describe_flag(flag: AllowedFlags) -> (&'static str, &'static str) {
+    match flag {
+        AllowedFlags::F => ("PATH", "write the listing to a file, - for standard output"),
+        AllowedFlags::L => ("", "show dates, permissions and any optional columns"),
+        AllowedFlags::GridDown => ("", "names in columns filled top to bottom"),
+        AllowedFlags::GridAcross => ("", "names in columns filled left to right"),
+        AllowedFlags::Recursive => (
+            "",
+            "list every directory below the target in its own section",
+        ),
+        AllowedFlags::Largest => ("N", "report the N largest files below the target"),
+        AllowedFlags::Oldest => ("N", "report the N least recently modified entries"),
+        AllowedFlags::Newest => ("N", "report the N most recently modified entries"),
+        AllowedFlags::ByExtension => ("", "total size and count of files per extension"),
+        AllowedFlags::Histogram => ("", "chart how many files fall in each size bucket"),
+        AllowedFlags::Verify => ("MANIFEST", "compare files against a sha256sum manifest"),
+        AllowedFlags::Checksums => ("", "print a sha256sum manifest of every file"),
+        AllowedFlags::CheckCollisions => {
+            ("", "list names that only differ by case or normalisation")
+        }
+        AllowedFlags::Summary => (
+            "",
+            "append counts of each kind of entry and the top extensions",
+        ),
+        AllowedFlags::Octal => ("", "add a Mode column with the octal permission bits"),
+        AllowedFlags::Audit => ("", "add an Audit column flagging deceptive names"),
+        AllowedFlags::Mount => ("", "add a Mount column with each entry's mount point"),
+        AllowedFlags::ProjectId => ("", "add a Project column with the quota project id"),
+        AllowedFlags::Compression => (
+            "",
+            "add a Compression column with allocated against logical size",
+        ),
+        AllowedFlags::SharedExtents => ("", "add a Shared column with reflinked bytes"),
+        AllowedFlags::Access => ("", "add an Access column with what the current user may do"),
+        AllowedFlags::Json => ("", "print the listing as JSON"),
+        AllowedFlags::Ndjson => ("", "print the listing as one JSON record per line"),
+        AllowedFlags::ExportParquet => ("PATH", "write the entries to a Parquet file"),
+        AllowedFlags::ExportArrow => ("PATH", "write the entries to an Arrow IPC file"),
+        AllowedFlags::GroupDirs => ("first|last|none", "where directories go, last by default"),
+        AllowedFlags::Port => ("PORT", "port for serve, 8080 by default"),
+        AllowedFlags::Bind => (
+            "ADDRESS",
+            "address for serve to listen on, 127.0.0.1 by default",
+        ),
+        AllowedFlags::Socket => ("PATH", "socket for index and query"),
+        AllowedFlags::Locale => (
+            "LOCALE",
+            "date and number format, from LC_ALL or LANG by default",
+        ),
+        AllowedFlags::Owner => ("", "add an Owner column with the login name"),
+        AllowedFlags::FullName => ("", "show the owner's real name in the Owner column"),
+        AllowedFlags::TwoLine => ("", "put the -l columns on a line under each name"),
+        AllowedFlags::Dired => ("", "ls -l output with Emacs dired offsets"),
+        AllowedFlags::RepeatHeader => ("N", "reprint the header every N entries"),
+        AllowedFlags::Csv => ("", "print the listing as CSV"),
+        AllowedFlags::CsvDelimiter => (
+            "comma|semicolon|pipe",
+            "CSV field delimiter, comma by default",
+        ),
+        AllowedFlags::CsvQuoting => (
+            "minimal|all",
+            "which CSV fields are quoted, minimal by default",
+        ),
+        AllowedFlags::NoCsvHeader => ("", "leave out the CSV header row"),
+        AllowedFlags::Tsv => ("", "print the listing as tab separated values"),
+        AllowedFlags::Html => (
+            "",
+            "print the listing as an HTML page with a sortable table",
+        ),
+        AllowedFlags::Separator => ("TEXT", "put TEXT between the -l columns"),
+        AllowedFlags::Crlf => ("", "end lines with CRLF"),
+        AllowedFlags::Encoding => (
+            "utf-8|utf-8-bom|utf-16le",
+            "encoding of the -F file, utf-8 by default",
+        ),
+        AllowedFlags::Mkdir => ("", "create missing parent directories of the -F file"),
+        AllowedFlags::Force => ("", "overwrite an existing -F file or config file"),
+        AllowedFlags::DumpConfig => ("", "print the resolved settings instead of listing"),
+        AllowedFlags::Version => ("", "print the version and exit"),
+        AllowedFlags::Sort => (
+            "name|size|modified|created|version",
+            "sort order, directory order by default",
+        ),
+        AllowedFlags::Compat => (
+            "gnu|bsd|dir",
+            "print exactly the shape of another tool's listing",
+        ),
+        AllowedFlags::Profile => ("NAME", "apply a [profiles.NAME] table from the config file"),
+        AllowedFlags::PathStyle => ("name|relative|absolute", "how entries are named"),
+        AllowedFlags::Pick => (
+            "",
+            "(Unix) pick entries with a fuzzy filter and print their paths",
+        ),
+        AllowedFlags::Print0 => (
+            "",
+            "bare names or picked paths ended by NUL bytes, for xargs -0",
+        ),
+        AllowedFlags::PageSize => ("N", "machine readable output in pages of N entries"),
+        AllowedFlags::Cursor => ("TOKEN", "the page to resume from, from next_cursor"),
+        AllowedFlags::HighlightOlderThan => ("AGE", "mark entries modified longer ago, e.g. 90d"),
+        AllowedFlags::HighlightLargerThan => ("SIZE", "mark files bigger than SIZE, e.g. 1G"),
+        AllowedFlags::InodeOrder => ("", "stat entries in inode order before sorting"),
+        AllowedFlags::Reverse => ("", "reverse the sort order"),
+        AllowedFlags::All => ("", "include entries starting with ."),
+        AllowedFlags::Dereference => ("", "describe what symlinks point at"),
+        AllowedFlags::Glob => ("PATTERN", "only list names matching the glob"),
+        AllowedFlags::Exclude => ("PATTERN", "leave out names matching the glob, repeatable"),
+        AllowedFlags::Match => ("REGEX", "only list names matching the regular expression"),
+        AllowedFlags::Files => ("", "only list entries that are not directories"),
+        AllowedFlags::Dirs => ("", "only list directories"),
+        AllowedFlags::Watch => ("N", "list again every N seconds until interrupted"),
+        AllowedFlags::Follow => (
+            "",
+            "redraw the whole listing whenever an entry changes, Linux only",
+        ),
+        AllowedFlags::NoPager => (
+            "",
+            "never show listings taller than the terminal through $PAGER",
+        ),
+        AllowedFlags::GitIgnore => (
+            "",
+            "leave out what the repository's .gitignore files ignore",
+        ),
+        AllowedFlags::Tree => ("", "draw the target and everything below it as a tree"),
+        AllowedFlags::Depth => ("N", "how many levels --tree and -R descend"),
+        AllowedFlags::Color => ("auto|always|never", "colour names by type, auto by default"),
+        AllowedFlags::SingleColumn => ("", "bare names one per line"),
+        AllowedFlags::Help => ("", "print this help and exit"),
+        AllowedFlags::Icons => (
+            "emoji|ascii",
+            "how entry types are marked, emoji by default",
+        ),
+        AllowedFlags::Width => (
+            "N",
+            "lay the listing out for N columns, the terminal width by default",
+        ),
+        AllowedFlags::StrictUtf8 => (
+            "",
+            "fail on names that are not UTF-8 instead of marking them",
+        ),
+        AllowedFlags::RelativeTime => ("", "show dates as how long ago they were"),
+        AllowedFlags::Format => (
+            "PATTERN",
+            "print each entry as PATTERN, e.g. \"{name}\\t{size}\"",
+        ),
+        AllowedFlags::TimeFormat => ("FORMAT", "strftime format for dates, such as \"%d %b %Y\""),
+        AllowedFlags::Completions => (
+            "bash|zsh|fish|powershell",
+            "print a completion script for the shell and exit",
+        ),
+    }
+}
+
+/// The long name where there is one, as that is what the config file and variables use.
+fn flag_name(target: AllowedFlags) -> &'static str {
+    LONG_FLAGS
+        .iter()
+        .chain(SHORT_FLAGS.iter())
+        .find(|(_, switch, _)| *switch == target)
+        .map(|(name, ..)| *name)
+        .expect("every flag is listed in the flag tables")
+}
+
+fn default_socket_path(variable: impl Fn(&str) -> Option<String>) -> PathBuf {
+    variable("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(std::env::temp_dir)
+        .join(DEFAULT_SOCKET_NAME)
+}
+
+fn parse_port(flags: &[Argument]) -> Result<Option<u16>, ArgParsingError> {
+    let Some(value) = parse_flag_value(flags, AllowedFlags::Port, PORT_FLAG)? else {
+        return Ok(None);
+    };
+    match value.parse::<u16>() {
+        Ok(port) if port > 0 => Ok(Some(port)),
+        _ => Err(ArgParsingError::InvalidPort { value }),
+    }
+}
+
+fn parse_bind_address(flags: &[Argument]) -> Result<Option<IpAddr>, ArgParsingError> {
+    let Some(value) = parse_flag_value(flags, AllowedFlags::Bind, BIND_FLAG)? else {
+        return Ok(None);
+    };
+    value
+        .parse::<IpAddr>()
+        .map(Some)
+        .map_err(|_| ArgParsingError::InvalidAddress { value })
+}
+
+fn parse_optional_columns(flags: &[Argument]) -> Vec<OptionalColumn> {
+    let owner = if is_flag_present(flags, AllowedFlags::FullName) {
+        Some(OptionalColumn::OwnerName)
+    } else if is_flag_present(flags, AllowedFlags::Owner) {
+        Some(OptionalColumn::Owner)
+    } else {
+        None
+    };
+    [
+        (AllowedFlags::Octal, OptionalColumn::Octal),
+        (AllowedFlags::Access, OptionalColumn::Access),
+        (AllowedFlags::Audit, OptionalColumn::Audit),
+        (AllowedFlags::Mount, OptionalColumn::Mount),
+        (AllowedFlags::ProjectId, OptionalColumn::Project),
+        (AllowedFlags::Compression, OptionalColumn::Compression),
+        (AllowedFlags::SharedExtents, OptionalColumn::Shared),
+    ]
+    .into_iter()
+    .filter(|(flag, _)| is_flag_present(flags, *flag))
+    .map(|(_, column)| column)
+    .chain(owner)
+    .collect()
+}
+
+/// As with `ls`, whichever of `-C` and `-x` comes last decides the order.
+fn parse_grid_order(flags: &[Argument]) -> Option<GridOrder> {
+    flags.iter().rev().find_map(|flag| match flag {
+        Argument::Flag {
+            switch: AllowedFlags::GridDown,
+            ..
+        } => Some(GridOrder::Down),
+        Argument::Flag {
+            switch: AllowedFlags::GridAcross,
+            ..
+        } => Some(GridOrder::Across),
+        _ => None,
+    })
+}
+
+fn parse_kind_filter(flags: &[Argument]) -> Option<KindFilter> {
+    flags.iter().rev().find_map(|flag| match flag {
+        Argument::Flag {
+            switch: AllowedFlags::Files,
+            ..
+        } => Some(KindFilter::Files),
+        Argument::Flag {
+            switch: AllowedFlags::Dirs,
+            ..
+        } => Some(KindFilter::Directories),
+        _ => None,
+    })
+}
+
+fn is_flag_present(flags: &[Argument], target: AllowedFlags) -> bool {
+    flags.iter().any(|flag| match flag {
+        Argument::Flag { switch, .. } => *switch == target,
+        _ => false,
+    })
+}
+
+fn parse_count_flag(
+    flags: &[Argument],
+    target: AllowedFlags,
+    flag_name: &str,
+) -> Result<Option<usize>, ArgParsingError> {
+    let Some(value) = parse_flag_value(flags, target, flag_name)? else {
+        return Ok(None);
+    };
+    match value.parse::<usize>() {
+        Ok(count) if count > 0 => Ok(Some(count)),
+        _ => Err(ArgParsingError::InvalidCount {
+            flag: flag_name.to_string(),
+            value,
+        }),
+    }
+}
+
+/// Every value given to a repeatable flag, in command line order.
+fn parse_flag_values(
+    flags: &[Argument],
+    target: AllowedFlags,
+    flag_name: &str,
+) -> Result<Vec<String>, ArgParsingError> {
+    let mut values = Vec::new();
+    for (i, arg) in flags.iter().enumerate() {
+        let Argument::Flag {
+            switch,
+            flag_option_text,
+        } = arg
+        else {
+            continue;
+        };
+        if *switch != target {
+            continue;
+        }
+        values.push(match (flag_option_text, flags.get(i + 1)) {
+            (Some(text), _) | (None, Some(Argument::Option { text })) => text.to_string(),
+            _ => {
+                return Err(ArgParsingError::MissingFlagOption {
+                    flag: flag_name.to_string(),
+                })
+            }
+        });
+    }
+    Ok(values)
+}
+
+fn parse_flag_value(
+    flags: &[Argument],
+    target: AllowedFlags,
+    flag_name: &str,
+) -> Result<Option<String>, ArgParsingError> {
+    for (i, arg) in flags.iter().enumerate().rev() {
+        if let Argument::Flag {
+            switch,
+            flag_option_text,
+        } = arg
+        {
+            if *switch != target {
+                continue;
+            }
+            return match flag_option_text {
+                Some(text) => Ok(Some(text.to_string())),
+                None => match flags.get(i + 1) {
+                    Some(Argument::Option { text }) => Ok(Some(text.to_string())),
+                    _ => Err(ArgParsingError::MissingFlagOption {
+                        flag: flag_name.to_string(),
+                    }),
+                },
+            };
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        completion_script, help_text, is_flag_present, parse_color_mode, parse_count_flag,
+        parse_flags, parse_icon_style, parse_sort_key, resolve_layers, AllowedFlags,
+        ArgParsingError, Config, ConfigAction, IndexMode, Source, GNU_ALIASES, LONG_FLAGS,
+        SUBCOMMANDS,
+    };
+    use crate::color::ColorMode;
+    use crate::columns::OptionalColumn;
+    use crate::compat::CompatStyle;
+    use crate::completions::Shell;
+    use crate::config_file::ConfigFile;
+    use crate::delimited::{CsvDialect, CsvQuoting};
+    use crate::export::ExportFormat;
+    use crate::locale::Locale;
+    use crate::machine_output::MachineFormat;
+    use crate::output_formatting::{DirGrouping, GridOrder, IconStyle};
+    use crate::reports::Report;
+    use crate::server::DEFAULT_ADDRESS;
+    use crate::sorting::SortKey;
+    use std::env::temp_dir;
+    use std::path::PathBuf;
+
+    /// As the command line would build it, with no config file and an empty environment.
+    fn build(args: Vec<String>) -> Result<Config, ArgParsingError> {
+        Config::build_with(args, None, |_| None)
+    }
+
+    #[test]
+    fn obtains_the_dir_from_args() {
+        let args = vec![String::from("./mini-ls"), String::from("~/dev")];
+        let config = build(args).unwrap();
+        assert_eq!(config.target, "~/dev");
+    }
+
+    #[test]
+    fn everything_after_double_dash_is_a_target() {
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("-l"),
+            String::from("--"),
+            String::from("-t"),
+        ];
+        let config = build(args).unwrap();
+        assert_eq!(config.target, "-t");
+        assert!(config.extended_attributes);
+        assert_eq!(config.sort, None);
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("--"),
+            String::from("-l"),
+        ];
+        let config = build(args).unwrap();
+        assert_eq!(config.target, "-l");
+        assert!(!config.extended_attributes);
+    }
+
+    #[test]
+    fn extracts_f_arg_to_config() {
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("-F"),
+            String::from("log.txt"),
+            String::from("~/dev"),
+        ];
+        let config = build(args).unwrap();
+        assert!(config.to_file);
+    }
+
+    #[test]
+    fn extracts_target_dir_when_f_arg() {
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("-F"),
+            String::from("log.txt"),
+            String::from("~/dev"),
+        ];
+        let config = build(args).unwrap();
+        assert_eq!(config.target, "~/dev");
+    }
+
+    #[test]
+    fn returns_an_error_if_missing_file_for_output_with_f_flag() {
+        let temp_dir = temp_dir();
+        let temp_dir_path = temp_dir.to_path_buf();
+        let temp_path = temp_dir_path.to_str().unwrap();
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("-F"),
+            String::from(temp_path),
+        ];
+        let config = build(args);
+        assert!(config.is_err());
+        let error = config.err().unwrap();
+        assert_eq!(error.to_string(), "missing file argument for -F flag")
+    }
+
+    #[test]
+    fn accepts_flags_concatenated_with_options() {
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("-Flog.txt"),
+            String::from("~/dev"),
+        ];
+        let config = build(args).unwrap();
+        assert_eq!(config.target, "~/dev");
+        assert!(config.to_file);
+        assert_eq!(config.target_file, "log.txt");
+    }
+
+    #[test]
+    fn target_dir_is_working_dir_if_un_supplied_with_concat_args() {
+        let args = vec![String::from("./mini-ls"), String::from("-Flog.txt")];
+        let config = build(args).unwrap();
+        assert_eq!(config.target, "./");
+    }
+
+    #[test]
+    fn target_dir_is_working_dir_if_un_supplied_with_args() {
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("-F"),
+            String::from("log.txt"),
+        ];
+        let config = build(args).unwrap();
+        assert_eq!(config.target, "./");
+    }
+
+    #[test]
+    fn target_dir_is_working_dir_if_un_supplied_with_no_args() {
+        let args = vec![String::from("./mini-ls")];
+        let config = build(args).unwrap();
+        assert_eq!(config.target, "./");
+    }
+
+    #[test]
+    fn config_includes_extended_arg_if_passed() {
+        let args = vec![String::from("./mini-ls"), String::from("-l")];
+        let config = build(args).unwrap();
+        assert!(config.extended_attributes);
+    }
+
+    #[test]
+    fn config_includes_l_arg_if_concatenated() {
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("-lF"),
+            String::from("log.txt"),
+        ];
+        let config = build(args).unwrap();
+        assert!(config.extended_attributes);
+    }
+
+    #[test]
+    fn finds_all_flags_and_options() {
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("-lF"),
+            String::from("log.txt"),
+        ];
+        let config = build(args).unwrap();
+        assert!(config.extended_attributes);
+        assert!(config.to_file);
+        assert_eq!(config.target_file, "log.txt");
+    }
+
+    #[test]
+    fn finds_all_flags_options_when_separated() {
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("-l"),
+            String::from("-F"),
+            String::from("log.txt"),
+            String::from("/opt/dev"),
+        ];
+        let config = build(args).unwrap();
+        assert!(config.extended_attributes);
+        assert!(config.to_file);
+        assert_eq!(config.target_file, "log.txt");
+        assert_eq!(config.target, "/opt/dev");
+    }
+
+    #[test]
+    fn still_extracts_all_flags_in_concat_block() {
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("-lFlog.txt"),
+            String::from("/opt/dev"),
+        ];
+        let config = build(args).unwrap();
+        assert!(config.to_file);
+        assert!(config.extended_attributes);
+        assert_eq!(config.target_file, "log.txt");
+        assert_eq!(config.target, "/opt/dev");
+    }
+
+    #[test]
+    fn extracts_largest_count_to_config() {
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("--largest"),
+            String::from("5"),
+            String::from("/opt/dev"),
+        ];
+        let config = build(args).unwrap();
+        assert!(matches!(config.report, Some(Report::Largest(5))));
+        assert_eq!(config.target, "/opt/dev");
+    }
+
+    #[test]
+    fn returns_an_error_if_largest_count_is_not_a_number() {
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("--largest"),
+            String::from("many"),
+        ];
+        let error = build(args).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "--largest expects a positive whole number but got many"
+        );
+    }
+
+    #[test]
+    fn extracts_newest_count_to_config() {
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("--newest"),
+            String::from("3"),
+        ];
+        let config = build(args).unwrap();
+        assert!(matches!(config.report, Some(Report::Newest(3))));
+        assert_eq!(config.target, "./");
+    }
+
+    #[test]
+    fn extracts_verify_manifest_to_config() {
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("--verify"),
+            String::from("manifest.sha256"),
+            String::from("/opt/dev"),
+        ];
+        let config = build(args).unwrap();
+        assert_eq!(
+            config.report,
+            Some(Report::Verify(PathBuf::from("manifest.sha256")))
+        );
+        assert_eq!(config.target, "/opt/dev");
+    }
+
+    #[test]
+    fn returns_an_error_if_verify_is_missing_its_manifest() {
+        let args = vec![String::from("./mini-ls"), String::from("--verify")];
+        let error = build(args).err().unwrap();
+        assert_eq!(error.to_string(), "missing value for --verify flag");
+    }
+
+    #[test]
+    fn extracts_checksums_report_with_file_output() {
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("--checksums"),
+            String::from("-F"),
+            String::from("manifest.sha256"),
+        ];
+        let config = build(args).unwrap();
+        assert_eq!(config.report, Some(Report::Checksums));
+        assert_eq!(config.target_file, "manifest.sha256");
+    }
+
+    #[test]
+    fn collision_check_conflicts_with_other_reports() {
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("--check-collisions"),
+        ];
+        let config = build(args).unwrap();
+        assert_eq!(config.report, Some(Report::Collisions));
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("--check-collisions"),
+            String::from("--checksums"),
+        ];
+        assert!(matches!(
+            build(args),
+            Err(ArgParsingError::ConflictingReports)
+        ));
+    }
+
+    #[test]
+    fn config_includes_type_summary_if_passed() {
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("-l"),
+            String::from("--summary"),
+        ];
+        let config = build(args).unwrap();
+        assert!(config.type_summary);
+        assert!(config.extended_attributes);
+    }
+
+    #[test]
+    fn config_includes_optional_columns_if_passed() {
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("-l"),
+            String::from("--access"),
+            String::from("--octal"),
+            String::from("--audit"),
+        ];
+        let config = build(args).unwrap();
+        assert_eq!(
+            config.optional_columns,
+            vec![
+                OptionalColumn::Octal,
+                OptionalColumn::Access,
+                OptionalColumn::Audit
+            ]
+        );
+    }
+
+    #[test]
+    fn recursive_listing_can_be_combined_with_other_short_flags() {
+        let args = vec![String::from("./mini-ls"), String::from("-lR")];
+        let config = build(args).unwrap();
+        assert!(config.recursive);
+        assert!(config.extended_attributes);
+        let args = vec![String::from("./mini-ls"), String::from("--recursive")];
+        assert!(build(args).unwrap().recursive);
+    }
+
+    #[test]
+    fn config_includes_two_line_layout_if_passed() {
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("-l"),
+            String::from("--two-line"),
+        ];
+        let config = build(args).unwrap();
+        assert!(config.two_line);
+    }
+
+    #[test]
+    fn full_name_replaces_the_login_owner_column() {
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("-l"),
+            String::from("--owner"),
+            String::from("--full-name"),
+        ];
+        let config = build(args).unwrap();
+        assert_eq!(config.optional_columns, vec![OptionalColumn::OwnerName]);
+    }
+
+    #[test]
+    fn extracts_machine_format_to_config() {
+        let args = vec![String::from("./mini-ls"), String::from("--ndjson")];
+        let config = build(args).unwrap();
+        assert_eq!(config.machine_format, Some(MachineFormat::Ndjson));
+    }
+
+    #[test]
+    fn returns_an_error_when_multiple_formats_requested() {
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("--json"),
+            String::from("--ndjson"),
+        ];
+        let error = build(args).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "only one output format can be used at once"
+        );
+    }
+
+    #[test]
+    fn extracts_parquet_export_target_to_config() {
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("--export-parquet"),
+            String::from("listing.parquet"),
+            String::from("/opt/dev"),
+        ];
+        let config = build(args).unwrap();
+        assert_eq!(
+            config.export,
+            Some((ExportFormat::Parquet, PathBuf::from("listing.parquet")))
+        );
+        assert_eq!(config.target, "/opt/dev");
+    }
+
+    #[test]
+    fn returns_an_error_when_exporting_to_multiple_formats() {
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("--export-arrow"),
+            String::from("listing.arrow"),
+            String::from("--export-parquet"),
+            String::from("listing.parquet"),
+        ];
+        assert!(build(args).is_err());
+    }
+
+    #[test]
+    fn stats_subcommand_selects_stats_report_for_target() {
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("stats"),
+            String::from("/opt/dev"),
+        ];
+        let config = build(args).unwrap();
+        assert!(matches!(config.report, Some(Report::Stats)));
+        assert_eq!(config.target, "/opt/dev");
+    }
+
+    #[test]
+    fn stats_is_only_a_subcommand_in_first_position() {
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("-l"),
+            String::from("stats"),
+        ];
+        let config = build(args).unwrap();
+        assert!(config.report.is_none());
+        assert_eq!(config.target, "stats");
+    }
+
+    #[test]
+    fn find_subcommand_extracts_pattern_and_target() {
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("find"),
+            String::from("*.rs"),
+            String::from("-l"),
+            String::from("/opt/dev"),
+        ];
+        let config = build(args).unwrap();
+        assert!(config.find_pattern.unwrap().matches("lib.rs"));
+        assert!(config.extended_attributes);
+        assert_eq!(config.target, "/opt/dev");
+    }
+
+    #[test]
+    fn find_pattern_may_follow_flags() {
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("find"),
+            String::from("-l"),
+            String::from("--sort"),
+            String::from("size"),
+            String::from("*.rs"),
+            String::from("/opt/dev"),
+        ];
+        let config = build(args).unwrap();
+        assert!(config.find_pattern.unwrap().matches("lib.rs"));
+        assert!(config.extended_attributes);
+        assert_eq!(config.target, "/opt/dev");
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("find"),
+            String::from("-l"),
+        ];
+        let error = build(args).err().unwrap();
+        assert_eq!(error.to_string(), "the find subcommand requires a pattern");
+    }
+
+    #[test]
+    fn extracts_dir_grouping_to_config() {
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("--group-dirs"),
+            String::from("none"),
+        ];
+        let config = build(args).unwrap();
+        assert_eq!(config.dir_grouping, DirGrouping::Mixed);
+        let config = build(vec![String::from("./mini-ls")]).unwrap();
+        assert_eq!(config.dir_grouping, DirGrouping::Last);
+    }
+
+    #[test]
+    fn returns_an_error_for_unknown_dir_grouping() {
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("--group-dirs"),
+            String::from("middle"),
+        ];
+        let error = build(args).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "--group-dirs expects one of first, last, none but got middle"
+        );
+    }
+
+    #[test]
+    fn serve_subcommand_extracts_target_and_port() {
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("serve"),
+            String::from("~/dev"),
+            String::from("--port"),
+            String::from("9000"),
+        ];
+        let config = build(args).unwrap();
+        assert_eq!(config.target, "~/dev");
+        assert_eq!(config.serve_port, Some(9000));
+        assert_eq!(config.serve_address, Some(DEFAULT_ADDRESS));
+        let args = vec![String::from("./mini-ls"), String::from("serve")];
+        assert_eq!(build(args).unwrap().serve_port, Some(8080));
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("serve"),
+            String::from("--bind"),
+            String::from("0.0.0.0"),
+        ];
+        let config = build(args).unwrap();
+        assert_eq!(config.serve_address, Some("0.0.0.0".parse().unwrap()));
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("serve"),
+            String::from("--bind"),
+            String::from("everywhere"),
+        ];
+        let error = build(args).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "--bind expects an IP address such as 127.0.0.1 or 0.0.0.0 but got everywhere"
+        );
+    }
+
+    #[test]
+    fn port_is_only_accepted_by_serve() {
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("--port"),
+            String::from("9000"),
+        ];
+        let error = build(args).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "--port can only be used with the serve subcommand"
+        );
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("serve"),
+            String::from("--port"),
+            String::from("70000"),
+        ];
+        let error = build(args).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "--port expects a port between 1 and 65535 but got 70000"
+        );
+    }
+
+    #[test]
+    fn index_and_query_subcommands_share_the_socket_flag() {
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("index"),
+            String::from("/srv/tree"),
+            String::from("--socket"),
+            String::from("/tmp/tree.sock"),
+        ];
+        let config = build(args).unwrap();
+        assert_eq!(config.target, "/srv/tree");
+        assert_eq!(
+            config.index_mode,
+            Some(IndexMode::Daemon {
+                socket: PathBuf::from("/tmp/tree.sock")
+            })
+        );
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("query"),
+            String::from("list src"),
+            String::from("--socket"),
+            String::from("/tmp/tree.sock"),
+        ];
+        let config = build(args).unwrap();
+        assert_eq!(
+            config.index_mode,
+            Some(IndexMode::Query {
+                socket: PathBuf::from("/tmp/tree.sock"),
+                request: String::from("list src"),
+            })
+        );
+    }
+
+    #[test]
+    fn extracts_locale_to_config() {
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("--locale"),
+            String::from("fr_FR.UTF-8"),
+        ];
+        let config = build(args).unwrap();
+        assert_eq!(config.locale, Locale::parse("fr_FR").unwrap());
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("--locale"),
+            String::from("tlh"),
+        ];
+        let error = build(args).err().unwrap();
+        assert_eq!(error.to_string(), "tlh is not a supported locale");
+    }
+
+    #[test]
+    fn find_subcommand_requires_a_pattern() {
+        let args = vec![String::from("./mini-ls"), String::from("find")];
+        let error = build(args).err().unwrap();
+        assert_eq!(error.to_string(), "the find subcommand requires a pattern");
+    }
+
+    #[test]
+    fn returns_an_error_when_histogram_combined_with_another_report() {
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("--histogram"),
+            String::from("--by-extension"),
+        ];
+        let error = build(args).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "only one report mode can be used at once"
+        );
+    }
+
+    #[test]
+    fn returns_an_error_when_multiple_reports_requested() {
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("--oldest"),
+            String::from("3"),
+            String::from("--largest"),
+            String::from("3"),
+        ];
+        assert!(build(args).is_err());
+    }
+
+    #[test]
+    fn extracts_extension_breakdown_report_to_config() {
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("--by-extension"),
+            String::from("/opt/dev"),
+        ];
+        let config = build(args).unwrap();
+        assert!(matches!(config.report, Some(Report::ByExtension)));
+        assert_eq!(config.target, "/opt/dev");
+    }
+
+    #[test]
+    fn last_grid_flag_decides_the_order() {
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("-C"),
+            String::from("-x"),
+        ];
+        let config = build(args).unwrap();
+        assert_eq!(config.grid, Some(GridOrder::Across));
+        let args = vec![String::from("./mini-ls"), String::from("-xC")];
+        let config = build(args).unwrap();
+        assert_eq!(config.grid, Some(GridOrder::Down));
     }
 
     #[test]
-    fn target_dir_is_working_dir_if_un_supplied_with_args() {
+    fn dired_cannot_be_combined_with_json() {
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("--dired"),
+            String::from("--json"),
+        ];
+        let error = build(args).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "only one output format can be used at once"
+        );
+    }
+
+    #[test]
+    fn repeat_header_requires_a_positive_count() {
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("--repeat-header"),
+            String::from("25"),
+        ];
+        assert_eq!(build(args).unwrap().repeat_header, Some(25));
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("--repeat-header"),
+            String::from("0"),
+        ];
+        assert!(build(args).is_err());
+    }
+
+    #[test]
+    fn extracts_csv_dialect_to_config() {
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("--csv"),
+            String::from("--csv-delimiter"),
+            String::from("semicolon"),
+            String::from("--no-csv-header"),
+        ];
+        let config = build(args).unwrap();
+        let expected = CsvDialect {
+            delimiter: ';',
+            quoting: CsvQuoting::Minimal,
+            header: false,
+        };
+        assert_eq!(config.machine_format, Some(MachineFormat::Csv(expected)));
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("--csv-quoting"),
+            String::from("all"),
+        ];
+        let error = build(args).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "--csv-quoting can only be used together with --csv"
+        );
+    }
+
+    #[test]
+    fn extracts_column_separator_to_config() {
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("-l"),
+            String::from("--separator"),
+            String::from(" | "),
+            String::from("/opt/dev"),
+        ];
+        let config = build(args).unwrap();
+        assert_eq!(config.column_separator.as_deref(), Some(" | "));
+        assert_eq!(config.target, "/opt/dev");
+    }
+
+    #[test]
+    fn rejects_unknown_encodings() {
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("--encoding"),
+            String::from("latin-1"),
+        ];
+        let error = build(args).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "--encoding expects one of utf-8, utf-8-bom, utf-16le but got latin-1"
+        );
+    }
+
+    #[test]
+    fn missing_output_directory_is_reported_unless_mkdir_given() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let target = temp_dir.path().join("missing").join("log.txt");
         let args = vec![
             String::from("./mini-ls"),
             String::from("-F"),
-            String::from("log.txt"),
+            target.to_str().unwrap().to_string(),
         ];
-        let config = Config::build(args).unwrap();
-        assert_eq!(config.target, "./");
+        let error = build(args.clone()).err().unwrap();
+        assert!(matches!(
+            error,
+            ArgParsingError::MissingOutputDirectory { .. }
+        ));
+        let mut args = args;
+        args.push(String::from("--mkdir"));
+        assert!(build(args).unwrap().create_output_dirs);
     }
 
     #[test]
-    fn target_dir_is_working_dir_if_un_supplied_with_no_args() {
-        let args = vec![String::from("./mini-ls")];
-        let config = Config::build(args).unwrap();
-        assert_eq!(config.target, "./");
+    fn refuses_to_overwrite_without_force() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let target = temp_dir.path().join("log.txt");
+        std::fs::write(&target, "").unwrap();
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("-F"),
+            target.to_str().unwrap().to_string(),
+        ];
+        let error = build(args.clone()).err().unwrap();
+        assert!(matches!(error, ArgParsingError::OutputFileExists { .. }));
+        let mut args = args;
+        args.push(String::from("--force"));
+        assert!(build(args).is_ok());
     }
 
     #[test]
-    fn config_includes_extended_arg_if_passed() {
-        let args = vec![String::from("./mini-ls"), String::from("-l")];
-        let config = Config::build(args).unwrap();
+    fn dash_file_target_means_standard_output() {
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("-F"),
+            String::from("-"),
+            String::from("/opt/dev"),
+        ];
+        let config = build(args).unwrap();
+        assert!(config.to_file);
+        assert_eq!(config.target_file, "-");
+        assert_eq!(config.target, "/opt/dev");
+    }
+
+    #[test]
+    fn dumps_the_resolved_config() {
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("-l"),
+            String::from("--group-dirs"),
+            String::from("first"),
+            String::from("--dump-config"),
+            String::from("/opt/dev"),
+        ];
+        let config = build(args).unwrap();
+        assert!(config.dump_config);
+        let dumped: toml::Table = toml::from_str(&config.dump().unwrap()).unwrap();
+        assert_eq!(dumped["target"].as_str(), Some("/opt/dev"));
+        assert_eq!(dumped["extended_attributes"].as_bool(), Some(true));
+        assert_eq!(dumped["dir_grouping"].as_str(), Some("first"));
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("--json"),
+            String::from("--dump-config"),
+        ];
+        let dumped: serde_json::Value =
+            serde_json::from_str(&build(args).unwrap().dump().unwrap()).unwrap();
+        assert_eq!(dumped["machine_format"], "json");
+    }
+
+    #[test]
+    fn extracts_config_subcommand_actions() {
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("config"),
+            String::from("validate"),
+        ];
+        let config = build(args).unwrap();
+        assert_eq!(config.config_action, Some(ConfigAction::Validate));
+        let args = vec![String::from("./mini-ls"), String::from("config")];
+        let error = build(args).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "the config subcommand requires a subcommand of init or validate"
+        );
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("config"),
+            String::from("check"),
+        ];
+        let error = build(args).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "the config subcommand expects one of init, validate but got check"
+        );
+    }
+
+    #[test]
+    fn words_after_end_of_options_are_never_subcommands() {
+        for command in SUBCOMMANDS {
+            let args = vec![
+                String::from("./mini-ls"),
+                String::from("--"),
+                String::from(command),
+            ];
+            let config = build(args).unwrap();
+            assert_eq!(config.config_action, None);
+            assert!(config.report.is_none());
+            assert!(config.index_mode.is_none());
+            assert!(config.find_pattern.is_none());
+            assert_eq!(config.target, command);
+        }
+    }
+
+    #[test]
+    fn gnu_flags_map_onto_mini_ls_options() {
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("-lat"),
+            String::from("--all"),
+            String::from("/opt/dev"),
+        ];
+        let config = build(args).unwrap();
+        assert!(config.extended_attributes);
+        assert!(config.all);
+        assert_eq!(config.sort, Some(SortKey::Modified));
+        assert_eq!(config.target, "/opt/dev");
+        let args = vec![String::from("./mini-ls"), String::from("-lhS")];
+        let config = build(args).unwrap();
         assert!(config.extended_attributes);
+        assert!(!config.all);
+        assert!(!config.dereference);
+        assert_eq!(config.sort, Some(SortKey::Size));
+        let args = vec![String::from("./mini-ls"), String::from("-1v")];
+        let config = build(args).unwrap();
+        assert!(config.single_column);
+        assert_eq!(config.sort, Some(SortKey::Version));
     }
 
     #[test]
-    fn config_includes_l_arg_if_concatenated() {
+    fn values_of_flags_are_not_taken_for_gnu_flags() {
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("--format"),
+            String::from("-v"),
+            String::from("-t"),
+        ];
+        let config = build(args).unwrap();
+        assert!(config.template.is_some());
+        assert_eq!(config.sort, Some(SortKey::Modified));
         let args = vec![
             String::from("./mini-ls"),
             String::from("-lF"),
-            String::from("log.txt"),
+            String::from("-t"),
+        ];
+        let config = build(args).unwrap();
+        assert_eq!(config.target_file, "-t");
+        assert_eq!(config.sort, None);
+    }
+
+    #[test]
+    fn short_sort_flag_takes_a_key() {
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("-ls"),
+            String::from("created"),
         ];
-        let config = Config::build(args).unwrap();
+        let config = build(args).unwrap();
         assert!(config.extended_attributes);
+        assert_eq!(config.sort, Some(SortKey::Created));
+        let args = vec![String::from("./mini-ls"), String::from("-s=size")];
+        assert_eq!(build(args).unwrap().sort, Some(SortKey::Size));
     }
 
     #[test]
-    fn finds_all_flags_and_options() {
+    fn reverse_flag_combines_with_sort_keys() {
+        let args = vec![String::from("./mini-ls"), String::from("-ltr")];
+        let config = build(args).unwrap();
+        assert!(config.extended_attributes);
+        assert!(config.reverse);
+        assert_eq!(config.sort, Some(SortKey::Modified));
+        let args = vec![String::from("./mini-ls"), String::from("--reverse")];
+        let config = build(args).unwrap();
+        assert!(config.reverse);
+        assert_eq!(config.sort, None);
+    }
+
+    #[test]
+    fn glob_targets_split_into_directory_and_pattern() {
+        let args = vec![String::from("./mini-ls"), String::from("src/*.rs")];
+        let config = build(args).unwrap();
+        assert_eq!(config.target, "src");
+        assert!(config.glob.as_ref().unwrap().matches("lib.rs"));
+        assert!(!config.glob.unwrap().matches("Cargo.toml"));
+        let args = vec![String::from("./mini-ls"), String::from("*.txt")];
+        let config = build(args).unwrap();
+        assert_eq!(config.target, "./");
+        assert!(config.glob.unwrap().matches("notes.txt"));
         let args = vec![
             String::from("./mini-ls"),
-            String::from("-lF"),
-            String::from("log.txt"),
+            String::from("--glob"),
+            String::from("lib"),
+        ];
+        let config = build(args).unwrap();
+        assert_eq!(config.target, "./");
+        assert!(!config.glob.unwrap().matches("lib.rs"));
+        let args = vec![String::from("./mini-ls"), String::from("--glob=[a")];
+        assert!(matches!(
+            build(args),
+            Err(ArgParsingError::InvalidPattern { .. })
+        ));
+    }
+
+    #[test]
+    fn match_takes_a_regular_expression() {
+        let args = vec![String::from("./mini-ls"), String::from("--match=^v\\d+$")];
+        let name_match = build(args).unwrap().name_match.unwrap();
+        assert!(name_match.matches("v12"));
+        assert!(!name_match.matches("v12.bak"));
+        let args = vec![String::from("./mini-ls"), String::from("--match=(unclosed")];
+        assert!(matches!(
+            build(args),
+            Err(ArgParsingError::InvalidRegex { .. })
+        ));
+    }
+
+    #[test]
+    fn time_formats_are_checked_when_parsed() {
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("--time-format=%d %b"),
+        ];
+        let config = build(args).unwrap();
+        assert_eq!(config.time_format.as_deref(), Some("%d %b"));
+        let args = vec![String::from("./mini-ls"), String::from("--time-format=%Q")];
+        assert!(matches!(
+            build(args),
+            Err(ArgParsingError::InvalidTimeFormat { .. })
+        ));
+    }
+
+    #[test]
+    fn depth_limits_the_tree() {
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("--tree"),
+            String::from("--depth=2"),
+        ];
+        let config = build(args).unwrap();
+        assert!(config.tree);
+        assert_eq!(config.depth, Some(2));
+        let args = vec![String::from("./mini-ls"), String::from("--depth=2")];
+        assert_eq!(
+            build(args).err().unwrap().to_string(),
+            "--depth can only be used together with --tree or --recursive"
+        );
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("-R"),
+            String::from("--depth=1"),
+        ];
+        assert_eq!(build(args).unwrap().depth, Some(1));
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("--tree"),
+            String::from("--depth=0"),
+        ];
+        assert!(matches!(
+            build(args),
+            Err(ArgParsingError::InvalidCount { .. })
+        ));
+    }
+
+    #[test]
+    fn color_defaults_to_auto_and_rejects_unknown_modes() {
+        let args = vec![String::from("./mini-ls")];
+        assert_eq!(build(args).unwrap().color, ColorMode::Auto);
+        let args = vec![String::from("./mini-ls"), String::from("--color=never")];
+        assert_eq!(build(args).unwrap().color, ColorMode::Never);
+        let args = vec![String::from("./mini-ls"), String::from("--color=rainbow")];
+        assert_eq!(
+            build(args).err().unwrap().to_string(),
+            "--color expects one of auto, always, never but got rainbow"
+        );
+    }
+
+    #[test]
+    fn single_column_flag_combines_with_others() {
+        let args = vec![String::from("./mini-ls"), String::from("-1a")];
+        let config = build(args).unwrap();
+        assert!(config.single_column);
+        assert!(config.all);
+        let args = vec![String::from("./mini-ls"), String::from("--single-column")];
+        assert!(build(args).unwrap().single_column);
+    }
+
+    #[test]
+    fn file_flag_has_a_long_form() {
+        for args in [
+            vec!["./mini-ls", "--file", "log.txt", "--extended"],
+            vec!["./mini-ls", "--file=log.txt", "-l"],
+            vec!["./mini-ls", "-lF", "log.txt"],
+        ] {
+            let args: Vec<String> = args.into_iter().map(String::from).collect();
+            let config = build(args).unwrap();
+            assert!(config.to_file);
+            assert_eq!(config.target_file, "log.txt");
+            assert!(config.extended_attributes);
+        }
+    }
+
+    #[test]
+    fn help_skips_everything_else_and_lists_every_flag() {
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("--sort=bogus"),
+            String::from("--help"),
+        ];
+        let config = build(args).unwrap();
+        assert!(config.show_help);
+        let help = help_text();
+        for (name, ..) in LONG_FLAGS {
+            assert!(help.contains(&format!("--{}", name)), "{} is missing", name);
+        }
+        assert!(help.contains("  -l, --extended  "));
+        assert!(help.contains("  -F, --file PATH  "));
+        let footer = help.lines().last().unwrap();
+        for (alias, _) in GNU_ALIASES
+            .iter()
+            .filter(|(alias, _)| !alias.starts_with("--"))
+        {
+            assert!(footer.contains(alias), "{} is missing", alias);
+        }
+    }
+
+    #[test]
+    fn completions_take_a_shell_and_cover_every_flag() {
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("--completions"),
+            String::from("fish"),
         ];
-        let config = Config::build(args).unwrap();
+        assert_eq!(build(args).unwrap().completions, Some(Shell::Fish));
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("--completions=tcsh"),
+        ];
+        assert!(matches!(
+            build(args),
+            Err(ArgParsingError::InvalidChoice { .. })
+        ));
+        let script = completion_script(Shell::Bash);
+        for (name, ..) in LONG_FLAGS {
+            assert!(
+                script.contains(&format!("--{}", name)),
+                "{} is missing",
+                name
+            );
+        }
+        assert!(script.contains("compgen -W \"find stats serve index query config doctor\""));
+    }
+
+    #[test]
+    fn dereference_flag_combines_with_extended() {
+        let args = vec![String::from("./mini-ls"), String::from("-lL")];
+        let config = build(args).unwrap();
         assert!(config.extended_attributes);
-        assert!(config.to_file);
-        assert_eq!(config.target_file, "log.txt");
+        assert!(config.dereference);
+        let args = vec![String::from("./mini-ls"), String::from("--dereference")];
+        assert!(build(args).unwrap().dereference);
     }
 
     #[test]
-    fn finds_all_flags_options_when_separated() {
+    fn extracts_compat_style_to_config() {
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("--compat"),
+            String::from("gnu"),
+        ];
+        let config = build(args).unwrap();
+        assert_eq!(config.compat, Some(CompatStyle::Gnu));
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("--compat"),
+            String::from("gnu"),
+            String::from("--csv"),
+        ];
+        assert!(build(args).is_err());
+    }
+
+    #[test]
+    fn layers_apply_in_precedence_order_and_record_their_source() {
+        let config_file: ConfigFile = toml::from_str(
+            "[defaults]\nextended = true\nsort = \"size\"\ncsv = true\n\n[profiles.quiet]\ncsv = false\n",
+        )
+        .unwrap();
+        let variable = |name: &str| match name {
+            "MINI_LS_SORT" => Some(String::from("modified")),
+            "MINI_LS_PROFILE" => Some(String::from("quiet")),
+            _ => None,
+        };
+        let command_line =
+            parse_flags(&[String::from("./mini-ls"), String::from("--no-extended")]).unwrap();
+        let (flags, sources) = resolve_layers(&config_file, command_line, variable).unwrap();
+        assert!(!is_flag_present(&flags, AllowedFlags::L));
+        assert!(!is_flag_present(&flags, AllowedFlags::Csv));
+        assert_eq!(parse_sort_key(&flags).unwrap(), Some(SortKey::Modified));
+        assert_eq!(sources["extended"], Source::CommandLine);
+        assert_eq!(sources["csv"], Source::Profile);
+        assert_eq!(sources["sort"], Source::Environment);
+    }
+
+    #[test]
+    fn build_with_reads_only_the_given_config_file_and_variables() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("config.toml");
+        std::fs::write(&path, "[defaults]\nsort = \"size\"\n").unwrap();
+        let args = || vec![String::from("./mini-ls")];
+        let config = Config::build_with(args(), Some(&path), |_| None).unwrap();
+        assert_eq!(config.sort, Some(SortKey::Size));
+        let variable = |name: &str| (name == "MINI_LS_SORT").then(|| String::from("created"));
+        let config = Config::build_with(args(), Some(&path), variable).unwrap();
+        assert_eq!(config.sort, Some(SortKey::Created));
+        std::fs::write(&path, "[defaults\n").unwrap();
+        assert!(matches!(
+            Config::build_with(args(), Some(&path), |_| None),
+            Err(ArgParsingError::InvalidConfigFile { .. })
+        ));
+        assert_eq!(build(args()).unwrap().sort, None);
+    }
+
+    #[test]
+    fn options_variable_is_overridden_by_single_settings_and_the_command_line() {
+        let variable = |name: &str| match name {
+            "MINI_LS_OPTS" => Some(String::from("-lr  --sort size -t")),
+            "MINI_LS_WIDTH" => Some(String::from("100")),
+            "MINI_LS_REVERSE" => Some(String::from("false")),
+            _ => None,
+        };
+        let command_line =
+            parse_flags(&[String::from("./mini-ls"), String::from("--width=60")]).unwrap();
+        let (flags, sources) =
+            resolve_layers(&ConfigFile::default(), command_line, variable).unwrap();
+        assert!(is_flag_present(&flags, AllowedFlags::L));
+        assert!(!is_flag_present(&flags, AllowedFlags::Reverse));
+        assert_eq!(parse_sort_key(&flags).unwrap(), Some(SortKey::Modified));
+        assert_eq!(
+            parse_count_flag(&flags, AllowedFlags::Width, "width").unwrap(),
+            Some(60)
+        );
+        assert_eq!(sources["extended"], Source::Environment);
+        let variable = |name: &str| (name == "MINI_LS_OPTS").then(|| String::from("-l src"));
+        assert!(matches!(
+            resolve_layers(&ConfigFile::default(), vec![], variable),
+            Err(ArgParsingError::InvalidSetting { .. })
+        ));
+    }
+
+    #[test]
+    fn command_line_output_file_beats_the_environment() {
+        let variable = |name: &str| (name == "MINI_LS_FILE").then(|| String::from("env.out"));
         let args = vec![
             String::from("./mini-ls"),
-            String::from("-l"),
             String::from("-F"),
-            String::from("log.txt"),
-            String::from("/opt/dev"),
+            String::from("cli.out"),
         ];
-        let config = Config::build(args).unwrap();
+        let config = Config::build_with(args, None, variable).unwrap();
+        assert_eq!(config.target_file, "cli.out");
+        let config = Config::build_with(vec![String::from("./mini-ls")], None, variable).unwrap();
+        assert_eq!(config.target_file, "env.out");
+    }
+
+    #[test]
+    fn options_variable_is_split_with_shell_quoting() {
+        let build_with_options = |options: &'static str| {
+            let variable = move |name: &str| (name == "MINI_LS_OPTS").then(|| options.to_string());
+            Config::build_with(vec![String::from("./mini-ls")], None, variable)
+        };
+        let config = build_with_options("--format '{name} {size}' -l").unwrap();
+        assert!(config.template.is_some());
         assert!(config.extended_attributes);
-        assert!(config.to_file);
-        assert_eq!(config.target_file, "log.txt");
-        assert_eq!(config.target, "/opt/dev");
+        let error = |options: &'static str| build_with_options(options).err().unwrap().to_string();
+        assert!(error("--format {name} {size}").contains("`{size}` is not a flag; quote values"));
+        assert!(error("--format '{name}").contains("a quote is never closed"));
     }
 
     #[test]
-    fn still_extracts_all_flags_in_concat_block() {
+    fn icon_style_and_color_default_from_the_config_file() {
+        let config_file: ConfigFile =
+            toml::from_str("[defaults]\nicons = \"ascii\"\ncolor = \"never\"\n").unwrap();
+        let (flags, _) = resolve_layers(&config_file, vec![], |_| None).unwrap();
+        assert_eq!(parse_icon_style(&flags).unwrap(), IconStyle::Ascii);
+        assert_eq!(parse_color_mode(&flags).unwrap(), ColorMode::Never);
+        let command_line =
+            parse_flags(&[String::from("./mini-ls"), String::from("--icons=emoji")]).unwrap();
+        let (flags, _) = resolve_layers(&config_file, command_line, |_| None).unwrap();
+        assert_eq!(parse_icon_style(&flags).unwrap(), IconStyle::Emoji);
+        let args = vec![String::from("./mini-ls"), String::from("--icons=none")];
+        assert!(matches!(
+            build(args),
+            Err(ArgParsingError::InvalidChoice { .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_unknown_settings_in_layers() {
+        let config_file: ConfigFile = toml::from_str("[defaults]\ncolour = true\n").unwrap();
+        let error = resolve_layers(&config_file, vec![], |_| None)
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "invalid setting in the config file: there is no --colour flag"
+        );
+        let variable = |name: &str| (name == "MINI_LS_CSV").then(|| String::from("maybe"));
+        let error = resolve_layers(&ConfigFile::default(), vec![], variable)
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "invalid setting in MINI_LS_CSV: expected true or false but got maybe"
+        );
+    }
+
+    #[test]
+    fn page_size_needs_json_output_and_cursors_must_decode() {
         let args = vec![
             String::from("./mini-ls"),
-            String::from("-lFlog.txt"),
+            String::from("--json"),
+            String::from("--page-size"),
+            String::from("50"),
+            String::from("--cursor"),
+            String::from("612f62"),
+        ];
+        let config = build(args).unwrap();
+        let pagination = config.pagination.unwrap();
+        assert_eq!(pagination.page_size, 50);
+        assert_eq!(pagination.after.as_deref(), Some("a/b"));
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("--page-size"),
+            String::from("50"),
+        ];
+        let error = build(args).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "--page-size can only be used together with --json or --ndjson"
+        );
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("--ndjson"),
+            String::from("--page-size=5"),
+            String::from("--cursor=nothex"),
+        ];
+        assert!(matches!(
+            build(args),
+            Err(ArgParsingError::InvalidCursor { .. })
+        ));
+    }
+
+    #[test]
+    fn highlight_thresholds_are_parsed_and_checked() {
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("--highlight-older-than"),
+            String::from("90d"),
+            String::from("--highlight-larger-than=1G"),
+        ];
+        let config = build(args).unwrap();
+        assert_eq!(config.highlights.older_than.unwrap().text, "90d");
+        assert_eq!(config.highlights.larger_than.unwrap().limit, 1 << 30);
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("--highlight-older-than=soon"),
+        ];
+        let error = build(args).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "--highlight-older-than expects a value such as 90d but got soon"
+        );
+    }
+
+    #[test]
+    fn negated_flags_follow_last_one_wins() {
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("-l"),
+            String::from("--no-extended"),
+        ];
+        assert!(!build(args).unwrap().extended_attributes);
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("--no-extended"),
+            String::from("--extended"),
+        ];
+        assert!(build(args).unwrap().extended_attributes);
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("-lt"),
+            String::from("--no-sort"),
             String::from("/opt/dev"),
         ];
-        let config = Config::build(args).unwrap();
-        assert!(config.to_file);
+        let config = build(args).unwrap();
+        assert_eq!(config.sort, None);
         assert!(config.extended_attributes);
-        assert_eq!(config.target_file, "log.txt");
         assert_eq!(config.target, "/opt/dev");
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("-t"),
+            String::from("-S"),
+        ];
+        assert_eq!(build(args).unwrap().sort, Some(SortKey::Size));
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("-F"),
+            String::from("a.out"),
+            String::from("--file"),
+            String::from("b.out"),
+        ];
+        assert_eq!(build(args).unwrap().target_file, "b.out");
+    }
+
+    #[test]
+    fn option_taking_flags_accept_values_in_blocks_and_after_equals() {
+        for block in ["-lFlog.txt", "-lF=log.txt"] {
+            let args = vec![
+                String::from("./mini-ls"),
+                String::from(block),
+                String::from("/opt/dev"),
+            ];
+            let config = build(args).unwrap();
+            assert!(config.extended_attributes);
+            assert_eq!(config.target_file, "log.txt");
+            assert_eq!(config.target, "/opt/dev");
+        }
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("-F=log.txt"),
+            String::from("--sort=size"),
+        ];
+        let config = build(args).unwrap();
+        assert_eq!(config.target_file, "log.txt");
+        assert_eq!(config.sort, Some(SortKey::Size));
+        assert_eq!(config.target, "./");
     }
 
-    //duplicate options generated where multiple flags with options in block (NYI)
+    #[test]
+    fn rejects_values_for_switches_and_unknown_block_letters() {
+        let args = vec![String::from("./mini-ls"), String::from("--csv=yes")];
+        let error = build(args).err().unwrap();
+        assert_eq!(error.to_string(), "--csv does not take a value");
+        let args = vec![String::from("./mini-ls"), String::from("-lz")];
+        let error = build(args).err().unwrap();
+        assert_eq!(error.to_string(), "unexpected argument provided of z");
+    }
 }
@@ -0,0 +1,117 @@
+use crate::entries::FileEntry;
+use crate::export::millis_since_epoch;
+use crate::machine_output::SCHEMA_VERSION;
+use crate::FileEntryParsingError;
+use arrow_array::{
+    ArrayRef, BooleanArray, RecordBatch, StringArray, TimestampMillisecondArray, UInt32Array,
+    UInt64Array,
+};
+use arrow_ipc::writer::FileWriter;
+use arrow_schema::{ArrowError, DataType, Field, Schema, TimeUnit};
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Writes the entries as an Arrow IPC file, which is also readable as Feather v2.
+pub(crate) fn export(entries: &[FileEntry], target: &Path) -> Result<(), FileEntryParsingError> {
+    write_entries(entries, target).map_err(|error| FileEntryParsingError::ExportFailed {
+        target: target.to_string_lossy().to_string(),
+        reason: error.to_string(),
+    })
+}
+
+fn write_entries(entries: &[FileEntry], target: &Path) -> Result<(), ArrowError> {
+    let timestamp = DataType::Timestamp(TimeUnit::Millisecond, Some(Arc::from("UTC")));
+    let schema = Arc::new(Schema::new_with_metadata(
+        vec![
+            Field::new("name", DataType::Utf8, false),
+            Field::new("path", DataType::Utf8, false),
+            Field::new("kind", DataType::Utf8, false),
+            Field::new("size", DataType::UInt64, true),
+            Field::new("created", timestamp.clone(), true),
+            Field::new("modified", timestamp, true),
+            Field::new("readonly", DataType::Boolean, true),
+            Field::new("mode", DataType::UInt32, true),
+        ],
+        HashMap::from([(String::from("schema_version"), SCHEMA_VERSION.to_string())]),
+    ));
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(StringArray::from_iter_values(
+            entries.iter().map(|entry| entry.name.as_str()),
+        )),
+        Arc::new(StringArray::from_iter_values(
+            entries.iter().map(|entry| entry.path.to_string_lossy()),
+        )),
+        Arc::new(StringArray::from_iter_values(
+            entries.iter().map(|entry| entry.kind.as_str()),
+        )),
+        Arc::new(UInt64Array::from_iter(
+            entries.iter().map(|entry| entry.size),
+        )),
+        Arc::new(
+            TimestampMillisecondArray::from_iter(
+                entries
+                    .iter()
+                    .map(|entry| entry.created.map(millis_since_epoch)),
+            )
+            .with_timezone("UTC"),
+        ),
+        Arc::new(
+            TimestampMillisecondArray::from_iter(
+                entries
+                    .iter()
+                    .map(|entry| entry.modified.map(millis_since_epoch)),
+            )
+            .with_timezone("UTC"),
+        ),
+        Arc::new(BooleanArray::from_iter(
+            entries.iter().map(|entry| entry.readonly),
+        )),
+        Arc::new(UInt32Array::from_iter(
+            entries.iter().map(|entry| entry.mode),
+        )),
+    ];
+    let batch = RecordBatch::try_new(schema.clone(), columns)?;
+    let mut writer = FileWriter::try_new(File::create(target)?, &schema)?;
+    writer.write(&batch)?;
+    writer.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::Array;
+    use arrow_ipc::reader::FileReader;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn exported_file_round_trips_entries_and_schema_version() {
+        let temp_dir = tempdir().unwrap();
+        fs::write(temp_dir.path().join("file_1.txt"), "1234").unwrap();
+        let entries: Vec<FileEntry> = fs::read_dir(temp_dir.path())
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .map(|entry| FileEntry::from_dir_entry(&entry))
+            .collect();
+        let output = temp_dir.path().join("listing.arrow");
+
+        export(&entries, &output).unwrap();
+
+        let reader = FileReader::try_new(File::open(&output).unwrap(), None).unwrap();
+        assert_eq!(
+            reader.schema().metadata().get("schema_version"),
+            Some(&SCHEMA_VERSION.to_string())
+        );
+        let batches: Vec<RecordBatch> = reader.map(|batch| batch.unwrap()).collect();
+        assert_eq!(batches[0].num_rows(), 1);
+        let sizes = batches[0]
+            .column_by_name("size")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<UInt64Array>()
+            .unwrap();
+        assert_eq!(sizes.value(0), 4);
+    }
+}
@@ -0,0 +1,83 @@
+/// Formatting characters that reorder the text around them, used to make `exe.pdf` appear where
+/// the name really ends in `fdp.exe`.
+const BIDI_CONTROLS: [char; 12] = [
+    '\u{061C}', '\u{200E}', '\u{200F}', '\u{202A}', '\u{202B}', '\u{202C}', '\u{202D}', '\u{202E}',
+    '\u{2066}', '\u{2067}', '\u{2068}', '\u{2069}',
+];
+
+/// Cyrillic and Greek letters drawn the same as a Latin one in most fonts.
+const LATIN_LOOKALIKES: &str = "АВЕКМНОРСТХаеорсухіјѕԁԛԝΑΒΕΖΗΙΚΜΝΟΡΤΥΧοαν";
+
+const EXECUTABLE_EXTENSIONS: [&str; 14] = [
+    "exe", "scr", "bat", "cmd", "com", "pif", "msi", "js", "vbs", "ps1", "jar", "lnk", "sh", "app",
+];
+
+const DOCUMENT_EXTENSIONS: [&str; 14] = [
+    "pdf", "doc", "docx", "xls", "xlsx", "ppt", "pptx", "txt", "rtf", "jpg", "jpeg", "png", "gif",
+    "zip",
+];
+
+/// Short codes for every trick `name` looks to be playing, empty for an innocent name.
+pub(crate) fn suspicious_traits(name: &str) -> Vec<&'static str> {
+    let mut traits = Vec::new();
+    if name.ends_with(char::is_whitespace) {
+        traits.push("space");
+    }
+    if name.chars().any(|letter| BIDI_CONTROLS.contains(&letter)) {
+        traits.push("bidi");
+    }
+    if has_homoglyphs(name) {
+        traits.push("homoglyph");
+    }
+    if has_hidden_extension(name) {
+        traits.push("hidden-ext");
+    }
+    traits
+}
+
+/// Lookalike letters mixed with Latin ones in the same part of the name, or fullwidth forms such
+/// as `ｅｘｅ`. Names written wholly in Cyrillic or Greek are left alone.
+fn has_homoglyphs(name: &str) -> bool {
+    let fullwidth = name
+        .chars()
+        .any(|letter| ('\u{FF01}'..='\u{FF5E}').contains(&letter));
+    fullwidth
+        || name.split('.').any(|part| {
+            part.chars().any(|letter| LATIN_LOOKALIKES.contains(letter))
+                && part.chars().any(|letter| letter.is_ascii_alphabetic())
+        })
+}
+
+/// A document extension followed by an executable one, e.g. `invoice.pdf.exe`, which shows up as
+/// `invoice.pdf` wherever known extensions are hidden.
+fn has_hidden_extension(name: &str) -> bool {
+    let parts: Vec<String> = name
+        .split('.')
+        .map(|part| part.trim().to_lowercase())
+        .collect();
+    match parts.as_slice() {
+        [stem @ .., disguise, extension] if !stem.is_empty() => {
+            DOCUMENT_EXTENSIONS.contains(&disguise.as_str())
+                && EXECUTABLE_EXTENSIONS.contains(&extension.as_str())
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_each_kind_of_disguise() {
+        assert!(suspicious_traits("report.pdf").is_empty());
+        assert!(suspicious_traits("archive.tar.gz").is_empty());
+        assert!(suspicious_traits("привет.txt").is_empty());
+        assert_eq!(suspicious_traits("notes.txt "), vec!["space"]);
+        assert_eq!(suspicious_traits("invoice\u{202E}fdp.exe"), vec!["bidi"]);
+        assert_eq!(suspicious_traits("pаypal.html"), vec!["homoglyph"]);
+        assert_eq!(suspicious_traits("setup.ｅｘｅ"), vec!["homoglyph"]);
+        assert_eq!(suspicious_traits("invoice.pdf.exe"), vec!["hidden-ext"]);
+        assert_eq!(suspicious_traits("invoice.PDF   .Exe"), vec!["hidden-ext"]);
+    }
+}
@@ -0,0 +1,100 @@
+use crate::FileEntryParsingError;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::{Component, Path, PathBuf};
+use std::{fs, io};
+
+pub(crate) fn sha256_file(path: &Path) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher)?;
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect())
+}
+
+/// Formats a `HASH  path` line as sha256sum does, escaping the name and starting the line with a
+/// backslash when the name holds a backslash, newline or carriage return.
+pub(crate) fn manifest_line(hash: &str, path: &Path) -> String {
+    let name = path.display().to_string();
+    if !name.contains(['\\', '\n', '\r']) {
+        return format!("{}  {}", hash, name);
+    }
+    let escaped = name
+        .replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r");
+    format!("\\{}  {}", hash, escaped)
+}
+
+/// Reads `HASH  path` lines as written by sha256sum, including the `HASH *path` binary marker.
+/// A leading backslash marks a name whose backslashes and newlines sha256sum escaped.
+pub(crate) fn read_manifest(
+    manifest: &Path,
+) -> Result<HashMap<PathBuf, String>, FileEntryParsingError> {
+    let contents = match fs::read_to_string(manifest) {
+        Ok(contents) => contents,
+        Err(original_error) => {
+            return Err(FileEntryParsingError::UnableToReadManifest {
+                target: manifest.to_string_lossy().to_string(),
+                original_error: original_error.kind(),
+            })
+        }
+    };
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            let invalid = || FileEntryParsingError::InvalidManifestLine { line: i + 1 };
+            let (escaped, line) = match line.strip_prefix('\\') {
+                Some(rest) => (true, rest),
+                None => (false, line),
+            };
+            let (hash, path) = line
+                .split_once(' ')
+                .filter(|(hash, _)| hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit()))
+                .ok_or_else(invalid)?;
+            let path = path
+                .strip_prefix(' ')
+                .or_else(|| path.strip_prefix('*'))
+                .ok_or_else(invalid)?;
+            let path = if escaped {
+                unescape_name(path).ok_or_else(invalid)?
+            } else {
+                path.to_string()
+            };
+            Ok((normalise_path(Path::new(&path)), hash.to_lowercase()))
+        })
+        .collect()
+}
+
+/// Undoes the `\\`, `\n` and `\r` escapes of an escaped manifest line, none when another
+/// character follows a backslash.
+fn unescape_name(escaped: &str) -> Option<String> {
+    let mut name = String::with_capacity(escaped.len());
+    let mut chars = escaped.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            name.push(c);
+            continue;
+        }
+        name.push(match chars.next()? {
+            '\\' => '\\',
+            'n' => '\n',
+            'r' => '\r',
+            _ => return None,
+        });
+    }
+    Some(name)
+}
+
+/// Drops `.` components so `./dir/file` and `dir/file` refer to the same manifest entry.
+pub(crate) fn normalise_path(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|component| !matches!(component, Component::CurDir))
+        .collect()
+}
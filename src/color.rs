@@ -0,0 +1,125 @@
+use crate::entries::{EntryKind, FileEntry};
+use serde::Serialize;
+
+const RESET: &str = "\x1b[0m";
+const DIRECTORY: &str = "\x1b[1;34m";
+const SYMLINK: &str = "\x1b[1;36m";
+const EXECUTABLE: &str = "\x1b[1;32m";
+
+/// When names are coloured by entry type. `Auto` colours only when writing to a terminal and
+/// `NO_COLOR` is unset.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ColorMode {
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorMode {
+    pub(crate) fn enabled(self, to_terminal: bool) -> bool {
+        match self {
+            ColorMode::Auto => to_terminal && std::env::var_os("NO_COLOR").is_none(),
+            ColorMode::Always => true,
+            ColorMode::Never => false,
+        }
+    }
+}
+
+/// Wraps `text` in the colour for the kind of `entry`, regular files are left plain. Trailing
+/// padding stays outside the escape codes so an underline or background never runs past the name.
+pub(crate) fn paint(entry: &FileEntry, text: &str) -> String {
+    let Some(colour) = colour(entry) else {
+        return text.to_string();
+    };
+    let name = text.trim_end_matches(' ');
+    format!("{}{}{}{}", colour, name, RESET, &text[name.len()..])
+}
+
+fn colour(entry: &FileEntry) -> Option<&'static str> {
+    match entry.kind {
+        EntryKind::Directory => Some(DIRECTORY),
+        EntryKind::Symlink => Some(SYMLINK),
+        _ if is_executable(entry) => Some(EXECUTABLE),
+        _ => None,
+    }
+}
+
+#[cfg(unix)]
+fn is_executable(entry: &FileEntry) -> bool {
+    entry.mode.is_some_and(|mode| mode & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(entry: &FileEntry) -> bool {
+    let extension = entry
+        .path
+        .extension()
+        .map(|extension| extension.to_ascii_lowercase());
+    matches!(
+        extension.as_ref().and_then(|extension| extension.to_str()),
+        Some("exe" | "bat" | "cmd" | "com")
+    )
+}
+
+/// Escape sequences take no room on screen, so widths are worked out without them.
+pub(crate) fn strip(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('\x1b') {
+        stripped.push_str(&rest[..start]);
+        rest = &rest[start..];
+        rest = match rest.find('m') {
+            Some(end) => &rest[end + 1..],
+            None => "",
+        };
+    }
+    stripped + rest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{self, File};
+    use tempfile::tempdir;
+
+    #[test]
+    fn colours_by_kind_and_keeps_padding_plain() {
+        let temp_dir = tempdir().unwrap();
+        File::create(temp_dir.path().join("plain.txt")).unwrap();
+        fs::create_dir(temp_dir.path().join("nested")).unwrap();
+        let entries: Vec<FileEntry> = fs::read_dir(temp_dir.path())
+            .unwrap()
+            .map(|entry| FileEntry::from_dir_entry(&entry.unwrap()))
+            .collect();
+        let entry = |name: &str| entries.iter().find(|entry| entry.name == name).unwrap();
+        assert_eq!(paint(entry("plain.txt"), "plain.txt  "), "plain.txt  ");
+        let painted = paint(entry("nested"), "nested   ");
+        assert_eq!(painted, "\x1b[1;34mnested\x1b[0m   ");
+        assert_eq!(strip(&painted), "nested   ");
+        assert!(!ColorMode::Auto.enabled(false));
+        assert!(ColorMode::Always.enabled(false));
+        assert!(!ColorMode::Never.enabled(true));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn executables_and_symlinks_have_their_own_colours() {
+        use std::os::unix::fs::PermissionsExt;
+        let temp_dir = tempdir().unwrap();
+        let script = temp_dir.path().join("run.sh");
+        File::create(&script).unwrap();
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+        std::os::unix::fs::symlink("run.sh", temp_dir.path().join("link")).unwrap();
+        for entry in fs::read_dir(temp_dir.path()).unwrap() {
+            let entry = FileEntry::from_dir_entry(&entry.unwrap());
+            let expected = if entry.name == "link" {
+                SYMLINK
+            } else {
+                EXECUTABLE
+            };
+            assert!(paint(&entry, "name").starts_with(expected));
+        }
+    }
+}
@@ -0,0 +1,485 @@
+use crate::output_formatting::format_size;
+use crate::{access, audit, mounts};
+use serde::{Deserialize, Serialize};
+use std::fs::Metadata;
+use std::path::Path;
+use unicode_segmentation::UnicodeSegmentation;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OptionalColumn {
+    Octal,
+    Access,
+    Owner,
+    OwnerName,
+    Audit,
+    Mount,
+    Project,
+    Compression,
+    Shared,
+}
+
+/// Optional bounds on a column's width, read from the `[columns]` table of the config file.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct WidthLimit {
+    pub min: Option<usize>,
+    pub max: Option<usize>,
+}
+
+impl WidthLimit {
+    pub(crate) fn apply(&self, natural: usize) -> usize {
+        let capped = self.max.map_or(natural, |max| natural.min(max));
+        self.min.map_or(capped, |min| capped.max(min))
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ColumnLimits {
+    pub name: WidthLimit,
+    pub created: WidthLimit,
+    pub permissions: WidthLimit,
+    pub modified: WidthLimit,
+}
+
+impl OptionalColumn {
+    pub(crate) fn heading(&self) -> &'static str {
+        match self {
+            OptionalColumn::Octal => "Mode",
+            OptionalColumn::Access => "Access",
+            OptionalColumn::Owner | OptionalColumn::OwnerName => "Owner",
+            OptionalColumn::Audit => "Audit",
+            OptionalColumn::Mount => "Mount",
+            OptionalColumn::Project => "Project",
+            OptionalColumn::Compression => "Compression",
+            OptionalColumn::Shared => "Shared",
+        }
+    }
+
+    /// Width of the column including the single space separating it from the next one.
+    pub(crate) fn width(&self) -> usize {
+        match self {
+            OptionalColumn::Octal => 6,
+            OptionalColumn::Access => 7,
+            OptionalColumn::Owner => 13,
+            OptionalColumn::OwnerName => 21,
+            OptionalColumn::Audit => 21,
+            OptionalColumn::Mount => 21,
+            OptionalColumn::Project => 11,
+            OptionalColumn::Compression => 26,
+            OptionalColumn::Shared => 18,
+        }
+    }
+
+    pub(crate) fn render(&self, path: &Path, meta_data: &Metadata) -> String {
+        let value = match self {
+            OptionalColumn::Octal => octal_mode(meta_data),
+            OptionalColumn::Access => access::describe_access(path, meta_data),
+            OptionalColumn::Owner => owner_login(meta_data),
+            OptionalColumn::OwnerName => owner_full_name(meta_data),
+            OptionalColumn::Audit => audit_name(path),
+            OptionalColumn::Mount => mounts::mount_point(path)
+                .map_or_else(|| String::from("-"), |mount| mount.display().to_string()),
+            OptionalColumn::Project => {
+                project_id(path, meta_data).map_or_else(|| String::from("-"), |id| id.to_string())
+            }
+            OptionalColumn::Compression => describe_compression(path, meta_data),
+            OptionalColumn::Shared => shared_bytes(path, meta_data)
+                .filter(|shared| *shared > 0)
+                .map_or_else(
+                    || String::from("-"),
+                    |shared| format!("shared {}", format_size(shared)),
+                ),
+        };
+        let value: String = value.graphemes(true).take(self.width() - 1).collect();
+        let padding = self.width() - 1 - value.graphemes(true).count();
+        value + " ".repeat(padding).as_str()
+    }
+}
+
+fn audit_name(path: &Path) -> String {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+    let traits = audit::suspicious_traits(&name);
+    if traits.is_empty() {
+        String::from("-")
+    } else {
+        traits.join(",")
+    }
+}
+
+/// `struct fsxattr` from `linux/fs.h`.
+#[cfg(target_os = "linux")]
+#[repr(C)]
+#[derive(Default)]
+struct FsXAttr {
+    xflags: u32,
+    extsize: u32,
+    nextents: u32,
+    projid: u32,
+    cowextsize: u32,
+    pad: [u8; 8],
+}
+
+/// `_IOR('X', 31, struct fsxattr)`
+#[cfg(target_os = "linux")]
+const FS_IOC_FSGETXATTR: libc::c_ulong = 0x801c_581f;
+
+/// `_IOR('f', 1, long)`, the kernel only ever writes an int through it.
+#[cfg(target_os = "linux")]
+const FS_IOC_GETFLAGS: libc::c_ulong = 0x8008_6601;
+
+#[cfg(target_os = "linux")]
+const FS_COMPR_FL: libc::c_int = 0x4;
+
+/// Only files and directories are opened for their attributes, a device or FIFO could react to
+/// being opened.
+#[cfg(target_os = "linux")]
+fn open_for_ioctl(path: &Path, meta_data: &Metadata) -> Option<std::fs::File> {
+    use std::fs::OpenOptions;
+    use std::os::unix::fs::OpenOptionsExt;
+    if !meta_data.is_file() && !meta_data.is_dir() {
+        return None;
+    }
+    OpenOptions::new()
+        .read(true)
+        .custom_flags(libc::O_NOFOLLOW | libc::O_NONBLOCK | libc::O_NOCTTY)
+        .open(path)
+        .ok()
+}
+
+/// The project quota id as set by `xfs_quota` or `chattr -p`.
+#[cfg(target_os = "linux")]
+fn project_id(path: &Path, meta_data: &Metadata) -> Option<u32> {
+    use std::os::unix::io::AsRawFd;
+    let handle = open_for_ioctl(path, meta_data)?;
+    let mut attributes = FsXAttr::default();
+    let result = unsafe {
+        libc::ioctl(
+            handle.as_raw_fd(),
+            FS_IOC_FSGETXATTR as _,
+            &mut attributes as *mut FsXAttr,
+        )
+    };
+    (result == 0).then_some(attributes.projid)
+}
+
+/// The algorithm btrfs records in the `btrfs.compression` property, or `yes` when only the
+/// compression attribute from `chattr +c` is set.
+#[cfg(target_os = "linux")]
+fn compression_algorithm(path: &Path, meta_data: &Metadata) -> Option<String> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::io::AsRawFd;
+    let c_path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut value = [0u8; 32];
+    let length = unsafe {
+        libc::lgetxattr(
+            c_path.as_ptr(),
+            c"btrfs.compression".as_ptr(),
+            value.as_mut_ptr() as *mut libc::c_void,
+            value.len(),
+        )
+    };
+    if length > 0 {
+        let algorithm = String::from_utf8_lossy(&value[..length as usize]);
+        return Some(algorithm.trim_end_matches('\0').to_string());
+    }
+    let handle = open_for_ioctl(path, meta_data)?;
+    let mut flags: libc::c_int = 0;
+    let result = unsafe {
+        libc::ioctl(
+            handle.as_raw_fd(),
+            FS_IOC_GETFLAGS as _,
+            &mut flags as *mut libc::c_int,
+        )
+    };
+    (result == 0 && flags & FS_COMPR_FL != 0).then(|| String::from("yes"))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn compression_algorithm(_path: &Path, _meta_data: &Metadata) -> Option<String> {
+    None
+}
+
+/// `struct fiemap_extent` from `linux/fiemap.h`.
+#[cfg(target_os = "linux")]
+#[repr(C)]
+#[derive(Default, Clone, Copy)]
+struct FiemapExtent {
+    logical: u64,
+    physical: u64,
+    length: u64,
+    reserved64: [u64; 2],
+    flags: u32,
+    reserved: [u32; 3],
+}
+
+#[cfg(target_os = "linux")]
+const FIEMAP_BATCH: usize = 32;
+
+/// `struct fiemap` followed by room for a batch of extents.
+#[cfg(target_os = "linux")]
+#[repr(C)]
+#[derive(Default)]
+struct Fiemap {
+    start: u64,
+    length: u64,
+    flags: u32,
+    mapped_extents: u32,
+    extent_count: u32,
+    reserved: u32,
+    extents: [FiemapExtent; FIEMAP_BATCH],
+}
+
+/// `_IOWR('f', 11, struct fiemap)`
+#[cfg(target_os = "linux")]
+const FS_IOC_FIEMAP: libc::c_ulong = 0xc020_660b;
+
+#[cfg(target_os = "linux")]
+const FIEMAP_EXTENT_LAST: u32 = 0x1;
+
+#[cfg(target_os = "linux")]
+const FIEMAP_EXTENT_SHARED: u32 = 0x2000;
+
+/// Bytes of the file held in extents shared with other files, as left behind by reflink copies
+/// and deduplication. Such space is counted in full by every file sharing it.
+#[cfg(target_os = "linux")]
+fn shared_bytes(path: &Path, meta_data: &Metadata) -> Option<u64> {
+    use std::os::unix::io::AsRawFd;
+    if !meta_data.is_file() {
+        return None;
+    }
+    let handle = open_for_ioctl(path, meta_data)?;
+    let mut shared = 0;
+    let mut start = 0;
+    loop {
+        let mut map = Fiemap {
+            start,
+            length: u64::MAX - start,
+            extent_count: FIEMAP_BATCH as u32,
+            ..Fiemap::default()
+        };
+        let result = unsafe {
+            libc::ioctl(
+                handle.as_raw_fd(),
+                FS_IOC_FIEMAP as _,
+                &mut map as *mut Fiemap,
+            )
+        };
+        if result != 0 {
+            return None;
+        }
+        let extents = &map.extents[..map.mapped_extents as usize];
+        let Some(last) = extents.last() else {
+            return Some(shared);
+        };
+        shared += extents
+            .iter()
+            .filter(|extent| extent.flags & FIEMAP_EXTENT_SHARED != 0)
+            .map(|extent| extent.length)
+            .sum::<u64>();
+        if last.flags & FIEMAP_EXTENT_LAST != 0 {
+            return Some(shared);
+        }
+        start = last.logical + last.length;
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn shared_bytes(_path: &Path, _meta_data: &Metadata) -> Option<u64> {
+    None
+}
+
+/// The algorithm where one can be found followed by the space allocated on disk against the
+/// logical size, which is how ZFS shows its compression as it has no per file attribute.
+fn describe_compression(path: &Path, meta_data: &Metadata) -> String {
+    if !meta_data.is_file() {
+        return String::from("-");
+    }
+    let algorithm = compression_algorithm(path, meta_data).unwrap_or_else(|| String::from("-"));
+    format!(
+        "{} {}/{}",
+        algorithm,
+        format_size(allocated_bytes(meta_data)),
+        format_size(meta_data.len())
+    )
+}
+
+#[cfg(unix)]
+fn allocated_bytes(meta_data: &Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    meta_data.blocks() * 512
+}
+
+#[cfg(not(unix))]
+fn allocated_bytes(meta_data: &Metadata) -> u64 {
+    meta_data.len()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn project_id(_path: &Path, _meta_data: &Metadata) -> Option<u32> {
+    None
+}
+
+#[cfg(unix)]
+fn octal_mode(meta_data: &Metadata) -> String {
+    use std::os::unix::fs::PermissionsExt;
+    format_octal_mode(meta_data.permissions().mode())
+}
+
+/// Renders the permission bits of `mode` as four octal digits, e.g. `0755`, the form every
+/// column and machine format shares.
+pub(crate) fn format_octal_mode(mode: u32) -> String {
+    format!("{:04o}", mode & 0o7777)
+}
+
+/// Renders the permission bits of `mode` the way `ls -l` does, e.g. `rwsr-xr-t`.
+#[cfg(unix)]
+pub(crate) fn format_symbolic_mode(mode: u32) -> String {
+    let mut symbols: Vec<char> = [
+        (0o400, 'r'),
+        (0o200, 'w'),
+        (0o100, 'x'),
+        (0o040, 'r'),
+        (0o020, 'w'),
+        (0o010, 'x'),
+        (0o004, 'r'),
+        (0o002, 'w'),
+        (0o001, 'x'),
+    ]
+    .iter()
+    .map(|(bit, symbol)| if mode & bit != 0 { *symbol } else { '-' })
+    .collect();
+    for (special_bit, index, symbol) in [(0o4000, 2, 's'), (0o2000, 5, 's'), (0o1000, 8, 't')] {
+        if mode & special_bit != 0 {
+            symbols[index] = if symbols[index] == 'x' {
+                symbol
+            } else {
+                symbol.to_ascii_uppercase()
+            };
+        }
+    }
+    symbols.into_iter().collect()
+}
+
+#[cfg(not(unix))]
+fn octal_mode(_meta_data: &Metadata) -> String {
+    String::from("-")
+}
+
+#[cfg(unix)]
+fn owner_login(meta_data: &Metadata) -> String {
+    use std::os::unix::fs::MetadataExt;
+    crate::users::user_name(meta_data.uid()).unwrap_or_else(|| meta_data.uid().to_string())
+}
+
+/// Falls back to the login name for accounts without a real name recorded.
+#[cfg(unix)]
+fn owner_full_name(meta_data: &Metadata) -> String {
+    use std::os::unix::fs::MetadataExt;
+    crate::users::user_full_name(meta_data.uid()).unwrap_or_else(|| owner_login(meta_data))
+}
+
+#[cfg(not(unix))]
+fn owner_login(_meta_data: &Metadata) -> String {
+    String::from("-")
+}
+
+#[cfg(not(unix))]
+fn owner_full_name(_meta_data: &Metadata) -> String {
+    String::from("-")
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn width_limits_clamp_the_natural_width() {
+        use super::WidthLimit;
+        let limit = WidthLimit {
+            min: Some(10),
+            max: Some(60),
+        };
+        assert_eq!(limit.apply(80), 60);
+        assert_eq!(limit.apply(4), 10);
+        assert_eq!(limit.apply(30), 30);
+        assert_eq!(WidthLimit::default().apply(80), 80);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn symbolic_mode_includes_special_bits() {
+        use super::format_symbolic_mode;
+        assert_eq!(format_symbolic_mode(0o755), "rwxr-xr-x");
+        assert_eq!(format_symbolic_mode(0o4755), "rwsr-xr-x");
+        assert_eq!(format_symbolic_mode(0o1644), "rw-r--r-T");
+    }
+
+    #[test]
+    fn octal_mode_drops_the_file_type_bits() {
+        use super::format_octal_mode;
+        assert_eq!(format_octal_mode(0o100644), "0644");
+        assert_eq!(format_octal_mode(0o41755), "1755");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn owner_columns_are_padded_to_their_width() {
+        use super::OptionalColumn;
+        let temp_dir = tempfile::tempdir().unwrap();
+        let meta_data = std::fs::metadata(temp_dir.path()).unwrap();
+        for column in [OptionalColumn::Owner, OptionalColumn::OwnerName] {
+            let rendered = column.render(temp_dir.path(), &meta_data);
+            assert_eq!(rendered.chars().count(), column.width() - 1);
+            assert!(!rendered.trim().is_empty());
+        }
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn project_column_shows_an_id_or_a_dash() {
+        use super::{FsXAttr, OptionalColumn};
+        // the request number encodes this size
+        assert_eq!(std::mem::size_of::<FsXAttr>(), 0x1c);
+        let temp_dir = tempfile::tempdir().unwrap();
+        let meta_data = std::fs::metadata(temp_dir.path()).unwrap();
+        let rendered = OptionalColumn::Project.render(temp_dir.path(), &meta_data);
+        assert_eq!(rendered.len(), OptionalColumn::Project.width() - 1);
+        let value = rendered.trim();
+        assert!(value == "-" || value.parse::<u32>().is_ok());
+    }
+
+    #[test]
+    fn compression_column_compares_allocated_and_logical_sizes() {
+        use super::OptionalColumn;
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("data.bin");
+        std::fs::write(&path, vec![7u8; 4096]).unwrap();
+        let meta_data = std::fs::metadata(&path).unwrap();
+        let rendered = OptionalColumn::Compression.render(&path, &meta_data);
+        assert!(rendered.trim_end().ends_with("/4.0 KiB"));
+        let meta_data = std::fs::metadata(temp_dir.path()).unwrap();
+        let rendered = OptionalColumn::Compression.render(temp_dir.path(), &meta_data);
+        assert_eq!(rendered.trim(), "-");
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn shared_column_reads_the_extent_map() {
+        use super::{shared_bytes, FiemapExtent, OptionalColumn};
+        assert_eq!(std::mem::size_of::<FiemapExtent>(), 56);
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("data.bin");
+        std::fs::write(&path, vec![7u8; 64 * 1024]).unwrap();
+        let meta_data = std::fs::metadata(&path).unwrap();
+        // filesystems without FIEMAP, like tmpfs, give no answer at all
+        if let Some(shared) = shared_bytes(&path, &meta_data) {
+            assert_eq!(shared, 0);
+        }
+        let rendered = OptionalColumn::Shared.render(&path, &meta_data);
+        assert_eq!(rendered.trim(), "-");
+    }
+}
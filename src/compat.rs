@@ -0,0 +1,466 @@
+use crate::entries::FileEntry;
+use crate::output_formatting::PathStyle;
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use std::fs::{self, Metadata};
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+const SIX_MONTHS: Duration = Duration::from_secs(183 * 24 * 60 * 60);
+
+/// Output shapes copied from other listing tools for scripts that parse their output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CompatStyle {
+    Gnu,
+    Bsd,
+    Dir,
+}
+
+/// One `ls -l` style line before the columns are padded to a common width.
+struct LongLine {
+    mode: String,
+    links: String,
+    owner: String,
+    group: String,
+    size: String,
+    date: String,
+    name: String,
+    link_target: Option<String>,
+}
+
+/// Renders `entries` in the shape of another tool's long listing, sorted the mini-ls way.
+pub(crate) fn render(
+    style: CompatStyle,
+    target: &Path,
+    entries: &[&FileEntry],
+    path_style: PathStyle,
+) -> String {
+    match style {
+        CompatStyle::Gnu => gnu_long_listing(target, entries, path_style, false),
+        CompatStyle::Bsd => bsd_long_listing(target, entries, path_style),
+        CompatStyle::Dir => dir_listing(target, entries, path_style),
+    }
+}
+
+/// Renders the listing the way `ls --dired -l` does: every line is indented by two spaces and
+/// the output ends with the byte offsets of each name so Emacs dired can find them.
+pub(crate) fn render_dired(target: &Path, entries: &[&FileEntry], path_style: PathStyle) -> String {
+    gnu_long_listing(target, entries, path_style, true)
+}
+
+/// Byte for byte the shape of GNU `ls -l`: mode, links, owner, group, size, date and name.
+fn gnu_long_listing(
+    target: &Path,
+    entries: &[&FileEntry],
+    path_style: PathStyle,
+    dired: bool,
+) -> String {
+    let (total_blocks, lines) = long_lines(target, entries, path_style);
+    let total_kib = total_blocks.div_ceil(2);
+    let links_width = lines.iter().map(|line| line.links.len()).max().unwrap_or(0);
+    let owner_width = lines.iter().map(|line| line.owner.len()).max().unwrap_or(0);
+    let group_width = lines.iter().map(|line| line.group.len()).max().unwrap_or(0);
+    let size_width = lines.iter().map(|line| line.size.len()).max().unwrap_or(0);
+    let indent = if dired { "  " } else { "" };
+
+    let mut output = format!("{}total {}\n", indent, total_kib);
+    let mut offsets = Vec::new();
+    for line in &lines {
+        output += &format!(
+            "{}{} {:>links_width$} {:<owner_width$} {:<group_width$} {:>size_width$} {} ",
+            indent, line.mode, line.links, line.owner, line.group, line.size, line.date
+        );
+        offsets.push(output.len());
+        output += &line.name;
+        offsets.push(output.len());
+        if let Some(target) = &line.link_target {
+            output += " -> ";
+            output += target;
+        }
+        output += "\n";
+    }
+    if !dired {
+        output.pop();
+        return output;
+    }
+    let offsets: Vec<String> = offsets.iter().map(|offset| offset.to_string()).collect();
+    output += &format!("//DIRED// {}\n", offsets.join(" "));
+    output + "//DIRED-OPTIONS// --quoting-style=literal"
+}
+
+/// BSD and macOS `ls -l`: the total counts 512 byte blocks, a column after the mode is kept for
+/// the `@`/`+` markers and owner and group are followed by two spaces.
+fn bsd_long_listing(target: &Path, entries: &[&FileEntry], path_style: PathStyle) -> String {
+    let (total_blocks, lines) = long_lines(target, entries, path_style);
+    let links_width = lines.iter().map(|line| line.links.len()).max().unwrap_or(0);
+    let owner_width = lines.iter().map(|line| line.owner.len()).max().unwrap_or(0);
+    let group_width = lines.iter().map(|line| line.group.len()).max().unwrap_or(0);
+    let size_width = lines.iter().map(|line| line.size.len()).max().unwrap_or(0);
+    let mut output = vec![format!("total {}", total_blocks)];
+    for line in &lines {
+        let mut row = format!(
+            "{}  {:>links_width$} {:<owner_width$}  {:<group_width$}  {:>size_width$} {} {}",
+            line.mode, line.links, line.owner, line.group, line.size, line.date, line.name
+        );
+        if let Some(target) = &line.link_target {
+            row += " -> ";
+            row += target;
+        }
+        output.push(row);
+    }
+    output.join("\n")
+}
+
+/// The cmd.exe `dir` layout. There is no portable volume label or serial number so the volume
+/// lines are left out, everything from ` Directory of` onwards matches.
+fn dir_listing(target: &Path, entries: &[&FileEntry], path_style: PathStyle) -> String {
+    let directory = fs::canonicalize(target).unwrap_or_else(|_| target.to_path_buf());
+    let mut output = vec![
+        String::new(),
+        format!(" Directory of {}", directory.display()),
+        String::new(),
+    ];
+    let dir_row = |modified: Option<SystemTime>, name: &str| {
+        format!("{}    <DIR>          {}", format_dir_date(modified), name)
+    };
+    let target_modified = fs::metadata(target).and_then(|meta| meta.modified()).ok();
+    if directory.parent().is_some() {
+        output.push(dir_row(target_modified, "."));
+        let parent_modified = fs::metadata(directory.join(".."))
+            .and_then(|meta| meta.modified())
+            .ok();
+        output.push(dir_row(parent_modified, ".."));
+    }
+    let (mut file_count, mut dir_count, mut total_bytes) = (0u64, 0u64, 0u64);
+    for entry in entries {
+        let Some(meta_data) = &entry.metadata else {
+            continue;
+        };
+        let name = path_style
+            .label(&entry.path, target)
+            .to_string_lossy()
+            .to_string();
+        let modified = meta_data.modified().ok();
+        if meta_data.is_dir() {
+            dir_count += 1;
+            output.push(dir_row(modified, &name));
+        } else {
+            file_count += 1;
+            total_bytes += meta_data.len();
+            output.push(format!(
+                "{} {:>17} {}",
+                format_dir_date(modified),
+                group_thousands(meta_data.len()),
+                name
+            ));
+        }
+    }
+    if directory.parent().is_some() {
+        dir_count += 2;
+    }
+    output.push(format!(
+        "{:>16} File(s) {:>14} bytes",
+        file_count,
+        group_thousands(total_bytes)
+    ));
+    let free = free_bytes(&directory)
+        .map(|free| format!("{:>14} bytes free", group_thousands(free)))
+        .unwrap_or_default();
+    output.push(
+        format!("{:>16} Dir(s)  {}", dir_count, free)
+            .trim_end()
+            .to_string(),
+    );
+    output.join("\n")
+}
+
+fn format_dir_date(modified: Option<SystemTime>) -> String {
+    match modified {
+        Some(modified) => DateTime::<Local>::from(modified)
+            .format("%m/%d/%Y  %I:%M %p")
+            .to_string(),
+        None => format!("{:20}", ""),
+    }
+}
+
+fn group_thousands(number: u64) -> String {
+    let digits = number.to_string();
+    let mut grouped = String::new();
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+#[cfg(unix)]
+fn free_bytes(directory: &Path) -> Option<u64> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+    let c_path = CString::new(directory.as_os_str().as_bytes()).ok()?;
+    let mut stats: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stats) } != 0 {
+        return None;
+    }
+    Some(stats.f_bavail as u64 * stats.f_frsize as u64)
+}
+
+#[cfg(not(unix))]
+fn free_bytes(_directory: &Path) -> Option<u64> {
+    None
+}
+
+/// The details of each entry along with the total allocated size in 512 byte blocks.
+fn long_lines(
+    target: &Path,
+    entries: &[&FileEntry],
+    path_style: PathStyle,
+) -> (u64, Vec<LongLine>) {
+    let now = SystemTime::now();
+    let mut total_blocks = 0;
+    let mut lines: Vec<LongLine> = Vec::new();
+    for entry in entries {
+        let Some(meta_data) = &entry.metadata else {
+            continue;
+        };
+        total_blocks += allocated_blocks(meta_data);
+        let name = path_style
+            .label(&entry.path, target)
+            .to_string_lossy()
+            .to_string();
+        let link_target = meta_data
+            .file_type()
+            .is_symlink()
+            .then(|| fs::read_link(&entry.path).ok())
+            .flatten()
+            .map(|target| target.to_string_lossy().to_string());
+        lines.push(LongLine {
+            mode: mode_string(meta_data),
+            links: link_count(meta_data).to_string(),
+            owner: owner(meta_data),
+            group: group(meta_data),
+            size: meta_data.len().to_string(),
+            date: format_listing_date(meta_data.modified().ok(), now),
+            name,
+            link_target,
+        });
+    }
+    (total_blocks, lines)
+}
+
+/// Recent files show the time of day, anything older than about six months shows the year.
+fn format_listing_date(modified: Option<SystemTime>, now: SystemTime) -> String {
+    let Some(modified) = modified else {
+        return String::from("?            ");
+    };
+    // Like GNU ls, times in the future show the year too, so clock skew stands out.
+    let recent = now
+        .duration_since(modified)
+        .is_ok_and(|age| age < SIX_MONTHS);
+    let format = if recent { "%b %e %H:%M" } else { "%b %e  %Y" };
+    DateTime::<Local>::from(modified).format(format).to_string()
+}
+
+#[cfg(unix)]
+fn mode_string(meta_data: &Metadata) -> String {
+    use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+    let file_type = meta_data.file_type();
+    let type_char = if file_type.is_dir() {
+        'd'
+    } else if file_type.is_symlink() {
+        'l'
+    } else if file_type.is_block_device() {
+        'b'
+    } else if file_type.is_char_device() {
+        'c'
+    } else if file_type.is_fifo() {
+        'p'
+    } else if file_type.is_socket() {
+        's'
+    } else {
+        '-'
+    };
+    type_char.to_string() + &crate::columns::format_symbolic_mode(meta_data.permissions().mode())
+}
+
+#[cfg(unix)]
+fn link_count(meta_data: &Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    meta_data.nlink()
+}
+
+#[cfg(unix)]
+fn owner(meta_data: &Metadata) -> String {
+    use std::os::unix::fs::MetadataExt;
+    crate::users::user_name(meta_data.uid()).unwrap_or_else(|| meta_data.uid().to_string())
+}
+
+#[cfg(unix)]
+fn group(meta_data: &Metadata) -> String {
+    use std::os::unix::fs::MetadataExt;
+    crate::users::group_name(meta_data.gid()).unwrap_or_else(|| meta_data.gid().to_string())
+}
+
+#[cfg(unix)]
+fn allocated_blocks(meta_data: &Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    meta_data.blocks()
+}
+
+#[cfg(not(unix))]
+fn mode_string(meta_data: &Metadata) -> String {
+    let type_char = if meta_data.is_dir() { 'd' } else { '-' };
+    let write = if meta_data.permissions().readonly() {
+        '-'
+    } else {
+        'w'
+    };
+    format!("{0}r{1}-r{1}-r{1}-", type_char, write)
+}
+
+#[cfg(not(unix))]
+fn link_count(_meta_data: &Metadata) -> u64 {
+    1
+}
+
+#[cfg(not(unix))]
+fn owner(_meta_data: &Metadata) -> String {
+    String::from("-")
+}
+
+#[cfg(not(unix))]
+fn group(_meta_data: &Metadata) -> String {
+    String::from("-")
+}
+
+#[cfg(not(unix))]
+fn allocated_blocks(meta_data: &Metadata) -> u64 {
+    meta_data.len().div_ceil(512)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn offsets_point_at_each_name() {
+        let temp_dir = tempdir().unwrap();
+        fs::write(temp_dir.path().join("a.txt"), "123").unwrap();
+        fs::create_dir(temp_dir.path().join("sub dir")).unwrap();
+        let mut entries: Vec<FileEntry> = fs::read_dir(temp_dir.path())
+            .unwrap()
+            .map(|entry| FileEntry::from_dir_entry(&entry.unwrap()))
+            .collect();
+        entries.sort_by(|left, right| left.name.cmp(&right.name));
+        let output = render_dired(
+            temp_dir.path(),
+            &entries.iter().collect::<Vec<_>>(),
+            PathStyle::Name,
+        );
+        let dired_line = output
+            .lines()
+            .find(|line| line.starts_with("//DIRED// "))
+            .unwrap();
+        let offsets: Vec<usize> = dired_line["//DIRED// ".len()..]
+            .split(' ')
+            .map(|offset| offset.parse().unwrap())
+            .collect();
+        assert_eq!(&output[offsets[0]..offsets[1]], "a.txt");
+        assert_eq!(&output[offsets[2]..offsets[3]], "sub dir");
+        assert!(output.lines().nth(2).unwrap().starts_with("  d"));
+        assert!(output.ends_with("//DIRED-OPTIONS// --quoting-style=literal"));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn gnu_style_matches_ls_long_format() {
+        let temp_dir = tempdir().unwrap();
+        fs::write(temp_dir.path().join("a.txt"), "123").unwrap();
+        let entries: Vec<FileEntry> = fs::read_dir(temp_dir.path())
+            .unwrap()
+            .map(|entry| FileEntry::from_dir_entry(&entry.unwrap()))
+            .collect();
+        let output = render(
+            CompatStyle::Gnu,
+            temp_dir.path(),
+            &entries.iter().collect::<Vec<_>>(),
+            PathStyle::Name,
+        );
+        let lines: Vec<&str> = output.lines().collect();
+        assert!(lines[0].starts_with("total "));
+        let fields: Vec<&str> = lines[1].split_whitespace().collect();
+        assert_eq!(fields.len(), 9);
+        assert_eq!(fields[0].len(), 10);
+        assert!(fields[0].starts_with('-'));
+        assert_eq!(fields[1], "1");
+        assert_eq!(fields[4], "3");
+        assert_eq!(fields[8], "a.txt");
+    }
+
+    #[test]
+    fn listing_date_shows_the_year_unless_within_the_last_six_months() {
+        let now = SystemTime::now();
+        let day = Duration::from_secs(24 * 60 * 60);
+        let year = |time: SystemTime| DateTime::<Local>::from(time).format("%Y").to_string();
+        assert!(format_listing_date(Some(now - day), now).contains(':'));
+        let old = now - SIX_MONTHS - day;
+        assert!(format_listing_date(Some(old), now).ends_with(&year(old)));
+        let future = now + day;
+        assert!(format_listing_date(Some(future), now).ends_with(&year(future)));
+    }
+
+    #[test]
+    fn dir_style_marks_directories_and_totals_files() {
+        assert_eq!(group_thousands(1234567), "1,234,567");
+        assert_eq!(group_thousands(999), "999");
+        let temp_dir = tempdir().unwrap();
+        fs::write(temp_dir.path().join("a.txt"), "x".repeat(1234)).unwrap();
+        fs::create_dir(temp_dir.path().join("sub")).unwrap();
+        let mut entries: Vec<FileEntry> = fs::read_dir(temp_dir.path())
+            .unwrap()
+            .map(|entry| FileEntry::from_dir_entry(&entry.unwrap()))
+            .collect();
+        entries.sort_by(|left, right| left.name.cmp(&right.name));
+        let output = render(
+            CompatStyle::Dir,
+            temp_dir.path(),
+            &entries.iter().collect::<Vec<_>>(),
+            PathStyle::Name,
+        );
+        let lines: Vec<&str> = output.lines().collect();
+        assert!(lines[1].starts_with(" Directory of "));
+        assert!(lines[3].ends_with("    <DIR>          ."));
+        assert!(lines[5].ends_with("             1,234 a.txt"));
+        assert!(lines[6].ends_with("    <DIR>          sub"));
+        assert_eq!(lines[7], "               1 File(s)          1,234 bytes");
+        assert!(lines[8].starts_with("               3 Dir(s)  "));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn bsd_style_keeps_a_marker_column_and_double_spaces() {
+        let temp_dir = tempdir().unwrap();
+        fs::write(temp_dir.path().join("a.txt"), "123").unwrap();
+        let entries: Vec<FileEntry> = fs::read_dir(temp_dir.path())
+            .unwrap()
+            .map(|entry| FileEntry::from_dir_entry(&entry.unwrap()))
+            .collect();
+        let output = render(
+            CompatStyle::Bsd,
+            temp_dir.path(),
+            &entries.iter().collect::<Vec<_>>(),
+            PathStyle::Name,
+        );
+        let line = output.lines().nth(1).unwrap();
+        assert!(line.starts_with("-rw"));
+        assert_eq!(&line[10..13], "  1");
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let owner_end = line.find(fields[2]).unwrap() + fields[2].len();
+        assert_eq!(&line[owner_end..owner_end + 2], "  ");
+        assert_eq!(fields[8], "a.txt");
+    }
+}
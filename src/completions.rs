@@ -0,0 +1,230 @@
+/// A flag as the help screen and the completion scripts describe it.
+pub(crate) struct DocumentedFlag {
+    pub(crate) short: Option<&'static str>,
+    pub(crate) long: &'static str,
+    /// The value placeholder, `None` for switches. `a|b|c` lists the accepted values.
+    pub(crate) value: Option<&'static str>,
+    pub(crate) description: &'static str,
+}
+
+impl DocumentedFlag {
+    fn choices(&self) -> Option<Vec<&'static str>> {
+        self.value
+            .filter(|value| value.contains('|'))
+            .map(|value| value.split('|').collect())
+    }
+
+    fn spellings(&self) -> Vec<String> {
+        let mut spellings: Vec<String> = self
+            .short
+            .map(|short| format!("-{}", short))
+            .into_iter()
+            .collect();
+        spellings.push(format!("--{}", self.long));
+        spellings
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    Powershell,
+}
+
+/// A completion script for `shell` offering `commands` as the first word and `flags` anywhere,
+/// with the accepted values after flags that list them and paths everywhere else.
+pub(crate) fn script(shell: Shell, commands: &[&str], flags: &[DocumentedFlag]) -> String {
+    match shell {
+        Shell::Bash => bash(commands, flags),
+        Shell::Zsh => zsh(commands, flags),
+        Shell::Fish => fish(commands, flags),
+        Shell::Powershell => powershell(commands, flags),
+    }
+}
+
+fn bash(commands: &[&str], flags: &[DocumentedFlag]) -> String {
+    let mut lines = vec![
+        String::from("_mini_ls() {"),
+        String::from("    local cur=\"${COMP_WORDS[COMP_CWORD]}\""),
+        String::from("    local prev=\"${COMP_WORDS[COMP_CWORD-1]}\""),
+        String::from("    case \"$prev\" in"),
+    ];
+    for flag in flags {
+        if let Some(choices) = flag.choices() {
+            lines.push(format!(
+                "        {}) COMPREPLY=($(compgen -W \"{}\" -- \"$cur\")); return ;;",
+                flag.spellings().join("|"),
+                choices.join(" ")
+            ));
+        }
+    }
+    let all_flags: Vec<String> = flags.iter().flat_map(DocumentedFlag::spellings).collect();
+    lines.extend([
+        String::from("    esac"),
+        String::from("    if [[ \"$cur\" == -* ]]; then"),
+        format!(
+            "        COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))",
+            all_flags.join(" ")
+        ),
+        String::from("    elif [[ $COMP_CWORD -eq 1 ]]; then"),
+        format!(
+            "        COMPREPLY=($(compgen -W \"{}\" -- \"$cur\") $(compgen -f -- \"$cur\"))",
+            commands.join(" ")
+        ),
+        String::from("    else"),
+        String::from("        COMPREPLY=($(compgen -f -- \"$cur\"))"),
+        String::from("    fi"),
+        String::from("}"),
+        String::from("complete -o filenames -F _mini_ls mini-ls"),
+    ]);
+    lines.join("\n")
+}
+
+fn zsh(commands: &[&str], flags: &[DocumentedFlag]) -> String {
+    let escape = |text: &str| {
+        text.replace('\'', "'\\''")
+            .replace('[', "\\[")
+            .replace(']', "\\]")
+    };
+    let mut lines = vec![String::from("#compdef mini-ls"), String::new()];
+    lines.push(String::from("_arguments -s \\"));
+    for flag in flags {
+        let value = match (flag.value, flag.choices()) {
+            (None, _) => String::new(),
+            (Some(_), Some(choices)) => format!(":value:({})", choices.join(" ")),
+            (Some(placeholder), None) => format!(":{}:_files", placeholder.to_lowercase()),
+        };
+        for spelling in flag.spellings() {
+            lines.push(format!(
+                "    '{}[{}]{}' \\",
+                spelling,
+                escape(flag.description),
+                value
+            ));
+        }
+    }
+    lines.push(format!(
+        "    '1:command or target:{{_alternative \"commands:command:({})\" \"files:target:_files\"}}' \\",
+        commands.join(" ")
+    ));
+    lines.push(String::from("    '*:target:_files'"));
+    lines.join("\n")
+}
+
+fn fish(commands: &[&str], flags: &[DocumentedFlag]) -> String {
+    let escape = |text: &str| text.replace('\\', "\\\\").replace('\'', "\\'");
+    let mut lines = vec![format!(
+        "complete -c mini-ls -n __fish_use_subcommand -a '{}'",
+        commands.join(" ")
+    )];
+    for flag in flags {
+        let mut line = String::from("complete -c mini-ls");
+        if let Some(short) = flag.short {
+            line.push_str(&format!(" -s {}", short));
+        }
+        line.push_str(&format!(" -l {}", flag.long));
+        match (flag.value, flag.choices()) {
+            (None, _) => {}
+            (Some(_), Some(choices)) => line.push_str(&format!(" -x -a '{}'", choices.join(" "))),
+            (Some(_), None) => line.push_str(" -r -F"),
+        }
+        line.push_str(&format!(" -d '{}'", escape(flag.description)));
+        lines.push(line);
+    }
+    lines.join("\n")
+}
+
+fn powershell(commands: &[&str], flags: &[DocumentedFlag]) -> String {
+    let quote = |text: &str| format!("'{}'", text.replace('\'', "''"));
+    let mut lines = vec![
+        String::from("Register-ArgumentCompleter -Native -CommandName mini-ls -ScriptBlock {"),
+        String::from("    param($wordToComplete, $commandAst, $cursorPosition)"),
+        String::from("    $words = @($commandAst.CommandElements | Where-Object { $_.Extent.EndOffset -lt $cursorPosition } | ForEach-Object { $_.ToString() })"),
+        String::from("    $choices = @{"),
+    ];
+    for flag in flags {
+        if let Some(choices) = flag.choices() {
+            let choices: Vec<String> = choices.into_iter().map(quote).collect();
+            for spelling in flag.spellings() {
+                lines.push(format!(
+                    "        {} = @({})",
+                    quote(&spelling),
+                    choices.join(", ")
+                ));
+            }
+        }
+    }
+    lines.push(String::from("    }"));
+    lines.push(String::from("    $candidates = @("));
+    for flag in flags {
+        for spelling in flag.spellings() {
+            lines.push(format!(
+                "        @({}, {})",
+                quote(&spelling),
+                quote(flag.description)
+            ));
+        }
+    }
+    lines.push(String::from("    )"));
+    lines.extend([
+        String::from("    if ($words.Count -gt 0 -and $choices.ContainsKey($words[-1])) {"),
+        String::from("        $candidates = @($choices[$words[-1]] | ForEach-Object { ,@($_, $_) })"),
+        String::from("    } elseif ($words.Count -eq 1) {"),
+        format!(
+            "        $candidates += @({} | ForEach-Object {{ ,@($_, $_) }})",
+            commands
+                .iter()
+                .map(|command| quote(command))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        String::from("    }"),
+        String::from("    $candidates | Where-Object { $_[0] -like \"$wordToComplete*\" } | ForEach-Object {"),
+        String::from("        [System.Management.Automation.CompletionResult]::new($_[0], $_[0], 'ParameterValue', $_[1])"),
+        String::from("    }"),
+        String::from("}"),
+    ]);
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flags() -> Vec<DocumentedFlag> {
+        vec![
+            DocumentedFlag {
+                short: Some("s"),
+                long: "sort",
+                value: Some("name|size"),
+                description: "sort order",
+            },
+            DocumentedFlag {
+                short: None,
+                long: "compat",
+                value: Some("PATH"),
+                description: "another tool's [shape]",
+            },
+        ]
+    }
+
+    #[test]
+    fn every_shell_offers_flags_commands_and_choices() {
+        let commands = ["find", "stats"];
+        let bash = script(Shell::Bash, &commands, &flags());
+        assert!(bash.contains("-s|--sort) COMPREPLY=($(compgen -W \"name size\" -- \"$cur\"))"));
+        assert!(bash.contains("compgen -W \"-s --sort --compat\""));
+        assert!(bash.contains("compgen -W \"find stats\""));
+        let zsh = script(Shell::Zsh, &commands, &flags());
+        assert!(zsh.contains("'--sort[sort order]:value:(name size)' \\"));
+        assert!(zsh.contains("'--compat[another tool'\\''s \\[shape\\]]:path:_files' \\"));
+        let fish = script(Shell::Fish, &commands, &flags());
+        assert!(fish.contains("complete -c mini-ls -s s -l sort -x -a 'name size' -d 'sort order'"));
+        assert!(fish.contains("-l compat -r -F -d 'another tool\\'s [shape]'"));
+        let powershell = script(Shell::Powershell, &commands, &flags());
+        assert!(powershell.contains("'--sort' = @('name', 'size')"));
+        assert!(powershell.contains("@('--compat', 'another tool''s [shape]')"));
+    }
+}
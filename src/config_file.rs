@@ -0,0 +1,185 @@
+use crate::columns::ColumnLimits;
+use crate::hooks::Hooks;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+
+const CONFIG_DIR_NAME: &str = "mini-ls";
+const CONFIG_FILE_NAME: &str = "config.toml";
+const DEFAULT_CONFIG: &str = "\
+# mini-ls configuration, every setting is optional and the defaults apply when left out.
+
+# Width limits for the columns of the -l listing, each column accepts `min` and `max`.
+# Dates narrower than 23 columns drop the milliseconds, then the seconds and then the time.
+[columns]
+# name = { max = 60 }
+# created = { max = 16 }
+# permissions = { min = 10 }
+# modified = { min = 10, max = 23 }
+
+# Defaults for any long flag, `true` or `false` for switches and the value for the rest.
+# Environment variables such as MINI_LS_SORT and the command line both override these.
+[defaults]
+# extended = true
+# sort = \"size\"
+# color = \"never\"
+# icons = \"ascii\"
+
+# Named sets of defaults picked with `--profile NAME` or MINI_LS_PROFILE, applied over [defaults].
+# [profiles.report]
+# extended = true
+# sort = \"modified\"
+
+# Shell commands run before and after each listing, with MINI_LS_HOOK_TARGET set to the listed
+# directory and MINI_LS_HOOK_OUTPUT to the -F file or `-` for stdout. A failing `before` stops the
+# listing, `after` only runs once the listing has been written.
+[hooks]
+# before = \"mount /mnt/share\"
+# after = \"curl -sT \\\"$MINI_LS_HOOK_OUTPUT\\\" https://example.com/upload\"
+";
+
+/// Long flag names mapped to the value they default to.
+pub(crate) type FlagTable = BTreeMap<String, toml::Value>;
+
+/// Settings read from `~/.config/mini-ls/config.toml`. Every table is optional so a missing or
+/// empty file behaves exactly like the built in defaults.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct ConfigFile {
+    pub(crate) columns: ColumnLimits,
+    pub(crate) defaults: FlagTable,
+    pub(crate) profiles: BTreeMap<String, FlagTable>,
+    pub(crate) hooks: Hooks,
+}
+
+impl ConfigFile {
+    pub(crate) fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join(CONFIG_DIR_NAME).join(CONFIG_FILE_NAME))
+    }
+
+    /// A missing file is not an error, anything unreadable or malformed is reported as a reason.
+    pub(crate) fn load(path: &Path) -> Result<ConfigFile, String> {
+        match fs::read_to_string(path) {
+            Ok(contents) => ConfigFile::parse(&contents),
+            Err(error) if error.kind() == ErrorKind::NotFound => Ok(ConfigFile::default()),
+            Err(error) => Err(error.to_string()),
+        }
+    }
+
+    fn parse(contents: &str) -> Result<ConfigFile, String> {
+        let config: ConfigFile =
+            toml::from_str(contents).map_err(|error| error.message().to_string())?;
+        config.check_values()?;
+        Ok(config)
+    }
+
+    fn check_values(&self) -> Result<(), String> {
+        let columns = &self.columns;
+        for (name, limit) in [
+            ("name", columns.name),
+            ("created", columns.created),
+            ("permissions", columns.permissions),
+            ("modified", columns.modified),
+        ] {
+            if let (Some(min), Some(max)) = (limit.min, limit.max) {
+                if min > max {
+                    return Err(format!(
+                        "the {} column has a min of {} which is above its max of {}",
+                        name, min, max
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the commented default file, refusing to replace an existing one unless forced.
+    pub(crate) fn init(path: &Path, force: bool) -> io::Result<()> {
+        if path.exists() && !force {
+            return Err(io::Error::new(
+                ErrorKind::AlreadyExists,
+                format!(
+                    "{} already exists, pass --force to overwrite it",
+                    path.display()
+                ),
+            ));
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, DEFAULT_CONFIG)
+    }
+
+    /// Unlike `load` a missing file is reported, there is nothing to validate.
+    pub(crate) fn validate(path: &Path) -> io::Result<()> {
+        let contents = fs::read_to_string(path)?;
+        ConfigFile::parse(&contents)
+            .map(|_| ())
+            .map_err(|reason| io::Error::new(ErrorKind::InvalidData, reason))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::columns::WidthLimit;
+
+    #[test]
+    fn reads_column_width_limits() {
+        let config = ConfigFile::parse(
+            "[columns]\nname = { max = 60 }\ncreated = { max = 16 }\nmodified = { min = 10, max = 16 }\n",
+        )
+        .unwrap();
+        assert_eq!(
+            config.columns.name,
+            WidthLimit {
+                min: None,
+                max: Some(60)
+            }
+        );
+        assert_eq!(config.columns.modified.min, Some(10));
+        assert_eq!(config.columns.permissions, WidthLimit::default());
+    }
+
+    #[test]
+    fn rejects_unknown_columns() {
+        let error = ConfigFile::parse("[columns]\nsize = { max = 4 }\n").unwrap_err();
+        assert!(error.contains("unknown field `size`"));
+    }
+
+    #[test]
+    fn reads_hooks() {
+        let config = ConfigFile::parse("[hooks]\nbefore = \"mount /mnt/share\"\n").unwrap();
+        assert_eq!(config.hooks.before.as_deref(), Some("mount /mnt/share"));
+        assert_eq!(config.hooks.after, None);
+        assert!(ConfigFile::parse("[hooks]\nduring = \"true\"\n").is_err());
+    }
+
+    #[test]
+    fn rejects_min_above_max() {
+        let error = ConfigFile::parse("[columns]\nname = { min = 40, max = 20 }\n").unwrap_err();
+        assert_eq!(
+            error,
+            "the name column has a min of 40 which is above its max of 20"
+        );
+    }
+
+    #[test]
+    fn init_writes_a_valid_default_file_once() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("mini-ls").join("config.toml");
+        ConfigFile::init(&path, false).unwrap();
+        ConfigFile::validate(&path).unwrap();
+        assert!(ConfigFile::init(&path, false).is_err());
+        ConfigFile::init(&path, true).unwrap();
+    }
+
+    #[test]
+    fn missing_file_uses_defaults() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = ConfigFile::load(&temp_dir.path().join("config.toml")).unwrap();
+        assert_eq!(config.columns, ColumnLimits::default());
+    }
+}
@@ -0,0 +1,184 @@
+use crate::index::Index;
+use crate::inotify::{Change, Watcher};
+use crate::machine_output::{self, MachineFormat};
+use serde_json::json;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Component, Path};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::{fs, thread};
+
+/// Indexes `root`, keeps the index current from inotify events and answers queries sent to
+/// `socket` until the process is interrupted.
+pub(crate) fn run(root: &Path, socket: &Path) -> io::Result<()> {
+    // Watching the root before the scan queues anything changed while it runs, and refreshing
+    // an entry the scan already saw is harmless.
+    let mut watcher = Watcher::new()?;
+    watcher.watch(root);
+    let index = Index::build(root)?;
+    for dir in index.directories() {
+        watcher.watch(dir);
+    }
+    let entry_count = index.stats();
+    let index = Arc::new(Mutex::new(index));
+    let watched_index = Arc::clone(&index);
+    thread::spawn(move || keep_current(&watched_index, &mut watcher));
+    remove_stale_socket(socket)?;
+    let listener = UnixListener::bind(socket)?;
+    println!(
+        "Indexed {} entries under {}, answering queries on {}",
+        entry_count.files + entry_count.directories + entry_count.other,
+        root.display(),
+        socket.display()
+    );
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                if let Err(error) = answer_connection(stream, &index) {
+                    eprintln!("Unable to answer query due to: {}", error);
+                }
+            }
+            Err(error) => eprintln!("Unable to accept connection due to: {}", error),
+        }
+    }
+    Ok(())
+}
+
+fn keep_current(index: &Mutex<Index>, watcher: &mut Watcher) {
+    loop {
+        let changes = match watcher.read_changes() {
+            Ok(changes) => changes,
+            Err(error) => {
+                eprintln!("Stopped watching for changes due to: {}", error);
+                return;
+            }
+        };
+        let mut index = index.lock().unwrap_or_else(PoisonError::into_inner);
+        for change in changes {
+            match change {
+                Change::Path(path) => {
+                    for dir in index.refresh(&path) {
+                        watcher.watch(&dir);
+                    }
+                }
+                Change::Overflow => match Index::build(index.root()) {
+                    Ok(rebuilt) => {
+                        watcher.unwatch_all();
+                        for dir in rebuilt.directories() {
+                            watcher.watch(dir);
+                        }
+                        *index = rebuilt;
+                    }
+                    Err(error) => eprintln!("Unable to rebuild the index due to: {}", error),
+                },
+            }
+        }
+    }
+}
+
+fn remove_stale_socket(socket: &Path) -> io::Result<()> {
+    match fs::symlink_metadata(socket) {
+        Ok(meta) if meta.file_type().is_socket() => fs::remove_file(socket),
+        Ok(_) => Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} exists and is not a socket", socket.display()),
+        )),
+        Err(_) => Ok(()),
+    }
+}
+
+fn answer_connection(mut stream: UnixStream, index: &Mutex<Index>) -> io::Result<()> {
+    let mut request = String::new();
+    BufReader::new(&stream).read_line(&mut request)?;
+    // A panic while holding the lock leaves at worst a stale directory, which the next change
+    // to it refreshes, so keep serving rather than taking the daemon down.
+    let index = index.lock().unwrap_or_else(PoisonError::into_inner);
+    let response = answer(&index, request.trim());
+    stream.write_all(response.as_bytes())?;
+    stream.write_all(b"\n")
+}
+
+fn answer(index: &Index, request: &str) -> String {
+    let (command, argument) = request.split_once(' ').unwrap_or((request, ""));
+    match command {
+        "list" => {
+            let relative = Path::new(argument.trim());
+            let mut dir = index.root().to_path_buf();
+            let mut escapes = false;
+            for component in relative.components() {
+                match component {
+                    Component::Normal(part) => dir.push(part),
+                    Component::CurDir => {}
+                    _ => escapes = true,
+                }
+            }
+            let entries = if escapes { None } else { index.list(&dir) };
+            match entries {
+                Some(entries) => machine_output::render(
+                    MachineFormat::Json,
+                    &relative.to_string_lossy(),
+                    &entries,
+                    None,
+                ),
+                None => json!({"error": format!("{} is not an indexed directory", argument)})
+                    .to_string(),
+            }
+        }
+        "stats" => {
+            let stats = index.stats();
+            json!({
+                "files": stats.files,
+                "directories": stats.directories,
+                "other": stats.other,
+                "total_bytes": stats.total_bytes,
+            })
+            .to_string()
+        }
+        _ => json!({"error": format!("unknown query {}, expected list <dir> or stats", command)})
+            .to_string(),
+    }
+}
+
+/// Sends a single query to a running index daemon and returns its response.
+pub(crate) fn query(socket: &Path, request: &str) -> io::Result<String> {
+    let mut stream = UnixStream::connect(socket).map_err(|error| {
+        io::Error::new(
+            error.kind(),
+            format!(
+                "no index daemon is answering on {} ({})",
+                socket.display(),
+                error
+            ),
+        )
+    })?;
+    stream.write_all(request.as_bytes())?;
+    stream.write_all(b"\n")?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response)?;
+    Ok(response.trim_end().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+    use tempfile::tempdir;
+
+    #[test]
+    fn answers_list_and_stats_queries() {
+        let temp_dir = tempdir().unwrap();
+        fs::create_dir(temp_dir.path().join("nested")).unwrap();
+        fs::write(temp_dir.path().join("nested").join("a.txt"), "123").unwrap();
+        let index = Index::build(temp_dir.path()).unwrap();
+        let listing: Value = serde_json::from_str(&answer(&index, "list nested")).unwrap();
+        assert_eq!(listing["entries"][0]["name"], "a.txt");
+        let listing: Value = serde_json::from_str(&answer(&index, "list .")).unwrap();
+        assert_eq!(listing["entries"][0]["name"], "nested");
+        let stats: Value = serde_json::from_str(&answer(&index, "stats")).unwrap();
+        assert_eq!(stats["files"], 1);
+        assert_eq!(stats["total_bytes"], 3);
+        let escaped: Value = serde_json::from_str(&answer(&index, "list ../")).unwrap();
+        assert!(escaped["error"].is_string());
+    }
+}
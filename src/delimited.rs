@@ -0,0 +1,163 @@
+use crate::columns::format_octal_mode;
+use crate::entries::FileEntry;
+use crate::machine_output::format_timestamp;
+use serde::Serialize;
+
+const CSV_COLUMNS: [&str; 8] = [
+    "name", "path", "kind", "size", "created", "modified", "readonly", "mode",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CsvQuoting {
+    /// Only fields containing the delimiter, quotes or line breaks are quoted.
+    Minimal,
+    All,
+}
+
+/// Spreadsheet applications disagree on the delimiter, European Excel installs expect `;`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct CsvDialect {
+    pub delimiter: char,
+    pub quoting: CsvQuoting,
+    pub header: bool,
+}
+
+impl Default for CsvDialect {
+    fn default() -> Self {
+        CsvDialect {
+            delimiter: ',',
+            quoting: CsvQuoting::Minimal,
+            header: true,
+        }
+    }
+}
+
+pub(crate) fn render_csv(entries: &[FileEntry], dialect: CsvDialect) -> String {
+    let header = dialect
+        .header
+        .then(|| CSV_COLUMNS.map(String::from).to_vec());
+    header
+        .into_iter()
+        .chain(entries.iter().map(entry_fields))
+        .map(|fields| {
+            fields
+                .iter()
+                .map(|field| quote_csv_field(field, dialect))
+                .collect::<Vec<String>>()
+                .join(&dialect.delimiter.to_string())
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Strict tab separated values: no padding, and backslash escapes for tabs, line breaks and
+/// backslashes inside fields so every record stays on one line.
+pub(crate) fn render_tsv(entries: &[FileEntry]) -> String {
+    std::iter::once(CSV_COLUMNS.map(String::from).to_vec())
+        .chain(entries.iter().map(entry_fields))
+        .map(|fields| {
+            fields
+                .iter()
+                .map(|field| escape_tsv_field(field))
+                .collect::<Vec<String>>()
+                .join("\t")
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+fn escape_tsv_field(field: &str) -> String {
+    field
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+fn entry_fields(entry: &FileEntry) -> Vec<String> {
+    vec![
+        entry.name.clone(),
+        entry.path.to_string_lossy().to_string(),
+        entry.kind.as_str().to_string(),
+        entry.size.map(|size| size.to_string()).unwrap_or_default(),
+        entry.created.map(format_timestamp).unwrap_or_default(),
+        entry.modified.map(format_timestamp).unwrap_or_default(),
+        entry
+            .readonly
+            .map(|readonly| readonly.to_string())
+            .unwrap_or_default(),
+        entry.mode.map(format_octal_mode).unwrap_or_default(),
+    ]
+}
+
+fn quote_csv_field(field: &str, dialect: CsvDialect) -> String {
+    let needs_quotes = dialect.quoting == CsvQuoting::All
+        || field.contains(dialect.delimiter)
+        || field.contains(['"', '\n', '\r']);
+    if needs_quotes {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn dialect_controls_delimiter_quoting_and_header() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("a;b \"c\".txt");
+        fs::write(&path, "12").unwrap();
+        let entries = vec![FileEntry::from_path(&path).unwrap()];
+        let default = render_csv(&entries, CsvDialect::default());
+        assert!(default.starts_with("name,path,kind,size"));
+        assert!(default.contains("\n\"a;b \"\"c\"\".txt\","));
+        let semicolons = CsvDialect {
+            delimiter: ';',
+            quoting: CsvQuoting::Minimal,
+            header: false,
+        };
+        let rendered = render_csv(&entries, semicolons);
+        assert!(rendered.starts_with("\"a;b \"\"c\"\".txt\";"));
+        assert!(rendered.contains(";file;2;"));
+        let all_quoted = CsvDialect {
+            quoting: CsvQuoting::All,
+            ..CsvDialect::default()
+        };
+        assert!(render_csv(&entries, all_quoted).starts_with("\"name\",\"path\""));
+    }
+
+    #[test]
+    fn csv_quotes_names_with_commas_and_line_breaks() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("q1, q2\nfinal.txt");
+        fs::write(&path, "").unwrap();
+        let rendered = render_csv(
+            &[FileEntry::from_path(&path).unwrap()],
+            CsvDialect::default(),
+        );
+        let (header, record) = rendered.split_once('\n').unwrap();
+        assert_eq!(header, CSV_COLUMNS.join(","));
+        assert!(record.starts_with("\"q1, q2\nfinal.txt\",\""));
+        assert!(record.contains(",file,0,"));
+    }
+
+    #[test]
+    fn tsv_escapes_tabs_and_line_breaks_in_names() {
+        assert_eq!(escape_tsv_field("a\tb\nc\\d"), "a\\tb\\nc\\\\d");
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("tab\there.txt");
+        fs::write(&path, "").unwrap();
+        let rendered = render_tsv(&[FileEntry::from_path(&path).unwrap()]);
+        let lines: Vec<&str> = rendered.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("name\tpath\tkind"));
+        assert!(lines[1].starts_with("tab\\there.txt\t"));
+        assert_eq!(lines[1].split('\t').count(), 8);
+    }
+}
@@ -0,0 +1,82 @@
+use crate::access::has_access;
+use crate::columns::{format_octal_mode, format_symbolic_mode};
+use crate::users::{effective_ids, group_name, is_member_of, user_name};
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+
+/// Explains which permission bit stops the current user from listing `target`, first checking
+/// that every parent directory can be searched and then that `target` itself can be read.
+pub(crate) fn explain_permission_denied(target: &Path) -> Option<String> {
+    let absolute = fs::canonicalize(target)
+        .ok()
+        .or_else(|| std::env::current_dir().ok().map(|dir| dir.join(target)))?;
+    let blocked_parent = absolute
+        .ancestors()
+        .skip(1)
+        .collect::<Vec<&Path>>()
+        .into_iter()
+        .rev()
+        .find(|ancestor| !has_access(ancestor, libc::X_OK));
+    match blocked_parent {
+        Some(parent) => describe_block(parent, "search (x)"),
+        None if !has_access(&absolute, libc::R_OK) => describe_block(&absolute, "read (r)"),
+        None if !has_access(&absolute, libc::X_OK) => describe_block(&absolute, "search (x)"),
+        None => None,
+    }
+}
+
+fn describe_block(path: &Path, permission: &str) -> Option<String> {
+    let meta_data = fs::metadata(path).ok()?;
+    let (euid, _) = effective_ids();
+    let owner = user_name(meta_data.uid()).unwrap_or_else(|| meta_data.uid().to_string());
+    let group = group_name(meta_data.gid()).unwrap_or_else(|| meta_data.gid().to_string());
+    let current_user = user_name(euid).unwrap_or_else(|| euid.to_string());
+    let class = if meta_data.uid() == euid {
+        "owner"
+    } else if is_member_of(meta_data.gid()) {
+        "group"
+    } else {
+        "other"
+    };
+    Some(format!(
+        "{} is owned by {}:{} with mode {} ({}); {} is treated as {} which lacks {} permission",
+        path.display(),
+        owner,
+        group,
+        format_octal_mode(meta_data.mode()),
+        format_symbolic_mode(meta_data.mode()),
+        current_user,
+        class,
+        permission
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+    use tempfile::tempdir;
+
+    #[test]
+    fn explains_missing_read_permission_for_non_root_users() {
+        let (euid, _) = effective_ids();
+        if euid == 0 {
+            return; // root bypasses permission bits so there is nothing to diagnose
+        }
+        let temp_dir = tempdir().unwrap();
+        let locked = temp_dir.path().join("locked");
+        fs::create_dir(&locked).unwrap();
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o300)).unwrap();
+        let explanation = explain_permission_denied(&locked).unwrap();
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o700)).unwrap();
+        assert!(explanation.contains("mode 0300 (-wx------)"));
+        assert!(explanation.contains("treated as owner which lacks read (r) permission"));
+    }
+
+    #[test]
+    fn has_nothing_to_explain_for_readable_directories() {
+        let temp_dir = tempdir().unwrap();
+        assert!(explain_permission_denied(temp_dir.path()).is_none());
+    }
+}
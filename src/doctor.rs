@@ -0,0 +1,145 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ColorDepth {
+    None,
+    Basic,
+    Extended,
+    TrueColor,
+}
+
+impl ColorDepth {
+    fn describe(&self) -> &'static str {
+        match self {
+            ColorDepth::None => "none",
+            ColorDepth::Basic => "16 colors",
+            ColorDepth::Extended => "256 colors",
+            ColorDepth::TrueColor => "24-bit true color",
+        }
+    }
+}
+
+/// Summarises what mini-ls can detect about the terminal, locale and the filesystem holding
+/// `target`, for attaching to "why does it look wrong here" reports.
+pub(crate) fn report(target: &Path) -> String {
+    let variable = |name: &str| env::var(name).ok().filter(|value| !value.is_empty());
+    let width = match term_size::dimensions() {
+        Some((width, _)) => format!("{} columns", width),
+        None => String::from("unknown (not a terminal, -F output uses 120 columns)"),
+    };
+    let depth = color_depth(
+        variable("TERM").as_deref(),
+        variable("COLORTERM").as_deref(),
+        variable("NO_COLOR").is_some(),
+    );
+    let charset = ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .find_map(|name| variable(name));
+    let unicode = is_utf8(charset.as_deref());
+    let emoji = unicode && variable("TERM").as_deref() != Some("linux");
+    let timestamps = match fs::metadata(target) {
+        Ok(meta_data) => match (meta_data.created(), meta_data.modified()) {
+            (Ok(_), Ok(_)) => String::from("created and modified"),
+            (Err(_), Ok(_)) => String::from("modified only, created dates are unavailable"),
+            _ => String::from("none"),
+        },
+        Err(error) => format!("unknown ({})", error),
+    };
+    let features: Vec<&str> = [
+        ("parquet", cfg!(feature = "parquet")),
+        ("arrow", cfg!(feature = "arrow")),
+        ("index daemon", cfg!(target_os = "linux")),
+    ]
+    .into_iter()
+    .filter(|(_, enabled)| *enabled)
+    .map(|(name, _)| name)
+    .collect();
+    [
+        format!("mini-ls {}", env!("CARGO_PKG_VERSION")),
+        format!("Terminal width:    {}", width),
+        format!("Color depth:       {}", depth.describe()),
+        format!(
+            "Unicode:           {} ({})",
+            yes_no(unicode),
+            charset.as_deref().unwrap_or("no locale set")
+        ),
+        format!("Emoji icons:       {}", yes_no(emoji)),
+        format!("Timestamps:        {} ({})", timestamps, target.display()),
+        format!(
+            "Optional features: {}",
+            if features.is_empty() {
+                String::from("none")
+            } else {
+                features.join(", ")
+            }
+        ),
+    ]
+    .join("\n")
+}
+
+fn color_depth(term: Option<&str>, colorterm: Option<&str>, no_color: bool) -> ColorDepth {
+    match (term, colorterm) {
+        _ if no_color => ColorDepth::None,
+        (Some("dumb") | None, None) => ColorDepth::None,
+        (_, Some("truecolor" | "24bit")) => ColorDepth::TrueColor,
+        (Some(term), _) if term.contains("256color") => ColorDepth::Extended,
+        _ => ColorDepth::Basic,
+    }
+}
+
+fn is_utf8(locale: Option<&str>) -> bool {
+    locale.is_some_and(|locale| {
+        let locale = locale.to_ascii_lowercase();
+        locale.contains("utf-8") || locale.contains("utf8")
+    })
+}
+
+fn yes_no(value: bool) -> &'static str {
+    if value {
+        "yes"
+    } else {
+        "no"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn color_depth_follows_term_variables() {
+        assert_eq!(
+            color_depth(Some("xterm-256color"), None, false),
+            ColorDepth::Extended
+        );
+        assert_eq!(
+            color_depth(Some("xterm"), Some("truecolor"), false),
+            ColorDepth::TrueColor
+        );
+        assert_eq!(color_depth(Some("xterm"), None, false), ColorDepth::Basic);
+        assert_eq!(color_depth(Some("dumb"), None, false), ColorDepth::None);
+        assert_eq!(
+            color_depth(Some("xterm-256color"), None, true),
+            ColorDepth::None
+        );
+    }
+
+    #[test]
+    fn report_covers_each_check() {
+        assert!(is_utf8(Some("en_GB.UTF-8")));
+        assert!(!is_utf8(Some("C")));
+        let temp_dir = tempfile::tempdir().unwrap();
+        let report = report(temp_dir.path());
+        for heading in [
+            "Terminal width:",
+            "Color depth:",
+            "Unicode:",
+            "Emoji icons:",
+        ] {
+            assert!(report.contains(heading));
+        }
+        assert!(report.contains("modified"));
+    }
+}
@@ -0,0 +1,49 @@
+use serde::Serialize;
+
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+const UTF16LE_BOM: &[u8] = &[0xFF, 0xFE];
+
+/// Encoding of files written with `-F`, some Windows ingestion tools insist on a BOM or
+/// UTF-16. Standard output is always plain UTF-8.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum OutputEncoding {
+    #[default]
+    #[serde(rename = "utf-8")]
+    Utf8,
+    #[serde(rename = "utf-8-bom")]
+    Utf8Bom,
+    #[serde(rename = "utf-16le")]
+    Utf16Le,
+}
+
+impl OutputEncoding {
+    pub(crate) fn encode(&self, contents: &str) -> Vec<u8> {
+        match self {
+            OutputEncoding::Utf8 => contents.as_bytes().to_vec(),
+            OutputEncoding::Utf8Bom => [UTF8_BOM, contents.as_bytes()].concat(),
+            OutputEncoding::Utf16Le => UTF16LE_BOM
+                .iter()
+                .copied()
+                .chain(contents.encode_utf16().flat_map(|unit| unit.to_le_bytes()))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_with_the_requested_byte_order_mark() {
+        assert_eq!(OutputEncoding::Utf8.encode("ab"), b"ab");
+        assert_eq!(
+            OutputEncoding::Utf8Bom.encode("ab"),
+            [0xEF, 0xBB, 0xBF, b'a', b'b']
+        );
+        assert_eq!(
+            OutputEncoding::Utf16Le.encode("a\u{1F4BE}"),
+            [0xFF, 0xFE, b'a', 0x00, 0x3D, 0xD8, 0xBE, 0xDC]
+        );
+    }
+}
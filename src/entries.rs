@@ -0,0 +1,109 @@
+use std::fs::{self, DirEntry, Metadata};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    File,
+    Directory,
+    Symlink,
+    Other,
+}
+
+impl EntryKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EntryKind::File => "file",
+            EntryKind::Directory => "directory",
+            EntryKind::Symlink => "symlink",
+            EntryKind::Other => "other",
+        }
+    }
+}
+
+/// Structured view of a directory entry as returned by `list_entries` and rendered by the text,
+/// machine readable and export outputs. Metadata fields are `None` when the entry could not be
+/// inspected, a symlink describes the link itself.
+#[derive(Debug, Clone)]
+pub struct FileEntry {
+    pub name: String,
+    pub path: PathBuf,
+    pub kind: EntryKind,
+    pub size: Option<u64>,
+    pub created: Option<SystemTime>,
+    pub modified: Option<SystemTime>,
+    pub readonly: Option<bool>,
+    pub mode: Option<u32>,
+    pub(crate) metadata: Option<Metadata>,
+}
+
+impl FileEntry {
+    pub(crate) fn from_dir_entry(dir_entry: &DirEntry) -> FileEntry {
+        FileEntry::from_parts(
+            dir_entry.file_name().to_string_lossy().to_string(),
+            dir_entry.path(),
+            dir_entry.metadata().ok(),
+        )
+    }
+
+    /// Inspects `path` without following a final symlink, `None` if it no longer exists.
+    pub(crate) fn from_path(path: &Path) -> Option<FileEntry> {
+        let meta_data = fs::symlink_metadata(path).ok()?;
+        let name = path.file_name()?.to_string_lossy().to_string();
+        Some(FileEntry::from_parts(
+            name,
+            path.to_path_buf(),
+            Some(meta_data),
+        ))
+    }
+
+    /// From metadata a walk has already read, so nothing is statted twice.
+    pub(crate) fn from_metadata(path: PathBuf, meta_data: Metadata) -> FileEntry {
+        let name = path
+            .file_name()
+            .map_or_else(String::new, |name| name.to_string_lossy().to_string());
+        FileEntry::from_parts(name, path, Some(meta_data))
+    }
+
+    /// As `-L` shows it: a symlink is described by what it points at, a broken one stays a link.
+    pub(crate) fn dereferenced(self) -> FileEntry {
+        if self.kind != EntryKind::Symlink {
+            return self;
+        }
+        match fs::metadata(&self.path) {
+            Ok(meta_data) => FileEntry::from_parts(self.name, self.path, Some(meta_data)),
+            Err(_) => self,
+        }
+    }
+
+    fn from_parts(name: String, path: PathBuf, meta_data: Option<Metadata>) -> FileEntry {
+        let kind = match &meta_data {
+            Some(meta) if meta.file_type().is_symlink() => EntryKind::Symlink,
+            Some(meta) if meta.is_dir() => EntryKind::Directory,
+            Some(meta) if meta.is_file() => EntryKind::File,
+            _ => EntryKind::Other,
+        };
+        FileEntry {
+            name,
+            path,
+            kind,
+            size: meta_data.as_ref().map(|meta| meta.len()),
+            created: meta_data.as_ref().and_then(|meta| meta.created().ok()),
+            modified: meta_data.as_ref().and_then(|meta| meta.modified().ok()),
+            readonly: meta_data.as_ref().map(|meta| meta.permissions().readonly()),
+            mode: meta_data.as_ref().and_then(unix_mode),
+            metadata: meta_data,
+        }
+    }
+}
+
+#[cfg(unix)]
+fn unix_mode(meta_data: &Metadata) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    Some(meta_data.permissions().mode())
+}
+
+#[cfg(not(unix))]
+fn unix_mode(_meta_data: &Metadata) -> Option<u32> {
+    None
+}
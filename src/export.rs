@@ -0,0 +1,75 @@
+use crate::entries::FileEntry;
+use crate::FileEntryParsingError;
+use serde::Serialize;
+use std::path::Path;
+#[cfg(any(feature = "parquet", feature = "arrow"))]
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ExportFormat {
+    Parquet,
+    Arrow,
+}
+
+pub(crate) fn export(
+    format: ExportFormat,
+    entries: &[FileEntry],
+    target: &Path,
+) -> Result<(), FileEntryParsingError> {
+    match format {
+        ExportFormat::Parquet => export_parquet(entries, target),
+        ExportFormat::Arrow => export_arrow(entries, target),
+    }
+}
+
+#[cfg(feature = "parquet")]
+fn export_parquet(entries: &[FileEntry], target: &Path) -> Result<(), FileEntryParsingError> {
+    crate::parquet_export::export(entries, target)
+}
+
+#[cfg(not(feature = "parquet"))]
+fn export_parquet(_entries: &[FileEntry], target: &Path) -> Result<(), FileEntryParsingError> {
+    Err(missing_feature(target, "parquet"))
+}
+
+#[cfg(feature = "arrow")]
+fn export_arrow(entries: &[FileEntry], target: &Path) -> Result<(), FileEntryParsingError> {
+    crate::arrow_export::export(entries, target)
+}
+
+#[cfg(not(feature = "arrow"))]
+fn export_arrow(_entries: &[FileEntry], target: &Path) -> Result<(), FileEntryParsingError> {
+    Err(missing_feature(target, "arrow"))
+}
+
+#[cfg(not(all(feature = "parquet", feature = "arrow")))]
+fn missing_feature(target: &Path, feature: &str) -> FileEntryParsingError {
+    FileEntryParsingError::ExportFailed {
+        target: target.to_string_lossy().to_string(),
+        reason: format!("mini-ls was built without the {} feature", feature),
+    }
+}
+
+/// Timestamp columns hold signed milliseconds, so times before 1970 stay negative rather than
+/// collapsing onto the epoch.
+#[cfg(any(feature = "parquet", feature = "arrow"))]
+pub(crate) fn millis_since_epoch(time: SystemTime) -> i64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(since_epoch) => since_epoch.as_millis() as i64,
+        Err(before_epoch) => -(before_epoch.duration().as_millis() as i64),
+    }
+}
+
+#[cfg(all(test, any(feature = "parquet", feature = "arrow")))]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn times_before_the_epoch_are_negative() {
+        let second = Duration::from_millis(1500);
+        assert_eq!(millis_since_epoch(UNIX_EPOCH + second), 1500);
+        assert_eq!(millis_since_epoch(UNIX_EPOCH - second), -1500);
+    }
+}
@@ -0,0 +1,83 @@
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The ignore rules that apply to one directory of a git repository, as `git status` reads them:
+/// every `.gitignore` from the directory up to the repository root, the closest winning, then
+/// `.git/info/exclude` and finally the user's global excludes file.
+pub(crate) struct GitIgnore {
+    directory: PathBuf,
+    matchers: Vec<Gitignore>,
+}
+
+impl GitIgnore {
+    /// `None` outside a repository, where git ignores nothing.
+    pub(crate) fn for_directory(directory: &Path) -> Option<GitIgnore> {
+        let directory = fs::canonicalize(directory).ok()?;
+        let mut matchers = Vec::new();
+        for ancestor in directory.ancestors() {
+            let (matcher, _) = Gitignore::new(ancestor.join(".gitignore"));
+            matchers.push(matcher);
+            if ancestor.join(".git").exists() {
+                let mut exclude = GitignoreBuilder::new(ancestor);
+                exclude.add(ancestor.join(".git/info/exclude"));
+                matchers.extend(exclude.build().ok());
+                matchers.push(Gitignore::global().0);
+                return Some(GitIgnore {
+                    directory,
+                    matchers,
+                });
+            }
+        }
+        None
+    }
+
+    /// Whether the entry `name` in this directory is ignored, the `.git` directory always is.
+    pub(crate) fn is_ignored(&self, name: &Path, is_dir: bool) -> bool {
+        if name == Path::new(".git") {
+            return true;
+        }
+        let path = self.directory.join(name);
+        self.matchers
+            .iter()
+            .map(|matcher| matcher.matched(&path, is_dir))
+            .find(|matched| !matched.is_none())
+            .is_some_and(|matched| matches!(matched, Match::Ignore(_)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn closest_gitignore_wins_and_directories_outside_repositories_ignore_nothing() {
+        let temp_dir = tempdir().unwrap();
+        assert!(GitIgnore::for_directory(temp_dir.path()).is_none());
+        fs::create_dir(temp_dir.path().join(".git")).unwrap();
+        fs::write(
+            temp_dir.path().join(".gitignore"),
+            "target/\n*.log\n!keep.log\n",
+        )
+        .unwrap();
+        fs::create_dir(temp_dir.path().join("sub")).unwrap();
+        fs::write(
+            temp_dir.path().join("sub/.gitignore"),
+            "!debug.log\nlocal.txt\n",
+        )
+        .unwrap();
+        let root = GitIgnore::for_directory(temp_dir.path()).unwrap();
+        assert!(root.is_ignored(Path::new("target"), true));
+        assert!(!root.is_ignored(Path::new("target"), false));
+        assert!(root.is_ignored(Path::new("build.log"), false));
+        assert!(!root.is_ignored(Path::new("keep.log"), false));
+        assert!(root.is_ignored(Path::new(".git"), true));
+        assert!(!root.is_ignored(Path::new("src"), true));
+        let sub = GitIgnore::for_directory(&temp_dir.path().join("sub")).unwrap();
+        assert!(sub.is_ignored(Path::new("local.txt"), false));
+        assert!(sub.is_ignored(Path::new("trace.log"), false));
+        assert!(!sub.is_ignored(Path::new("debug.log"), false));
+    }
+}
@@ -0,0 +1,132 @@
+use serde::Serialize;
+use std::fs::Metadata;
+use std::time::{Duration, SystemTime};
+
+const DAY_SECONDS: u64 = 24 * 60 * 60;
+
+/// A threshold as given on the command line, kept so the marker can quote it back.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Threshold<T> {
+    pub limit: T,
+    pub text: String,
+}
+
+/// Entries crossing either threshold get a marker after their row, so stale or oversized files
+/// stand out without disturbing the columns.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct Highlights {
+    pub older_than: Option<Threshold<Duration>>,
+    pub larger_than: Option<Threshold<u64>>,
+}
+
+impl Highlights {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.older_than.is_none() && self.larger_than.is_none()
+    }
+
+    pub(crate) fn marker(&self, meta_data: &Metadata, now: SystemTime) -> String {
+        let mut reasons = Vec::new();
+        if let Some(threshold) = &self.older_than {
+            let age = meta_data
+                .modified()
+                .ok()
+                .and_then(|modified| now.duration_since(modified).ok());
+            if age.is_some_and(|age| age > threshold.limit) {
+                reasons.push(format!("older than {}", threshold.text));
+            }
+        }
+        if let Some(threshold) = &self.larger_than {
+            if meta_data.is_file() && meta_data.len() > threshold.limit {
+                reasons.push(format!("larger than {}", threshold.text));
+            }
+        }
+        if reasons.is_empty() {
+            String::new()
+        } else {
+            format!("  [{}]", reasons.join(", "))
+        }
+    }
+}
+
+/// A whole number followed by `m`, `h`, `d`, `w` or `y`, e.g. `90d`.
+pub(crate) fn parse_age(text: &str) -> Option<Duration> {
+    let unit = text.chars().last()?;
+    let number = &text[..text.len() - unit.len_utf8()];
+    let seconds = match unit {
+        'm' => 60,
+        'h' => 60 * 60,
+        'd' => DAY_SECONDS,
+        'w' => 7 * DAY_SECONDS,
+        'y' => 365 * DAY_SECONDS,
+        _ => return None,
+    };
+    Some(Duration::from_secs(
+        number.parse::<u64>().ok()?.checked_mul(seconds)?,
+    ))
+}
+
+/// Bytes, or a whole number with a binary `K`, `M`, `G` or `T` suffix to match the size column.
+pub(crate) fn parse_size(text: &str) -> Option<u64> {
+    let upper = text.to_ascii_uppercase();
+    let upper = upper
+        .strip_suffix("IB")
+        .or_else(|| upper.strip_suffix('B'))
+        .unwrap_or(&upper);
+    let (number, multiplier) = match upper.char_indices().last()? {
+        (i, 'K') => (&upper[..i], 1u64 << 10),
+        (i, 'M') => (&upper[..i], 1 << 20),
+        (i, 'G') => (&upper[..i], 1 << 30),
+        (i, 'T') => (&upper[..i], 1 << 40),
+        _ => (upper, 1),
+    };
+    number.parse::<u64>().ok()?.checked_mul(multiplier)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn parses_ages_and_sizes() {
+        assert_eq!(
+            parse_age("90d"),
+            Some(Duration::from_secs(90 * DAY_SECONDS))
+        );
+        assert_eq!(parse_age("2w"), Some(Duration::from_secs(14 * DAY_SECONDS)));
+        assert_eq!(parse_age("90"), None);
+        assert_eq!(parse_age("d"), None);
+        assert_eq!(parse_size("1G"), Some(1 << 30));
+        assert_eq!(parse_size("500MiB"), Some(500 << 20));
+        assert_eq!(parse_size("10kb"), Some(10 << 10));
+        assert_eq!(parse_size("4096"), Some(4096));
+        assert_eq!(parse_size("lots"), None);
+    }
+
+    #[test]
+    fn marks_entries_past_either_threshold() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("big.bin");
+        fs::write(&path, vec![0u8; 2048]).unwrap();
+        let meta_data = fs::metadata(&path).unwrap();
+        let highlights = Highlights {
+            older_than: Some(Threshold {
+                limit: Duration::from_secs(DAY_SECONDS),
+                text: String::from("1d"),
+            }),
+            larger_than: Some(Threshold {
+                limit: 1024,
+                text: String::from("1K"),
+            }),
+        };
+        let now = SystemTime::now();
+        assert_eq!(highlights.marker(&meta_data, now), "  [larger than 1K]");
+        let later = now + Duration::from_secs(2 * DAY_SECONDS);
+        assert_eq!(
+            highlights.marker(&meta_data, later),
+            "  [older than 1d, larger than 1K]"
+        );
+        assert_eq!(Highlights::default().marker(&meta_data, now), "");
+    }
+}
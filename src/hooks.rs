@@ -0,0 +1,81 @@
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::process::Command;
+
+const TARGET_VARIABLE: &str = "MINI_LS_HOOK_TARGET";
+const OUTPUT_VARIABLE: &str = "MINI_LS_HOOK_OUTPUT";
+
+/// Shell commands from the `[hooks]` table of the config file. `before` runs ahead of the
+/// listing and stops it when it fails, `after` runs once the listing has been written. With
+/// `--watch` and `--follow` they run around the first frame only, not on every redraw.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct Hooks {
+    pub(crate) before: Option<String>,
+    pub(crate) after: Option<String>,
+}
+
+/// Runs `command` through the shell with the target and output path, `-` for stdout, in the
+/// environment. Whatever it prints goes to stderr so it never ends up in the listing.
+pub(crate) fn run(
+    stage: &str,
+    command: Option<&str>,
+    target: &str,
+    output: &str,
+) -> io::Result<()> {
+    let Some(command) = command else {
+        return Ok(());
+    };
+    let status = shell(command)
+        .env(TARGET_VARIABLE, target)
+        .env(OUTPUT_VARIABLE, output)
+        .stdout(io::stderr())
+        .status()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!(
+            "the {} hook `{}` failed with {}",
+            stage, command, status
+        )))
+    }
+}
+
+#[cfg(unix)]
+pub(crate) fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command);
+    shell
+}
+
+#[cfg(not(unix))]
+pub(crate) fn shell(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.arg("/C").arg(command);
+    shell
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    #[cfg(unix)]
+    fn hooks_see_the_target_and_output_and_report_failures() {
+        let temp_dir = tempdir().unwrap();
+        let record = temp_dir.path().join("record.txt");
+        let command = format!(
+            "echo \"$MINI_LS_HOOK_TARGET $MINI_LS_HOOK_OUTPUT\" > {}",
+            record.display()
+        );
+        run("before", Some(&command), "/srv/share", "-").unwrap();
+        assert_eq!(fs::read_to_string(&record).unwrap(), "/srv/share -\n");
+        run("after", None, "/srv/share", "-").unwrap();
+        let error = run("after", Some("exit 3"), "/srv/share", "-").unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("the after hook `exit 3` failed"));
+    }
+}
@@ -0,0 +1,139 @@
+use crate::entries::{EntryKind, FileEntry};
+use crate::machine_output::format_timestamp;
+use crate::output_formatting::format_size;
+
+const STYLE: &str = "body { font-family: sans-serif; margin: 2em; }
+table { border-collapse: collapse; }
+th, td { padding: 0.25em 1em; text-align: left; }
+th { cursor: pointer; border-bottom: 2px solid #888; user-select: none; }
+th[data-order=asc]::after { content: \" \\25B2\"; }
+th[data-order=desc]::after { content: \" \\25BC\"; }
+tbody tr:nth-child(even) { background: #f3f3f3; }
+td.size { text-align: right; }";
+
+/// Clicking a heading sorts the rows by that column's `data-value`, clicking again reverses it.
+const SORT_SCRIPT: &str =
+    "document.querySelectorAll('th').forEach((th, column) => th.addEventListener('click', () => {
+  const body = th.closest('table').tBodies[0];
+  const ascending = th.dataset.order !== 'asc';
+  th.parentNode.querySelectorAll('th').forEach(other => delete other.dataset.order);
+  th.dataset.order = ascending ? 'asc' : 'desc';
+  const value = row => row.cells[column].dataset.value;
+  const numeric = th.dataset.type === 'number';
+  [...body.rows].sort((a, b) => {
+    const order = numeric ? Number(value(a)) - Number(value(b)) : value(a).localeCompare(value(b));
+    return ascending ? order : -order;
+  }).forEach(row => body.appendChild(row));
+}));";
+
+/// A standalone page with a table of the entries, meant to be written into the listed directory
+/// with `-F index.html`, so names link relative to it.
+pub(crate) fn render_html(target: &str, entries: &[FileEntry]) -> String {
+    render_index(target, entries, ".", None)
+}
+
+/// The index page of both `--html` and `serve`: each name links to `base` followed by the name and
+/// `parent`, when given, is linked above the table.
+pub(crate) fn render_index(
+    target: &str,
+    entries: &[FileEntry],
+    base: &str,
+    parent: Option<&str>,
+) -> String {
+    let title = format!("Index of {}", escape(target));
+    let mut lines = vec![
+        String::from("<!DOCTYPE html>"),
+        String::from("<html lang=\"en\">"),
+        String::from("<head>"),
+        String::from("<meta charset=\"utf-8\">"),
+        format!("<title>{}</title>", title),
+        format!("<style>\n{}\n</style>", STYLE),
+        String::from("</head>"),
+        String::from("<body>"),
+        format!("<h1>{}</h1>", title),
+    ];
+    if let Some(parent) = parent {
+        lines.push(format!("<p><a href=\"{}\">../</a></p>", escape(parent)));
+    }
+    lines.extend([
+        String::from("<table>"),
+        String::from("<thead><tr><th>Name</th><th>Kind</th><th data-type=\"number\">Size</th><th>Modified</th></tr></thead>"),
+        String::from("<tbody>"),
+    ]);
+    lines.extend(entries.iter().map(|entry| entry_row(entry, base)));
+    lines.extend([
+        String::from("</tbody>"),
+        String::from("</table>"),
+        format!("<script>\n{}\n</script>", SORT_SCRIPT),
+        String::from("</body>"),
+        String::from("</html>"),
+    ]);
+    lines.join("\n")
+}
+
+fn entry_row(entry: &FileEntry, base: &str) -> String {
+    let name = escape(&entry.name);
+    let href = format!("{}/{}", base, encode_segment(&entry.name));
+    let href = if entry.kind == EntryKind::Directory {
+        href + "/"
+    } else {
+        href
+    };
+    let (size_value, size) = match entry.size {
+        Some(size) => (size.to_string(), format_size(size)),
+        None => (String::from("-1"), String::from("-")),
+    };
+    let modified = entry.modified.map_or(String::from("-"), format_timestamp);
+    format!(
+        "<tr><td data-value=\"{name}\"><a href=\"{href}\">{name}</a></td><td data-value=\"{kind}\">{kind}</td><td class=\"size\" data-value=\"{size_value}\">{size}</td><td data-value=\"{modified}\">{modified}</td></tr>",
+        kind = entry.kind.as_str(),
+    )
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Percent encodes everything but unreserved characters, which also keeps names such as
+/// `a:b` from being read as a URL scheme.
+fn encode_segment(name: &str) -> String {
+    name.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn entries_become_escaped_linked_rows() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("<b> & c.txt");
+        fs::write(&path, "12").unwrap();
+        fs::create_dir(temp_dir.path().join("sub dir")).unwrap();
+        let entries = vec![
+            FileEntry::from_path(&path).unwrap(),
+            FileEntry::from_path(&temp_dir.path().join("sub dir")).unwrap(),
+        ];
+        let page = render_html("<dir>", &entries);
+        assert!(page.starts_with("<!DOCTYPE html>"));
+        assert!(page.contains("<h1>Index of &lt;dir&gt;</h1>"));
+        assert!(page.contains(
+            "<a href=\"./%3Cb%3E%20%26%20c.txt\">&lt;b&gt; &amp; c.txt</a></td><td data-value=\"file\">file</td><td class=\"size\" data-value=\"2\">2 B</td>"
+        ));
+        assert!(page.contains("<a href=\"./sub%20dir/\">sub dir</a>"));
+        assert!(page.ends_with("</html>"));
+    }
+}
@@ -0,0 +1,182 @@
+use crate::entries::{EntryKind, FileEntry};
+use crate::walker::{walk, WalkedEntry};
+use crate::FileEntryParsingError;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+/// In-memory copy of a tree keyed by directory so listings are answered without touching disk.
+pub(crate) struct Index {
+    root: PathBuf,
+    children: HashMap<PathBuf, BTreeMap<String, FileEntry>>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct IndexStats {
+    pub(crate) files: usize,
+    pub(crate) directories: usize,
+    pub(crate) other: usize,
+    pub(crate) total_bytes: u64,
+}
+
+impl Index {
+    pub(crate) fn build(root: &Path) -> Result<Index, FileEntryParsingError> {
+        let mut index = Index {
+            root: root.to_path_buf(),
+            children: HashMap::from([(root.to_path_buf(), BTreeMap::new())]),
+        };
+        index.scan(root)?;
+        Ok(index)
+    }
+
+    pub(crate) fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub(crate) fn directories(&self) -> impl Iterator<Item = &PathBuf> {
+        self.children.keys()
+    }
+
+    pub(crate) fn list(&self, dir: &Path) -> Option<Vec<FileEntry>> {
+        self.children
+            .get(dir)
+            .map(|entries| entries.values().cloned().collect())
+    }
+
+    pub(crate) fn stats(&self) -> IndexStats {
+        let mut stats = IndexStats {
+            files: 0,
+            directories: 0,
+            other: 0,
+            total_bytes: 0,
+        };
+        for entry in self.children.values().flat_map(|entries| entries.values()) {
+            match entry.kind {
+                EntryKind::File => {
+                    stats.files += 1;
+                    stats.total_bytes += entry.size.unwrap_or(0);
+                }
+                EntryKind::Directory => stats.directories += 1,
+                _ => stats.other += 1,
+            }
+        }
+        stats
+    }
+
+    /// Brings `path` back in line with the disk after a change notification and returns any
+    /// directories that were newly indexed so the caller can start watching them.
+    pub(crate) fn refresh(&mut self, path: &Path) -> Vec<PathBuf> {
+        let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+            return Vec::new();
+        };
+        let name = name.to_string_lossy().to_string();
+        let Some(entry) = FileEntry::from_path(path) else {
+            if let Some(siblings) = self.children.get_mut(parent) {
+                siblings.remove(&name);
+            }
+            self.remove_subtree(path);
+            return Vec::new();
+        };
+        let Some(siblings) = self.children.get_mut(parent) else {
+            return Vec::new();
+        };
+        let is_dir = entry.kind == EntryKind::Directory;
+        siblings.insert(name, entry);
+        if !is_dir {
+            self.remove_subtree(path);
+            return Vec::new();
+        }
+        if self.children.contains_key(path) {
+            return Vec::new();
+        }
+        self.children.insert(path.to_path_buf(), BTreeMap::new());
+        let mut added = vec![path.to_path_buf()];
+        if let Ok(nested) = self.scan(path) {
+            added.extend(nested);
+        }
+        added
+    }
+
+    /// Indexes everything below `dir` and returns the directories found on the way.
+    fn scan(&mut self, dir: &Path) -> Result<Vec<PathBuf>, FileEntryParsingError> {
+        let mut found = Vec::new();
+        walk(dir, &[], &mut |entry: WalkedEntry| {
+            found.push((entry.path, FileEntry::from_dir_entry(&entry.dir_entry)));
+        })?;
+        let mut directories = Vec::new();
+        for (path, entry) in found {
+            if entry.kind == EntryKind::Directory {
+                self.children.entry(path.clone()).or_default();
+                directories.push(path.clone());
+            }
+            if let Some(parent) = path.parent() {
+                self.children
+                    .entry(parent.to_path_buf())
+                    .or_default()
+                    .insert(entry.name.clone(), entry);
+            }
+        }
+        Ok(directories)
+    }
+
+    fn remove_subtree(&mut self, dir: &Path) {
+        let Some(entries) = self.children.remove(dir) else {
+            return;
+        };
+        for entry in entries.values() {
+            if entry.kind == EntryKind::Directory {
+                self.remove_subtree(&entry.path);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn names(entries: Vec<FileEntry>) -> Vec<String> {
+        entries.into_iter().map(|entry| entry.name).collect()
+    }
+
+    #[test]
+    fn lists_each_directory_from_memory() {
+        let temp_dir = tempdir().unwrap();
+        fs::create_dir(temp_dir.path().join("nested")).unwrap();
+        fs::write(temp_dir.path().join("b.txt"), "12").unwrap();
+        fs::write(temp_dir.path().join("nested").join("a.txt"), "123").unwrap();
+        let index = Index::build(temp_dir.path()).unwrap();
+        assert_eq!(
+            names(index.list(temp_dir.path()).unwrap()),
+            vec!["b.txt", "nested"]
+        );
+        assert_eq!(
+            names(index.list(&temp_dir.path().join("nested")).unwrap()),
+            vec!["a.txt"]
+        );
+        let stats = index.stats();
+        assert_eq!((stats.files, stats.directories), (2, 1));
+        assert_eq!(stats.total_bytes, 5);
+    }
+
+    #[test]
+    fn refresh_picks_up_new_directories_and_removals() {
+        let temp_dir = tempdir().unwrap();
+        let mut index = Index::build(temp_dir.path()).unwrap();
+        let moved_in = temp_dir.path().join("moved_in");
+        fs::create_dir_all(moved_in.join("deeper")).unwrap();
+        fs::write(moved_in.join("deeper").join("c.txt"), "").unwrap();
+        let mut new_dirs = index.refresh(&moved_in);
+        new_dirs.sort();
+        assert_eq!(new_dirs, vec![moved_in.clone(), moved_in.join("deeper")]);
+        assert_eq!(
+            names(index.list(&moved_in.join("deeper")).unwrap()),
+            vec!["c.txt"]
+        );
+        fs::remove_dir_all(&moved_in).unwrap();
+        index.refresh(&moved_in);
+        assert!(index.list(&moved_in.join("deeper")).is_none());
+        assert!(index.list(temp_dir.path()).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+use std::ffi::{CString, OsStr};
+use std::io;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+
+const WATCH_MASK: u32 = libc::IN_CREATE
+    | libc::IN_DELETE
+    | libc::IN_MODIFY
+    | libc::IN_ATTRIB
+    | libc::IN_CLOSE_WRITE
+    | libc::IN_MOVED_FROM
+    | libc::IN_MOVED_TO
+    | libc::IN_ONLYDIR;
+const EVENT_BUFFER_SIZE: usize = 64 * 1024;
+
+pub(crate) enum Change {
+    Path(PathBuf),
+    Overflow,
+}
+
+/// inotify watches on individual directories, shared by the index daemon and `--follow`.
+pub(crate) struct Watcher {
+    fd: OwnedFd,
+    watched: HashMap<i32, PathBuf>,
+}
+
+impl Watcher {
+    pub(crate) fn new() -> io::Result<Watcher> {
+        let fd = unsafe { libc::inotify_init1(libc::IN_CLOEXEC) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Watcher {
+            fd: unsafe { OwnedFd::from_raw_fd(fd) },
+            watched: HashMap::new(),
+        })
+    }
+
+    /// Directories that vanish before the watch is added are ignored, the index drops them anyway.
+    pub(crate) fn watch(&mut self, dir: &Path) {
+        let Ok(c_path) = CString::new(dir.as_os_str().as_bytes()) else {
+            return;
+        };
+        let descriptor =
+            unsafe { libc::inotify_add_watch(self.fd.as_raw_fd(), c_path.as_ptr(), WATCH_MASK) };
+        if descriptor >= 0 {
+            self.watched.insert(descriptor, dir.to_path_buf());
+        }
+    }
+
+    pub(crate) fn unwatch_all(&mut self) {
+        for descriptor in self.watched.keys() {
+            unsafe { libc::inotify_rm_watch(self.fd.as_raw_fd(), *descriptor) };
+        }
+        self.watched.clear();
+    }
+
+    /// Blocks until at least one event is available.
+    pub(crate) fn read_changes(&mut self) -> io::Result<Vec<Change>> {
+        let mut buffer = vec![0u8; EVENT_BUFFER_SIZE];
+        let read = unsafe {
+            libc::read(
+                self.fd.as_raw_fd(),
+                buffer.as_mut_ptr() as *mut libc::c_void,
+                buffer.len(),
+            )
+        };
+        if read < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let header_size = std::mem::size_of::<libc::inotify_event>();
+        let mut changes = Vec::new();
+        let mut offset = 0;
+        while offset + header_size <= read as usize {
+            let event: libc::inotify_event =
+                unsafe { std::ptr::read_unaligned(buffer[offset..].as_ptr() as *const _) };
+            let name_bytes =
+                &buffer[offset + header_size..offset + header_size + event.len as usize];
+            offset += header_size + event.len as usize;
+            if event.mask & libc::IN_Q_OVERFLOW != 0 {
+                changes.push(Change::Overflow);
+                continue;
+            }
+            if event.mask & libc::IN_IGNORED != 0 {
+                self.watched.remove(&event.wd);
+                continue;
+            }
+            let Some(dir) = self.watched.get(&event.wd) else {
+                continue;
+            };
+            let name_end = name_bytes
+                .iter()
+                .position(|byte| *byte == 0)
+                .unwrap_or(name_bytes.len());
+            if name_end > 0 {
+                changes.push(Change::Path(
+                    dir.join(OsStr::from_bytes(&name_bytes[..name_end])),
+                ));
+            }
+        }
+        Ok(changes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::thread;
+    use std::time::Duration;
+    use tempfile::tempdir;
+
+    #[test]
+    fn watcher_reports_created_entries() {
+        let temp_dir = tempdir().unwrap();
+        let mut watcher = Watcher::new().unwrap();
+        watcher.watch(temp_dir.path());
+        let created = temp_dir.path().join("new.txt");
+        let writer_target = created.clone();
+        let writer = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            fs::write(writer_target, "").unwrap();
+        });
+        let changes = watcher.read_changes().unwrap();
+        writer.join().unwrap();
+        assert!(changes
+            .iter()
+            .any(|change| matches!(change, Change::Path(path) if *path == created)));
+    }
+}
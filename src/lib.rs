@@ -1,28 +1,122 @@
+mod access;
 pub mod arg_processing;
+#[cfg(feature = "arrow")]
+mod arrow_export;
+mod audit;
+mod checksum;
+pub mod color;
+pub mod columns;
+pub mod compat;
+mod completions;
+mod config_file;
+#[cfg(target_os = "linux")]
+mod daemon;
+pub mod delimited;
+#[cfg(unix)]
+mod diagnostics;
+mod doctor;
+pub mod encoding;
+pub mod entries;
+pub mod export;
+mod git_ignore;
+pub mod highlight;
+mod hooks;
+mod html;
+#[cfg(target_os = "linux")]
+mod index;
+#[cfg(target_os = "linux")]
+mod inotify;
+pub mod locale;
+pub mod machine_output;
+mod mounts;
+mod normalisation;
 mod output_formatting;
+mod pager;
+#[cfg(feature = "parquet")]
+mod parquet_export;
+mod picker;
+pub mod reports;
+pub mod search;
+mod server;
+pub mod sorting;
+mod template;
+mod tree;
+#[cfg(unix)]
+mod users;
+mod virtual_fs;
+mod walker;
 
-use crate::arg_processing::Config;
+use crate::arg_processing::{Config, ConfigAction, IndexMode, KindFilter, STDOUT_TARGET};
+use crate::config_file::ConfigFile;
+use crate::entries::{EntryKind, FileEntry};
+use crate::git_ignore::GitIgnore;
+use crate::machine_output::MachineFormat;
+use crate::search::NamePattern;
+use crate::sorting::SortKey;
 use crate::FileEntryParsingError::UnableToCalculatePathLengths;
 
-use output_formatting::FormattingCommand;
+use output_formatting::{DirGrouping, FormattingCommand, GridOrder, PathStyle};
+use std::collections::HashMap;
+use std::ffi::OsStr;
 use std::fmt::Formatter;
 use std::fs::{DirEntry, ReadDir};
-use std::io::ErrorKind;
+use std::io::{ErrorKind, IsTerminal, Write};
 
-use std::path::Path;
-use std::{fmt, fs, io};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use std::{fmt, fs, io, thread};
 
 #[derive(Debug, Clone)]
 pub enum FileEntryParsingError {
     UnableToReadDir {
         target: String,
         original_error: io::ErrorKind,
+        diagnosis: Option<String>,
     },
     FileNameInvalidUnicode,
     MissingMetaDataError {
         original_error: io::ErrorKind,
     },
     UnableToCalculatePathLengths,
+    UnableToReadManifest {
+        target: String,
+        original_error: io::ErrorKind,
+    },
+    InvalidManifestLine {
+        line: usize,
+    },
+    ExportFailed {
+        target: String,
+        reason: String,
+    },
+    TerminalTooNarrow {
+        required: usize,
+        actual: usize,
+    },
+}
+
+impl FileEntryParsingError {
+    pub(crate) fn unreadable_dir(target: &Path, error: &io::Error) -> Self {
+        FileEntryParsingError::UnableToReadDir {
+            target: target.to_string_lossy().to_string(),
+            original_error: error.kind(),
+            diagnosis: diagnose(target, error),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn diagnose(target: &Path, error: &io::Error) -> Option<String> {
+    if error.kind() == ErrorKind::PermissionDenied {
+        diagnostics::explain_permission_denied(target)
+    } else {
+        None
+    }
+}
+
+#[cfg(not(unix))]
+fn diagnose(_target: &Path, _error: &io::Error) -> Option<String> {
+    None
 }
 
 enum TimeOptions {
@@ -36,11 +130,18 @@ impl fmt::Display for FileEntryParsingError {
             FileEntryParsingError::UnableToReadDir {
                 target,
                 original_error,
-            } => write!(
-                f,
-                "was unable to read the contents of {} due to {:?}",
-                target, original_error
-            ),
+                diagnosis,
+            } => {
+                write!(
+                    f,
+                    "was unable to read the contents of {} due to {:?}",
+                    target, original_error
+                )?;
+                match diagnosis {
+                    Some(diagnosis) => write!(f, " - {}", diagnosis),
+                    None => Ok(()),
+                }
+            }
             FileEntryParsingError::FileNameInvalidUnicode => {
                 write!(f, "file entry did not consist of valid unicode")
             }
@@ -50,94 +151,938 @@ impl fmt::Display for FileEntryParsingError {
             UnableToCalculatePathLengths => {
                 write!(f, "unable to calculate the length of any paths")
             }
+            FileEntryParsingError::UnableToReadManifest {
+                target,
+                original_error,
+            } => write!(
+                f,
+                "was unable to read the manifest {} due to {:?}",
+                target, original_error
+            ),
+            FileEntryParsingError::InvalidManifestLine { line } => {
+                write!(f, "manifest line {} is not in sha256sum format", line)
+            }
+            FileEntryParsingError::ExportFailed { target, reason } => {
+                write!(
+                    f,
+                    "unable to export the listing to {} due to {}",
+                    target, reason
+                )
+            }
+            FileEntryParsingError::TerminalTooNarrow { required, actual } => write!(
+                f,
+                "the listing needs at least {} columns but only {} are available, widen the \
+                 terminal or pass --width",
+                required, actual
+            ),
         }
     }
 }
 
+impl std::error::Error for FileEntryParsingError {}
+
 impl From<FileEntryParsingError> for io::Error {
     fn from(value: FileEntryParsingError) -> Self {
-        match value {
-            FileEntryParsingError::UnableToReadDir { original_error, .. } => {
-                std::io::Error::from(original_error)
-            }
-            FileEntryParsingError::FileNameInvalidUnicode => {
-                std::io::Error::from(ErrorKind::InvalidData)
+        let kind = match &value {
+            FileEntryParsingError::UnableToReadDir { original_error, .. } => *original_error,
+            FileEntryParsingError::FileNameInvalidUnicode => ErrorKind::InvalidData,
+            FileEntryParsingError::MissingMetaDataError { original_error, .. } => *original_error,
+            UnableToCalculatePathLengths => ErrorKind::InvalidData,
+            FileEntryParsingError::UnableToReadManifest { original_error, .. } => *original_error,
+            FileEntryParsingError::InvalidManifestLine { .. } => ErrorKind::InvalidData,
+            FileEntryParsingError::ExportFailed { .. } => ErrorKind::Other,
+            FileEntryParsingError::TerminalTooNarrow { .. } => ErrorKind::InvalidInput,
+        };
+        std::io::Error::new(kind, value)
+    }
+}
+
+const DEFAULT_WIDTH: usize = 80;
+/// Clears the terminal and moves the cursor home so `--watch` redraws in place.
+const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
+/// How long `--follow` lets changes gather before redrawing, so a busy download is not redrawn
+/// on every write.
+#[cfg(target_os = "linux")]
+const FOLLOW_DEBOUNCE: Duration = Duration::from_millis(100);
+
+fn list_contents(config: &Config, width: usize) -> Result<String, FileEntryParsingError> {
+    let target = Path::new(&config.target);
+    if let Some(pattern) = &config.find_pattern {
+        let (directories, files) = found_entries(config, target, pattern)?;
+        return render_listing(config, target, width, files, directories, true);
+    }
+    if config.tree {
+        return render_tree(config);
+    }
+    // a depth of 1 is the target alone, which is just the flat listing
+    if config.recursive && config.depth != Some(1) {
+        return list_recursively(config, width);
+    }
+    let (directories, files) = walk_listing(config, false)?
+        .pop()
+        .and_then(|listed| listed.contents.ok())
+        .unwrap_or_default();
+    render_listing(
+        config,
+        target,
+        width,
+        matching_glob(config, files),
+        matching_glob(config, directories),
+        false,
+    )
+}
+
+/// A directory read by `walk_listing`.
+struct ListedDirectory {
+    path: PathBuf,
+    /// Its directories and files once hidden entries, `--exclude` and `--git-ignore` are left
+    /// out. The glob, `--match` and `--files`/`--dirs` are not applied yet as they only thin out
+    /// what is shown, not what is descended into. A nested directory that cannot be read has
+    /// the reason instead.
+    contents: io::Result<(Vec<FileEntry>, Vec<FileEntry>)>,
+}
+
+/// Reads the target and, when `descend` is set, every directory below it up to `--depth`, each
+/// parent before its children and siblings by name as `ls -R` orders them. Every listing reads
+/// its directories here so flat, `-R`, `--tree`, `--pick` and `list_entries` all see the same
+/// entries. Symlinked directories are not followed, only an unreadable target is an error.
+fn walk_listing(
+    config: &Config,
+    descend: bool,
+) -> Result<Vec<ListedDirectory>, FileEntryParsingError> {
+    let target = PathBuf::from(&config.target);
+    let mut listed = Vec::new();
+    let mut pending = vec![(target.clone(), 1)];
+    while let Some((path, level)) = pending.pop() {
+        let contents = fs::read_dir(&path).map(|contents| listed_entries(config, &path, contents));
+        match &contents {
+            Err(original_error) if path == target => {
+                return Err(FileEntryParsingError::unreadable_dir(
+                    &target,
+                    original_error,
+                ))
             }
-            FileEntryParsingError::MissingMetaDataError { original_error, .. } => {
-                std::io::Error::from(original_error)
+            Ok((directories, _)) if descend && config.depth.is_none_or(|depth| level < depth) => {
+                // `-L` groups links to directories with the directories but they are not entered
+                let mut nested: Vec<PathBuf> = directories
+                    .iter()
+                    .filter(|entry| !entry.path.is_symlink())
+                    .map(|entry| entry.path.clone())
+                    .collect();
+                nested.sort();
+                pending.extend(nested.into_iter().rev().map(|path| (path, level + 1)));
             }
-            UnableToCalculatePathLengths => std::io::Error::from(ErrorKind::InvalidData),
+            _ => {}
         }
+        listed.push(ListedDirectory { path, contents });
     }
+    Ok(listed)
 }
 
-fn list_contents(config: &Config, width: usize) -> Result<String, FileEntryParsingError> {
-    let dir_read = fs::read_dir(&config.target);
-    match dir_read {
-        Ok(file_collection) => Ok(convert_read_dir_to_filename_collection(
-            file_collection,
-            config.extended_attributes,
-            width,
-        )?),
-        Err(original_error) => {
-            let error_kind = original_error.kind();
-            Err(FileEntryParsingError::UnableToReadDir {
-                target: config.target.to_string(),
-                original_error: error_kind,
-            })
+/// `-R` lists the target and then every directory below it depth first, each in its own `path:`
+/// section as `ls -R` does. A nested directory that cannot be read gets the reason in its section
+/// rather than ending the listing. Machine readable formats get one document covering every
+/// entry instead.
+fn list_recursively(config: &Config, width: usize) -> Result<String, FileEntryParsingError> {
+    let machine_readable = config.machine_format.is_some() || config.export.is_some();
+    let mut sections = Vec::new();
+    let mut entries = Vec::new();
+    for ListedDirectory { path, contents } in walk_listing(config, true)? {
+        let (directories, files) = match contents {
+            Ok(contents) => contents,
+            Err(original_error) => {
+                let error = FileEntryParsingError::unreadable_dir(&path, &original_error);
+                sections.push(format!("{}:\n{}", path.display(), error));
+                continue;
+            }
+        };
+        let (directories, files) = (
+            matching_glob(config, directories),
+            matching_glob(config, files),
+        );
+        if machine_readable {
+            entries.extend(files.into_iter().chain(directories));
+            continue;
+        }
+        if files.is_empty() && directories.is_empty() {
+            sections.push(format!("{}:", path.display()));
+            continue;
         }
+        let listing = render_listing(config, &path, width, files, directories, false)?;
+        sections.push(format!("{}:\n{}", path.display(), listing));
     }
+    if machine_readable {
+        return render_entries(config, entries);
+    }
+    Ok(sections.join("\n\n"))
+}
+
+fn writes_to_terminal(config: &Config) -> bool {
+    !config.to_file && io::stdout().is_terminal()
+}
+
+/// `--tree` draws what `walk_listing` read. Every directory is kept so the glob only thins out
+/// files, and each level is sorted and grouped whether or not a sort was asked for.
+fn render_tree(config: &Config) -> Result<String, FileEntryParsingError> {
+    let key = config.sort.unwrap_or(SortKey::Name);
+    let mut children: HashMap<PathBuf, io::Result<Vec<FileEntry>>> = HashMap::new();
+    for ListedDirectory { path, contents } in walk_listing(config, true)? {
+        let ordered = contents.map(|(mut directories, files)| {
+            let mut files = matching_glob(config, files);
+            sorting::sort_entries(&mut files, key, config.reverse);
+            sorting::sort_entries(&mut directories, key, config.reverse);
+            match config.dir_grouping {
+                DirGrouping::First => directories.into_iter().chain(files).collect(),
+                DirGrouping::Last => files.into_iter().chain(directories).collect(),
+                DirGrouping::Mixed => {
+                    let mut entries: Vec<FileEntry> =
+                        files.into_iter().chain(directories).collect();
+                    sorting::sort_entries(&mut entries, key, config.reverse);
+                    entries
+                }
+            }
+        });
+        children.insert(path, ordered);
+    }
+    let target = Path::new(&config.target);
+    tree::render(target, config.depth, config.icons, |directory| {
+        children.remove(directory).unwrap_or_else(|| Ok(Vec::new()))
+    })
+    .map_err(|original_error| FileEntryParsingError::unreadable_dir(target, &original_error))
 }
 
-fn convert_read_dir_to_filename_collection(
-    file_collection: ReadDir,
-    extended_attr: bool,
+fn render_listing(
+    config: &Config,
+    target: &Path,
     width: usize,
+    files: Vec<FileEntry>,
+    directories: Vec<FileEntry>,
+    recursive: bool,
 ) -> Result<String, FileEntryParsingError> {
-    let (directories, files): (Vec<DirEntry>, Vec<DirEntry>) =
-        split_into_files_and_dirs(file_collection);
-    output_formatting::generate_textual_display(FormattingCommand::new(
-        extended_attr,
-        width,
-        files,
-        directories,
-    ))
+    let path_style = config
+        .path_style
+        .or(recursive.then_some(PathStyle::Relative));
+    let virtual_fs = virtual_fs::detect(target);
+    if let Some(template) = &config.template {
+        let time_format = config.time_format.as_deref();
+        return Ok(in_display_order(config, files, directories)
+            .iter()
+            .map(|entry| template.render(entry, time_format))
+            .collect::<Vec<_>>()
+            .join("\n"));
+    }
+    let (mut files, mut directories) = (files, directories);
+    if config.sort.is_some() || config.reverse {
+        let key = config.sort.unwrap_or(SortKey::Name);
+        sorting::sort_entries(&mut files, key, config.reverse);
+        sorting::sort_entries(&mut directories, key, config.reverse);
+    }
+    if config.export.is_some() || config.machine_format.is_some() {
+        return render_entries(config, files.into_iter().chain(directories).collect());
+    }
+    if config.dired || config.compat.is_some() {
+        let mut entries: Vec<&FileEntry> = files.iter().chain(directories.iter()).collect();
+        let key = config.sort.unwrap_or(SortKey::Name);
+        entries.sort_by(|left, right| sorting::compare(*left, *right, key, config.reverse));
+        return Ok(match config.compat {
+            Some(style) => compat::render(
+                style,
+                target,
+                &entries,
+                path_style.unwrap_or(PathStyle::Name),
+            ),
+            None => compat::render_dired(target, &entries, path_style.unwrap_or(PathStyle::Name)),
+        });
+    }
+    let command = FormattingCommand::new(config.extended_attributes, width, files, directories)
+        .with_optional_columns(&config.optional_columns)
+        .with_dir_grouping(config.dir_grouping)
+        .with_locale(config.locale)
+        .with_column_limits(config.column_limits)
+        .with_sort(config.sort.unwrap_or(SortKey::Name))
+        .with_icons(config.icons);
+    let command = match path_style {
+        Some(style) => command.with_path_style(style, target),
+        None => command,
+    };
+    let command = if config.reverse {
+        command.with_reverse_sort()
+    } else {
+        command
+    };
+    let command = if config.dereference {
+        command.with_dereference()
+    } else {
+        command
+    };
+    let command = if config.strict_utf8 {
+        command.with_strict_utf8()
+    } else {
+        command
+    };
+    let command = match &config.time_format {
+        Some(format) => command.with_time_format(format.clone()),
+        None => command,
+    };
+    let command = if config.relative_time {
+        command.with_relative_time()
+    } else {
+        command
+    };
+    let command = if config.single_column {
+        command.with_bare_names()
+    } else {
+        command
+    };
+    let command = if config.color.enabled(writes_to_terminal(config)) {
+        command.with_color()
+    } else {
+        command
+    };
+    let command = if config.two_line {
+        command.with_two_line_layout()
+    } else {
+        command
+    };
+    // as with `ls`, a terminal gets columns and anything else one name per line
+    let grid = if config.single_column {
+        None
+    } else {
+        config
+            .grid
+            .or(writes_to_terminal(config).then_some(GridOrder::Down))
+    };
+    let command = match grid {
+        Some(order) => command.with_grid(order),
+        None => command,
+    };
+    let command = match config.repeat_header {
+        Some(every) => command.with_repeated_header(every),
+        None => command,
+    };
+    let command = match &config.column_separator {
+        Some(separator) => command.with_column_separator(separator),
+        None => command,
+    };
+    let command = command.with_highlights(&config.highlights);
+    let command = match virtual_fs {
+        Some(virtual_fs) => command.with_virtual_fs(virtual_fs),
+        None => command,
+    };
+    output_formatting::generate_textual_display(command)
+}
+
+/// Exports the entries or renders them for `--json` and the like, one of which has been asked for.
+fn render_entries(
+    config: &Config,
+    entries: Vec<FileEntry>,
+) -> Result<String, FileEntryParsingError> {
+    if let Some((export_format, export_target)) = &config.export {
+        export::export(*export_format, &entries, export_target)?;
+        return Ok(format!(
+            "Exported {} entries to {}",
+            entries.len(),
+            export_target.display()
+        ));
+    }
+    let format = config.machine_format.unwrap_or(MachineFormat::Json);
+    Ok(match &config.pagination {
+        Some(pagination) => {
+            let (entries, page) = machine_output::paginate(entries, pagination);
+            machine_output::render(format, &config.target, &entries, Some(&page))
+        }
+        None => machine_output::render(format, &config.target, &entries, None),
+    })
 }
 
-fn split_into_files_and_dirs(file_collection: ReadDir) -> (Vec<DirEntry>, Vec<DirEntry>) {
-    file_collection
+/// The entries of the listing `config` describes, in the order the text listing shows them, for
+/// use as a library. `find`, `-R` and `--tree` give every entry below the target directory by
+/// directory; hidden entries, the glob, sorting and directory grouping apply as they do on screen.
+pub fn list_entries(config: &Config) -> Result<Vec<FileEntry>, FileEntryParsingError> {
+    let target = Path::new(&config.target);
+    if let Some(pattern) = &config.find_pattern {
+        let (directories, files) = found_entries(config, target, pattern)?;
+        return Ok(in_display_order(config, files, directories));
+    }
+    let mut entries = Vec::new();
+    for listed in walk_listing(config, config.recursive || config.tree)? {
+        let Ok((directories, files)) = listed.contents else {
+            continue;
+        };
+        let (directories, files) = (
+            matching_glob(config, directories),
+            matching_glob(config, files),
+        );
+        entries.extend(in_display_order(config, files, directories));
+    }
+    Ok(entries)
+}
+
+/// Sorted and grouped as `FormattingCommand` lays them out, directory order unless sorting.
+fn in_display_order(
+    config: &Config,
+    mut files: Vec<FileEntry>,
+    mut directories: Vec<FileEntry>,
+) -> Vec<FileEntry> {
+    let key = config.sort.unwrap_or(SortKey::Name);
+    if config.sort.is_some() || config.reverse {
+        sorting::sort_entries(&mut files, key, config.reverse);
+        sorting::sort_entries(&mut directories, key, config.reverse);
+    }
+    match config.dir_grouping {
+        DirGrouping::First => directories.into_iter().chain(files).collect(),
+        DirGrouping::Last => files.into_iter().chain(directories).collect(),
+        DirGrouping::Mixed => {
+            let mut entries: Vec<FileEntry> = files.into_iter().chain(directories).collect();
+            sorting::sort_entries(&mut entries, key, config.reverse);
+            entries
+        }
+    }
+}
+
+/// `-0` output is for `xargs -0`, which needs every name whole and nothing else: the names as the
+/// filesystem has them, bytes that are not UTF-8 included, each ended by a NUL. `find`, `-R` and
+/// `--tree` give paths relative to the target instead of sections.
+fn nul_terminated_names(config: &Config) -> Result<Vec<u8>, FileEntryParsingError> {
+    let target = Path::new(&config.target);
+    let nested = config.find_pattern.is_some() || config.recursive || config.tree;
+    let path_style = config
+        .path_style
+        .or(nested.then_some(PathStyle::Relative))
+        .unwrap_or(PathStyle::Name);
+    let mut names = Vec::new();
+    for entry in list_entries(config)? {
+        names.extend_from_slice(
+            path_style
+                .label(&entry.path, target)
+                .as_os_str()
+                .as_encoded_bytes(),
+        );
+        names.push(0);
+    }
+    Ok(names)
+}
+
+/// Pseudo files report sizes like 0 or 4096 regardless of their contents, so none is given.
+fn without_pseudo_sizes(mut entries: Vec<FileEntry>, pseudo: bool) -> Vec<FileEntry> {
+    if pseudo {
+        for entry in entries
+            .iter_mut()
+            .filter(|entry| entry.kind == EntryKind::File)
+        {
+            entry.size = None;
+        }
+    }
+    entries
+}
+
+/// Candidates are shown relative to the target but picked paths are printed as they would be
+/// reached from the working directory, ready for `cd "$(mini-ls --pick)"` and the like. Several
+/// picks are separated by newlines, or terminated by NUL bytes with `--print0` for `xargs -0`.
+fn pick_entry(config: &Config) -> io::Result<String> {
+    let target = Path::new(&config.target);
+    let (mut directories, mut files) = match &config.find_pattern {
+        Some(pattern) => found_entries(config, target, pattern)?,
+        None => {
+            let (directories, files) = walk_listing(config, false)?
+                .pop()
+                .and_then(|listed| listed.contents.ok())
+                .unwrap_or_default();
+            (
+                matching_glob(config, directories),
+                matching_glob(config, files),
+            )
+        }
+    };
+    let key = config.sort.unwrap_or(SortKey::Name);
+    sorting::sort_entries(&mut files, key, config.reverse);
+    sorting::sort_entries(&mut directories, key, config.reverse);
+    let entries: Vec<FileEntry> = directories.into_iter().chain(files).collect();
+    let labels: Vec<String> = entries
+        .iter()
+        .map(|entry| {
+            PathStyle::Relative
+                .label(&entry.path, target)
+                .to_string_lossy()
+                .to_string()
+        })
+        .collect();
+    let picked: Vec<String> = picker::pick(&labels)?
+        .into_iter()
+        .map(|index| entries[index].path.to_string_lossy().to_string())
+        .collect();
+    Ok(if config.print0 {
+        picked.iter().map(|path| path.to_string() + "\0").collect()
+    } else {
+        picked.join("\n")
+    })
+}
+
+/// The entries each with the index it was read at, by inode number. Statting them in this order
+/// touches the inode table front to back, which saves a lot of seeking for big directories on
+/// spinning disks.
+#[cfg(unix)]
+fn inode_order(entries: &[DirEntry]) -> Vec<(usize, &DirEntry)> {
+    use std::os::unix::fs::DirEntryExt;
+    let mut entries: Vec<(usize, &DirEntry)> = entries.iter().enumerate().collect();
+    entries.sort_by_key(|(_, entry)| entry.ino());
+    entries
+}
+
+#[cfg(not(unix))]
+fn inode_order(entries: &[DirEntry]) -> Vec<(usize, &DirEntry)> {
+    entries.iter().enumerate().collect()
+}
+
+/// Each entry statted once, in inode order with `--inode-order`, and left in directory order.
+fn to_file_entries(entries: &[DirEntry], by_inode: bool) -> Vec<FileEntry> {
+    if !by_inode {
+        return entries.iter().map(FileEntry::from_dir_entry).collect();
+    }
+    let mut statted: Vec<(usize, FileEntry)> = inode_order(entries)
         .into_iter()
+        .map(|(index, entry)| (index, FileEntry::from_dir_entry(entry)))
+        .collect();
+    statted.sort_by_key(|(index, _)| *index);
+    statted.into_iter().map(|(_, entry)| entry).collect()
+}
+
+/// The directories and files of `contents`, read from `directory`, that are shown and, for
+/// directories, descended into: hidden entries only with `-a`, nothing matching an `--exclude`
+/// pattern and with `--git-ignore` nothing git ignores. Files on a pseudo filesystem get no size
+/// and with `-L` symlinks are described by their targets, so a link to a directory is one.
+fn listed_entries(
+    config: &Config,
+    directory: &Path,
+    contents: ReadDir,
+) -> (Vec<FileEntry>, Vec<FileEntry>) {
+    let all = shows_hidden(config);
+    let read: Vec<DirEntry> = contents
         .filter_map(|dir_entry| dir_entry.ok())
-        .partition(|entry| entry.file_type().is_ok_and(|file_type| file_type.is_dir()))
+        .filter(|entry| all || !is_hidden(entry))
+        .filter(|entry| {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            !config.exclude.iter().any(|pattern| pattern.matches(&name))
+        })
+        .collect();
+    let git_ignore = config
+        .git_ignore
+        .then(|| GitIgnore::for_directory(directory))
+        .flatten();
+    let pseudo = virtual_fs::detect(directory).is_some();
+    without_pseudo_sizes(to_file_entries(&read, config.inode_order), pseudo)
+        .into_iter()
+        .map(|entry| dereferenced(config, entry))
+        .filter(|entry| {
+            let is_dir = entry.kind == EntryKind::Directory;
+            !git_ignore.as_ref().is_some_and(|rules| {
+                entry
+                    .path
+                    .file_name()
+                    .is_some_and(|name| rules.is_ignored(Path::new(name), is_dir))
+            })
+        })
+        .partition(|entry| entry.kind == EntryKind::Directory)
+}
+
+/// What `find` turns up below `target`, thinned out by `--match` and `--files`/`--dirs` and
+/// regrouped after `-L` as a directory listing would be.
+fn found_entries(
+    config: &Config,
+    target: &Path,
+    pattern: &NamePattern,
+) -> Result<(Vec<FileEntry>, Vec<FileEntry>), FileEntryParsingError> {
+    let (directories, files) = search::find_matching_entries(target, pattern, &config.exclude)?;
+    let found = directories
+        .into_iter()
+        .chain(files)
+        .map(|entry| dereferenced(config, entry))
+        .collect();
+    Ok(matching_glob(config, found)
+        .into_iter()
+        .partition(|entry| entry.kind == EntryKind::Directory))
+}
+
+fn dereferenced(config: &Config, entry: FileEntry) -> FileEntry {
+    if config.dereference {
+        entry.dereferenced()
+    } else {
+        entry
+    }
+}
+
+/// A glob starting with `.` asks for hidden entries just as it would when expanded by the shell.
+fn shows_hidden(config: &Config) -> bool {
+    config.all
+        || matches!(&config.glob, Some(NamePattern::Glob(glob)) if glob.as_str().starts_with('.'))
+}
+
+/// Recursive listings still descend into directories the glob, `--match` or `--files` leaves out.
+fn matching_glob(config: &Config, entries: Vec<FileEntry>) -> Vec<FileEntry> {
+    let patterns: Vec<&NamePattern> = config.glob.iter().chain(&config.name_match).collect();
+    if patterns.is_empty() && config.only.is_none() {
+        return entries;
+    }
+    entries
+        .into_iter()
+        .filter(|entry| {
+            let is_dir = entry.kind == EntryKind::Directory;
+            match config.only {
+                Some(KindFilter::Files) if is_dir => return false,
+                Some(KindFilter::Directories) if !is_dir => return false,
+                _ => {}
+            }
+            patterns.iter().all(|pattern| pattern.matches(&entry.name))
+        })
+        .collect()
+}
+
+pub(crate) fn is_hidden_name(name: &OsStr) -> bool {
+    name.as_encoded_bytes().starts_with(b".")
+}
+
+#[cfg(windows)]
+fn is_hidden(entry: &DirEntry) -> bool {
+    use std::os::windows::fs::MetadataExt;
+    const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+    is_hidden_name(&entry.file_name())
+        || entry
+            .metadata()
+            .is_ok_and(|meta_data| meta_data.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0)
+}
+
+#[cfg(not(windows))]
+fn is_hidden(entry: &DirEntry) -> bool {
+    is_hidden_name(&entry.file_name())
+}
+
+#[cfg(target_os = "linux")]
+fn run_index_mode(index_mode: &IndexMode, root: &Path, out: &mut impl Write) -> io::Result<()> {
+    match index_mode {
+        IndexMode::Daemon { socket } => daemon::run(root, socket),
+        IndexMode::Query { socket, request } => {
+            writeln!(out, "{}", daemon::query(socket, request)?)
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn run_index_mode(_index_mode: &IndexMode, _root: &Path, _out: &mut impl Write) -> io::Result<()> {
+    Err(io::Error::new(
+        ErrorKind::Unsupported,
+        "the index daemon relies on inotify and is only available on Linux",
+    ))
+}
+
+fn run_config_action(action: ConfigAction, force: bool, out: &mut impl Write) -> io::Result<()> {
+    let path = ConfigFile::default_path().ok_or_else(|| {
+        io::Error::new(
+            ErrorKind::NotFound,
+            "no configuration directory is known for this platform",
+        )
+    })?;
+    match action {
+        ConfigAction::Init => {
+            ConfigFile::init(&path, force)?;
+            writeln!(out, "Wrote the default configuration to {}", path.display())?;
+        }
+        ConfigAction::Validate => {
+            ConfigFile::validate(&path).map_err(|error| {
+                io::Error::new(error.kind(), format!("{}: {}", path.display(), error))
+            })?;
+            writeln!(out, "{} is valid", path.display())?;
+        }
+    }
+    Ok(())
 }
 
 pub fn manage_output(config: Config) -> std::io::Result<()> {
-    let width = if !config.to_file {
-        term_size::dimensions()
-            .expect("unable to obtain console width")
-            .0
+    if !is_paged(&config) {
+        return manage_output_to(config, &mut io::stdout());
+    }
+    let mut listing = Vec::new();
+    manage_output_to(config, &mut listing)?;
+    let height = term_size::dimensions().map(|(_, height)| height);
+    let pager = pager::configured();
+    pager::page(&listing, height, pager.as_deref(), &mut io::stdout())
+}
+
+/// Only a one off listing printed to a terminal goes through the pager, never anything that
+/// redraws or reads the keyboard itself.
+fn is_paged(config: &Config) -> bool {
+    !config.no_pager
+        && (!config.to_file || config.target_file == STDOUT_TARGET)
+        && config.watch.is_none()
+        && !config.follow
+        && !config.pick
+        && config.serve_port.is_none()
+        && config.index_mode.is_none()
+        && io::stdout().is_terminal()
+}
+
+/// Everything that would go to standard output is written to `out` instead, so library users can
+/// capture or redirect it. `-F` files are still written to disk and `serve` and the index daemon
+/// still run in the foreground.
+pub fn manage_output_to(config: Config, out: &mut impl Write) -> std::io::Result<()> {
+    if let Some(action) = config.config_action {
+        return run_config_action(action, config.force, out);
+    }
+    if config.show_help {
+        return writeln!(out, "{}", arg_processing::help_text());
+    }
+    if let Some(shell) = config.completions {
+        return writeln!(out, "{}", arg_processing::completion_script(shell));
+    }
+    if config.show_version {
+        return match config.machine_format {
+            Some(MachineFormat::Json) => writeln!(out, "{}", machine_output::version_info()),
+            _ => writeln!(out, "mini-ls {}", machine_output::TOOL_VERSION),
+        };
+    }
+    if config.doctor {
+        return writeln!(out, "{}", doctor::report(Path::new(&config.target)));
+    }
+    if config.dump_config {
+        return writeln!(out, "{}", config.dump().map_err(io::Error::other)?);
+    }
+    if let Some(port) = config.serve_port {
+        let address = config.serve_address.unwrap_or(server::DEFAULT_ADDRESS);
+        return server::serve(Path::new(&config.target), address, port);
+    }
+    if let Some(index_mode) = &config.index_mode {
+        return run_index_mode(index_mode, Path::new(&config.target), out);
+    }
+    let to_stdout = !config.to_file || config.target_file == STDOUT_TARGET;
+    let output = if to_stdout {
+        STDOUT_TARGET
     } else {
-        120
+        config.target_file.as_str()
     };
-    let contents = list_contents(&config, width)?;
-    if config.to_file {
-        return fs::write(Path::new(config.target_file.as_str()), contents);
+    if config.follow {
+        return follow(&config, to_stdout, output, out);
     }
-    println!("{}", contents);
-    Ok(())
+    let Some(interval) = config.watch else {
+        return list_once(&config, to_stdout, output, out);
+    };
+    let mut first = true;
+    loop {
+        if to_stdout {
+            write!(out, "{}", CLEAR_SCREEN)?;
+        }
+        draw_frame(&config, to_stdout, output, out, first)?;
+        first = false;
+        out.flush()?;
+        thread::sleep(Duration::from_secs(interval as u64));
+    }
+}
+
+/// Redraws the whole listing whenever inotify reports a change in the target, or with `-R` and
+/// `--tree` anywhere below it. Changes arriving together are drawn once and writing the `-F`
+/// file itself does not count as a change.
+#[cfg(target_os = "linux")]
+fn follow(config: &Config, to_stdout: bool, output: &str, out: &mut impl Write) -> io::Result<()> {
+    let target = Path::new(&config.target);
+    let nested = config.recursive || config.tree;
+    let output_file = if to_stdout {
+        None
+    } else {
+        absolute_in_directory(Path::new(output))
+    };
+    let mut watcher = inotify::Watcher::new()?;
+    watcher.watch(target);
+    if nested {
+        walker::walk(target, &config.exclude, &mut |entry| {
+            if entry.metadata.is_dir() {
+                watcher.watch(&entry.path);
+            }
+        })?;
+    }
+    let mut first = true;
+    loop {
+        if to_stdout {
+            write!(out, "{}", CLEAR_SCREEN)?;
+        }
+        draw_frame(config, to_stdout, output, out, first)?;
+        first = false;
+        out.flush()?;
+        wait_for_change(&mut watcher, nested, output_file.as_deref())?;
+        thread::sleep(FOLLOW_DEBOUNCE);
+    }
+}
+
+/// Blocks until something other than `output_file` changes, watching new directories as they
+/// appear when `nested`.
+#[cfg(target_os = "linux")]
+fn wait_for_change(
+    watcher: &mut inotify::Watcher,
+    nested: bool,
+    output_file: Option<&Path>,
+) -> io::Result<()> {
+    loop {
+        let mut changed = false;
+        for change in watcher.read_changes()? {
+            match change {
+                inotify::Change::Path(path)
+                    if output_file.is_some()
+                        && absolute_in_directory(&path).as_deref() == output_file => {}
+                inotify::Change::Path(path) => {
+                    if nested && path.is_dir() {
+                        watcher.watch(&path);
+                    }
+                    changed = true;
+                }
+                inotify::Change::Overflow => changed = true,
+            }
+        }
+        if changed {
+            return Ok(());
+        }
+    }
+}
+
+/// `path` in its canonical directory, so the same file named two ways compares equal.
+#[cfg(target_os = "linux")]
+fn absolute_in_directory(path: &Path) -> Option<PathBuf> {
+    let directory = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    Some(fs::canonicalize(directory).ok()?.join(path.file_name()?))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn follow(
+    _config: &Config,
+    _to_stdout: bool,
+    _output: &str,
+    _out: &mut impl Write,
+) -> io::Result<()> {
+    Err(io::Error::new(
+        ErrorKind::Unsupported,
+        "--follow relies on inotify and is only available on Linux, --watch works everywhere",
+    ))
+}
+
+/// One listing with its hooks.
+fn list_once(
+    config: &Config,
+    to_stdout: bool,
+    output: &str,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    let hooks = &config.hooks;
+    hooks::run("before", hooks.before.as_deref(), &config.target, output)?;
+    write_listing(config, to_stdout, out)?;
+    hooks::run("after", hooks.after.as_deref(), &config.target, output)
+}
+
+/// A frame of `--watch` or `--follow`. The hooks run around the first only, redrawing on every
+/// change would otherwise mount the share or upload the report again each time.
+fn draw_frame(
+    config: &Config,
+    to_stdout: bool,
+    output: &str,
+    out: &mut impl Write,
+    first: bool,
+) -> io::Result<()> {
+    if first {
+        list_once(config, to_stdout, output, out)
+    } else {
+        write_listing(config, to_stdout, out)
+    }
+}
+
+fn write_listing(config: &Config, to_stdout: bool, out: &mut impl Write) -> io::Result<()> {
+    if !to_stdout && config.create_output_dirs {
+        if let Some(parent) = Path::new(&config.target_file).parent() {
+            fs::create_dir_all(parent)?;
+        }
+    }
+    if config.print0 && !config.pick && config.report.is_none() {
+        let names = nul_terminated_names(config)?;
+        return if to_stdout {
+            out.write_all(&names)
+        } else {
+            fs::write(Path::new(config.target_file.as_str()), names)
+        };
+    }
+    let contents = match &config.report {
+        Some(report) => {
+            let target = Path::new(&config.target);
+            reports::generate_report(report, target, &config.exclude, &config.locale)?
+        }
+        None if config.pick => pick_entry(config)?,
+        None => {
+            let width = if let Some(width) = config.width {
+                width
+            } else if !config.to_file
+                && !config.dired
+                && config.compat.is_none()
+                && config.machine_format.is_none()
+                && config.export.is_none()
+            {
+                // output piped on from a terminal has no width of its own
+                term_size::dimensions().map_or(DEFAULT_WIDTH, |(width, _)| width)
+            } else {
+                120
+            };
+            let listing = list_contents(config, width)?;
+            if config.type_summary && config.machine_format.is_none() && config.export.is_none() {
+                let depth = if config.find_pattern.is_some() {
+                    None
+                } else {
+                    Some(1)
+                };
+                let target = Path::new(&config.target);
+                let summary = reports::traversal_summary(target, &config.exclude, depth)?;
+                listing + "\n\n" + summary.as_str()
+            } else {
+                listing
+            }
+        }
+    };
+    // Files written on Windows default to CRLF so they open cleanly in Notepad
+    let crlf = config.crlf || (cfg!(windows) && config.to_file);
+    let contents = if crlf { to_crlf(&contents) } else { contents };
+    if !to_stdout {
+        return fs::write(
+            Path::new(config.target_file.as_str()),
+            config.encoding.encode(&contents),
+        );
+    }
+    if config.print0 {
+        write!(out, "{}", contents)
+    } else if crlf {
+        write!(out, "{}\r\n", contents)
+    } else {
+        writeln!(out, "{}", contents)
+    }
+}
+
+fn to_crlf(contents: &str) -> String {
+    contents.replace("\r\n", "\n").replace('\n', "\r\n")
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::color::ColorMode;
+    use crate::encoding::OutputEncoding;
     use crate::output_formatting::DATE_FORMAT;
     use chrono::{DateTime, Utc};
     use std::fs::File;
     use std::io::Write;
+    use std::sync::mpsc;
     use std::time::SystemTime;
     use std::{fs, thread, time};
     use tempfile::*;
 
+    /// As the command line would build it, with no config file and an empty environment.
+    fn build(args: Vec<String>) -> Result<Config, arg_processing::ArgParsingError> {
+        Config::build_with(args, None, |_| None)
+    }
+
     const FILE_1_NAME: &str = "file_1.txt";
     const FILE_2_NAME: &str = "file_2.txt";
     const FLOPPY_ICON: &str = "\u{1F4BE}";
@@ -163,7 +1108,7 @@ mod tests {
             String::from("./mini-ls"),
             String::from(temp_dir.path().to_str().unwrap()),
         ];
-        (Config::build(args).unwrap(), temp_dir)
+        (build(args).unwrap(), temp_dir)
     }
 
     #[test]
@@ -198,6 +1143,7 @@ mod tests {
             to_file: true,
             target_file: file_1.to_str().unwrap().to_string(),
             extended_attributes: false,
+            ..Default::default()
         };
         manage_output(config).unwrap();
         assert!(file_1.exists());
@@ -206,6 +1152,268 @@ mod tests {
         assert!(file_content.contains(FILE_2_NAME));
     }
 
+    #[test]
+    fn zero_flag_prints_whole_names_ended_by_nul_bytes() {
+        let (temp_dir, ..) = setup_basic_test();
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("-0"),
+            String::from(temp_dir.path().to_str().unwrap()),
+        ];
+        let mut captured = Vec::new();
+        manage_output_to(build(args).unwrap(), &mut captured).unwrap();
+        let mut names: Vec<&[u8]> = captured.split(|byte| *byte == 0).collect();
+        assert_eq!(names.pop(), Some(&b""[..]));
+        names.sort();
+        assert_eq!(names, [FILE_1_NAME.as_bytes(), FILE_2_NAME.as_bytes()]);
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn zero_flag_keeps_raw_bytes_and_gives_recursive_listings_relative_paths() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+        let temp_dir = tempdir().unwrap();
+        fs::create_dir(temp_dir.path().join("nested")).unwrap();
+        File::create(temp_dir.path().join(OsStr::from_bytes(b"bad\xffname"))).unwrap();
+        File::create(temp_dir.path().join("nested/inner.txt")).unwrap();
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("-0"),
+            String::from("-R"),
+            String::from("--sort"),
+            String::from("name"),
+            String::from(temp_dir.path().to_str().unwrap()),
+        ];
+        let mut captured = Vec::new();
+        manage_output_to(build(args).unwrap(), &mut captured).unwrap();
+        assert_eq!(captured, b"bad\xffname\0nested\0nested/inner.txt\0");
+    }
+
+    /// Takes `frames` screens of `--watch` output, then fails like a closed pipe to end the loop.
+    /// Each finished screen is reported to `drawn` when set.
+    struct Frames {
+        written: Vec<u8>,
+        frames: usize,
+        drawn: Option<mpsc::Sender<()>>,
+    }
+
+    impl Write for Frames {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if buf.starts_with(CLEAR_SCREEN.as_bytes()) {
+                if self.frames == 0 {
+                    return Err(io::Error::from(ErrorKind::BrokenPipe));
+                }
+                self.frames -= 1;
+            }
+            self.written.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            if let Some(drawn) = &self.drawn {
+                let _ = drawn.send(());
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn watch_redraws_the_listing_until_the_output_goes_away() {
+        let (temp_dir, ..) = setup_basic_test();
+        let config = Config {
+            target: temp_dir.path().to_str().unwrap().to_string(),
+            single_column: true,
+            color: ColorMode::Never,
+            watch: Some(1),
+            ..Default::default()
+        };
+        let mut out = Frames {
+            written: Vec::new(),
+            frames: 2,
+            drawn: None,
+        };
+        let error = manage_output_to(config, &mut out).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::BrokenPipe);
+        let written = String::from_utf8(out.written).unwrap();
+        let frames: Vec<&str> = written.split(CLEAR_SCREEN).skip(1).collect();
+        assert_eq!(frames.len(), 2);
+        assert!(frames.iter().all(|frame| frame.contains(FILE_1_NAME)));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn hooks_run_once_and_print_outside_the_listing() {
+        let (temp_dir, ..) = setup_basic_test();
+        let record = temp_dir.path().join("record.log");
+        let config = Config {
+            target: temp_dir.path().to_str().unwrap().to_string(),
+            single_column: true,
+            color: ColorMode::Never,
+            watch: Some(1),
+            hooks: hooks::Hooks {
+                before: Some(format!("echo from-hook; echo ran >> {}", record.display())),
+                after: None,
+            },
+            ..Default::default()
+        };
+        let mut out = Frames {
+            written: Vec::new(),
+            frames: 2,
+            drawn: None,
+        };
+        manage_output_to(config, &mut out).unwrap_err();
+        let written = String::from_utf8(out.written).unwrap();
+        assert_eq!(written.split(CLEAR_SCREEN).skip(1).count(), 2);
+        assert!(!written.contains("from-hook"));
+        assert_eq!(fs::read_to_string(&record).unwrap(), "ran\n");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn follow_redraws_when_an_entry_appears() {
+        let (temp_dir, ..) = setup_basic_test();
+        let config = Config {
+            target: temp_dir.path().to_str().unwrap().to_string(),
+            single_column: true,
+            color: ColorMode::Never,
+            follow: true,
+            ..Default::default()
+        };
+        let directory = temp_dir.path().to_path_buf();
+        let (drawn, frame_drawn) = mpsc::channel();
+        let writer = thread::spawn(move || {
+            frame_drawn.recv().unwrap();
+            File::create(directory.join("arrived.txt")).unwrap();
+            // any later change draws a third frame, which ends the listing
+            frame_drawn.recv().unwrap();
+            File::create(directory.join("later.txt")).unwrap();
+        });
+        let mut out = Frames {
+            written: Vec::new(),
+            frames: 2,
+            drawn: Some(drawn),
+        };
+        let error = manage_output_to(config, &mut out).unwrap_err();
+        writer.join().unwrap();
+        assert_eq!(error.kind(), ErrorKind::BrokenPipe);
+        let written = String::from_utf8(out.written).unwrap();
+        let frames: Vec<&str> = written.split(CLEAR_SCREEN).skip(1).collect();
+        assert!(!frames[0].contains("arrived.txt"));
+        assert!(frames[1].contains("arrived.txt"));
+    }
+
+    #[test]
+    fn output_can_be_captured_in_any_writer() {
+        let (temp_dir, ..) = setup_basic_test();
+        let config = Config {
+            target: temp_dir.path().to_str().unwrap().to_string(),
+            width: Some(80),
+            single_column: true,
+            color: ColorMode::Never,
+            ..Default::default()
+        };
+        let mut captured = Vec::new();
+        manage_output_to(config, &mut captured).unwrap();
+        let captured = String::from_utf8(captured).unwrap();
+        assert!(captured.contains(FILE_1_NAME));
+        assert!(captured.ends_with('\n'));
+        let config = Config {
+            show_help: true,
+            ..Default::default()
+        };
+        let mut captured = Vec::new();
+        manage_output_to(config, &mut captured).unwrap();
+        assert!(String::from_utf8(captured)
+            .unwrap()
+            .starts_with("Usage: mini-ls"));
+    }
+
+    #[test]
+    fn crlf_line_endings_are_written_to_file() {
+        let (temp_dir, ..) = setup_basic_test();
+        let log = temp_dir.path().join("log.txt");
+        let config = Config {
+            target: temp_dir.path().to_str().unwrap().to_string(),
+            to_file: true,
+            target_file: log.to_str().unwrap().to_string(),
+            crlf: true,
+            ..Default::default()
+        };
+        manage_output(config).unwrap();
+        let file_content = fs::read_to_string(log.as_path()).unwrap();
+        assert!(file_content.contains("Name:\r\n"));
+        assert_eq!(
+            file_content.matches('\n').count(),
+            file_content.matches("\r\n").count()
+        );
+    }
+
+    #[test]
+    fn files_can_be_written_as_utf16() {
+        let (temp_dir, ..) = setup_basic_test();
+        let log = temp_dir.path().join("log.txt");
+        let config = Config {
+            target: temp_dir.path().to_str().unwrap().to_string(),
+            to_file: true,
+            target_file: log.to_str().unwrap().to_string(),
+            encoding: OutputEncoding::Utf16Le,
+            ..Default::default()
+        };
+        manage_output(config).unwrap();
+        let bytes = fs::read(log.as_path()).unwrap();
+        assert_eq!(&bytes[..2], &[0xFF, 0xFE]);
+        let units: Vec<u16> = bytes[2..]
+            .chunks(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+        assert!(String::from_utf16(&units).unwrap().contains(FILE_1_NAME));
+    }
+
+    #[test]
+    fn creates_missing_output_directories_when_asked() {
+        let (temp_dir, ..) = setup_basic_test();
+        let log = temp_dir.path().join("logs").join("nested").join("log.txt");
+        let config = Config {
+            target: temp_dir.path().to_str().unwrap().to_string(),
+            to_file: true,
+            target_file: log.to_str().unwrap().to_string(),
+            create_output_dirs: true,
+            ..Default::default()
+        };
+        manage_output(config).unwrap();
+        assert!(log.exists());
+    }
+
+    #[test]
+    fn recursive_listings_show_paths_relative_to_the_target() {
+        let (temp_dir, ..) = setup_basic_test();
+        fs::create_dir_all(temp_dir.path().join("sub").join("dir")).unwrap();
+        fs::write(temp_dir.path().join("sub").join("dir").join("file.txt"), "").unwrap();
+        let target = temp_dir.path().to_str().unwrap().to_string();
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("find"),
+            String::from("file.txt"),
+            target.clone(),
+        ];
+        let config = build(args).unwrap();
+        let contents = list_contents(&config, 100).unwrap();
+        assert!(contents.contains(" sub/dir/file.txt"));
+        assert!(!contents.contains(&target));
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("find"),
+            String::from("file.txt"),
+            String::from("--path-style"),
+            String::from("name"),
+            target,
+        ];
+        let config = build(args).unwrap();
+        let contents = list_contents(&config, 100).unwrap();
+        assert!(contents.contains(" file.txt"));
+        assert!(!contents.contains("sub/"));
+    }
+
     #[test]
     fn returns_an_error_on_non_existent_directories() {
         let (temp_dir, ..) = setup_basic_test();
@@ -215,6 +1423,7 @@ mod tests {
             to_file: false,
             target_file: "".to_string(),
             extended_attributes: false,
+            ..Default::default()
         };
         let contents = list_contents(&config, 100);
         assert!(contents.is_err());
@@ -235,6 +1444,7 @@ mod tests {
             to_file: false,
             target_file: "".to_string(),
             extended_attributes: true,
+            ..Default::default()
         };
         let contents = list_contents(&config, 400).unwrap();
         assert!(contents.contains(expected_date.as_str()));
@@ -253,7 +1463,7 @@ mod tests {
     }
 
     #[test]
-    #[cfg(not(target_os = "linux"))]
+    #[cfg(not(unix))]
     fn contains_permissions_when_extended_attr() {
         let (temp_dir, file_1, _file_2) = setup_basic_test();
         let mut permissions = file_1.metadata().unwrap().permissions();
@@ -265,6 +1475,7 @@ mod tests {
             to_file: false,
             target_file: "".to_string(),
             extended_attributes: true,
+            ..Default::default()
         };
         let contents = list_contents(&config, 400).unwrap();
         let lines: Vec<&str> = contents.split('\n').collect();
@@ -275,6 +1486,28 @@ mod tests {
         assert!(!lines[3].contains("read only"));
     }
 
+    #[test]
+    #[cfg(unix)]
+    fn shows_mode_bits_when_extended_attr() {
+        use std::os::unix::fs::PermissionsExt;
+        let (temp_dir, file_1, _file_2) = setup_basic_test();
+        file_1
+            .set_permissions(fs::Permissions::from_mode(0o640))
+            .unwrap();
+        let config = Config {
+            target: temp_dir.path().to_str().unwrap().to_string(),
+            extended_attributes: true,
+            ..Default::default()
+        };
+        let contents = list_contents(&config, 400).unwrap();
+        let line = contents
+            .lines()
+            .find(|line| line.contains(FILE_1_NAME))
+            .unwrap();
+        assert!(line.contains("rw-r-----"));
+        assert!(!line.contains("writable"));
+    }
+
     #[test]
     fn contains_date_modified() {
         let (temp_dir, mut file_1, _file_2) = setup_basic_test();
@@ -287,8 +1520,7 @@ mod tests {
     }
 
     #[test]
-    #[should_panic(expected = "requires minimum console width of 80")]
-    fn returns_err_on_too_narrow_terminals() {
+    fn narrow_terminals_drop_created_then_permissions() {
         let long_file_name =
             "very_long_filename_to_check_for_shortening_of_filename_on_small_consoles.txt";
         let temp_dir = tempdir().unwrap();
@@ -303,8 +1535,360 @@ mod tests {
             to_file: false,
             target_file: "".to_string(),
             extended_attributes: true,
+            ..Default::default()
+        };
+        let contents = list_contents(&config, 75).unwrap();
+        let header = contents.lines().next().unwrap();
+        assert!(!header.contains("Date Created"));
+        assert!(header.contains("Permissions"));
+        let contents = list_contents(&config, 60).unwrap();
+        let header = contents.lines().next().unwrap();
+        assert!(!header.contains("Permissions"));
+        assert!(header.contains("Date Modified"));
+        let row = contents
+            .lines()
+            .find(|line| line.contains(FILE_2_NAME))
+            .unwrap();
+        assert_eq!(row.split_whitespace().count(), 4);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn inode_order_sorts_by_inode_and_keeps_the_listing_unchanged() {
+        use std::os::unix::fs::DirEntryExt;
+        let (temp_dir, ..) = setup_basic_test();
+        fs::create_dir(temp_dir.path().join("nested")).unwrap();
+        let entries: Vec<DirEntry> = fs::read_dir(temp_dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap())
+            .collect();
+        let ordered = inode_order(&entries);
+        assert_eq!(ordered.len(), 3);
+        assert!(ordered
+            .windows(2)
+            .all(|pair| pair[0].1.ino() <= pair[1].1.ino()));
+        let config = Config {
+            target: temp_dir.path().to_str().unwrap().to_string(),
+            ..Default::default()
+        };
+        let expected = list_contents(&config, 120).unwrap();
+        let config = Config {
+            inode_order: true,
+            ..config
+        };
+        assert_eq!(list_contents(&config, 120).unwrap(), expected);
+    }
+
+    #[test]
+    fn recursive_listing_gives_each_directory_its_own_section() {
+        let (temp_dir, ..) = setup_basic_test();
+        let nested = temp_dir.path().join("nested");
+        fs::create_dir_all(nested.join("deeper")).unwrap();
+        File::create(nested.join("inner.txt")).unwrap();
+        fs::create_dir(temp_dir.path().join("other")).unwrap();
+        let config = Config {
+            target: temp_dir.path().to_str().unwrap().to_string(),
+            recursive: true,
+            ..Default::default()
+        };
+        let contents = list_contents(&config, 120).unwrap();
+        let headings: Vec<&str> = contents
+            .lines()
+            .filter(|line| line.ends_with(':') && line.contains(std::path::MAIN_SEPARATOR))
+            .collect();
+        let expected: Vec<String> = [
+            temp_dir.path().to_path_buf(),
+            nested.clone(),
+            nested.join("deeper"),
+            temp_dir.path().join("other"),
+        ]
+        .iter()
+        .map(|path| format!("{}:", path.display()))
+        .collect();
+        assert_eq!(headings, expected);
+        assert!(contents.contains("inner.txt"));
+        assert!(contents.contains(FILE_1_NAME));
+        let config = Config {
+            depth: Some(2),
+            ..config
+        };
+        let contents = list_contents(&config, 120).unwrap();
+        assert!(contents.contains(&format!("{}:", nested.display())));
+        assert!(!contents.contains(&format!("{}:", nested.join("deeper").display())));
+        let flat = Config {
+            depth: Some(1),
+            ..config
         };
-        let inadequate_length = 60; // less than reserved for extended attrs
-        let _contents = list_contents(&config, inadequate_length).unwrap();
+        let contents = list_contents(&flat, 120).unwrap();
+        assert!(!contents.contains("inner.txt"));
+        assert!(!contents.contains(&format!("{}:", temp_dir.path().display())));
+    }
+
+    #[test]
+    fn dotfiles_are_hidden_without_all() {
+        let (temp_dir, ..) = setup_basic_test();
+        File::create(temp_dir.path().join(".hidden")).unwrap();
+        fs::create_dir(temp_dir.path().join(".git")).unwrap();
+        let config = Config {
+            target: temp_dir.path().to_str().unwrap().to_string(),
+            ..Default::default()
+        };
+        let contents = list_contents(&config, 120).unwrap();
+        assert!(contents.contains(FILE_1_NAME));
+        assert!(!contents.contains(".hidden"));
+        assert!(!contents.contains(".git"));
+        let config = Config {
+            all: true,
+            ..config
+        };
+        let contents = list_contents(&config, 120).unwrap();
+        assert!(contents.contains(".hidden"));
+        assert!(contents.contains(".git"));
+    }
+
+    #[test]
+    fn list_entries_returns_structured_entries_in_display_order() {
+        let temp_dir = tempdir().unwrap();
+        fs::write(temp_dir.path().join("small.txt"), "1").unwrap();
+        fs::write(temp_dir.path().join("large.txt"), "12345").unwrap();
+        fs::create_dir(temp_dir.path().join("nested")).unwrap();
+        fs::write(temp_dir.path().join("nested/inner.txt"), "12").unwrap();
+        let config = Config {
+            target: temp_dir.path().to_str().unwrap().to_string(),
+            sort: Some(SortKey::Size),
+            ..Default::default()
+        };
+        let entries = list_entries(&config).unwrap();
+        let names: Vec<&str> = entries.iter().map(|entry| entry.name.as_str()).collect();
+        assert_eq!(names, vec!["large.txt", "small.txt", "nested"]);
+        assert_eq!(entries[0].kind, EntryKind::File);
+        assert_eq!(entries[0].size, Some(5));
+        assert_eq!(entries[0].path, temp_dir.path().join("large.txt"));
+        assert!(entries[0].modified.is_some());
+        assert_eq!(entries[2].kind, EntryKind::Directory);
+        let config = Config {
+            recursive: true,
+            ..config
+        };
+        let names: Vec<String> = list_entries(&config)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.name)
+            .collect();
+        assert_eq!(names, vec!["large.txt", "small.txt", "nested", "inner.txt"]);
+    }
+
+    #[test]
+    fn every_listing_reads_the_same_entries() {
+        let temp_dir = tempdir().unwrap();
+        fs::create_dir_all(temp_dir.path().join("kept/skipped")).unwrap();
+        fs::write(temp_dir.path().join("kept/inner.txt"), "").unwrap();
+        fs::write(temp_dir.path().join("kept/skipped/hidden.txt"), "").unwrap();
+        fs::write(temp_dir.path().join("top.txt"), "").unwrap();
+        let config = Config {
+            target: temp_dir.path().to_str().unwrap().to_string(),
+            recursive: true,
+            exclude: vec![NamePattern::parse("skipped").unwrap()],
+            ..Default::default()
+        };
+        let mut names: Vec<String> = list_entries(&config)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.name)
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["inner.txt", "kept", "top.txt"]);
+        let by_inode = Config {
+            target: config.target.clone(),
+            recursive: true,
+            exclude: vec![NamePattern::parse("skipped").unwrap()],
+            inode_order: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            list_contents(&by_inode, 120).unwrap(),
+            list_contents(&config, 120).unwrap()
+        );
+        let tree = Config {
+            recursive: false,
+            tree: true,
+            ..config
+        };
+        let drawn = list_contents(&tree, 120).unwrap();
+        assert!(names.iter().all(|name| drawn.contains(name.as_str())));
+        assert!(!drawn.contains("skipped") && !drawn.contains("hidden.txt"));
+        assert!(drawn.ends_with("1 directory, 2 files"));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn dereferencing_applies_to_every_output() {
+        let temp_dir = tempdir().unwrap();
+        fs::write(temp_dir.path().join("big.txt"), "1234567890").unwrap();
+        fs::write(temp_dir.path().join("small.txt"), "12").unwrap();
+        fs::create_dir(temp_dir.path().join("folder")).unwrap();
+        std::os::unix::fs::symlink("big.txt", temp_dir.path().join("a_link")).unwrap();
+        std::os::unix::fs::symlink("folder", temp_dir.path().join("b_dir_link")).unwrap();
+        std::os::unix::fs::symlink("missing", temp_dir.path().join("c_broken")).unwrap();
+        let config = Config {
+            target: temp_dir.path().to_str().unwrap().to_string(),
+            dereference: true,
+            sort: Some(SortKey::Size),
+            dir_grouping: DirGrouping::First,
+            ..Default::default()
+        };
+        let entries = list_entries(&config).unwrap();
+        let described: Vec<(&str, EntryKind)> = entries
+            .iter()
+            .map(|entry| (entry.name.as_str(), entry.kind))
+            .collect();
+        assert_eq!(
+            &described[..2],
+            &[
+                ("b_dir_link", EntryKind::Directory),
+                ("folder", EntryKind::Directory)
+            ]
+        );
+        assert_eq!(described[2], ("a_link", EntryKind::File));
+        assert_eq!(entries[2].size, Some(10));
+        assert!(described.contains(&("c_broken", EntryKind::Symlink)));
+        let json = list_contents(
+            &Config {
+                machine_format: Some(MachineFormat::Json),
+                ..config
+            },
+            120,
+        )
+        .unwrap();
+        let document: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let link = document["entries"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|entry| entry["name"] == "a_link")
+            .unwrap();
+        assert_eq!(link["kind"], "file");
+        assert_eq!(link["size"], 10);
+    }
+
+    #[test]
+    fn find_applies_match_and_kind_filters() {
+        let temp_dir = tempdir().unwrap();
+        fs::create_dir(temp_dir.path().join("keep_dir.txt")).unwrap();
+        fs::write(temp_dir.path().join("keep_dir.txt/keep_inner.txt"), "").unwrap();
+        fs::write(temp_dir.path().join("keep_top.txt"), "").unwrap();
+        fs::write(temp_dir.path().join("other.txt"), "").unwrap();
+        let config = Config {
+            target: temp_dir.path().to_str().unwrap().to_string(),
+            find_pattern: Some(NamePattern::parse("*.txt").unwrap()),
+            name_match: Some(NamePattern::Regex(regex::Regex::new("^keep").unwrap())),
+            sort: Some(SortKey::Name),
+            ..Default::default()
+        };
+        let names = |config: &Config| -> Vec<String> {
+            list_entries(config)
+                .unwrap()
+                .into_iter()
+                .map(|entry| entry.name)
+                .collect()
+        };
+        assert_eq!(
+            names(&config),
+            vec!["keep_inner.txt", "keep_top.txt", "keep_dir.txt"]
+        );
+        let config = Config {
+            only: Some(KindFilter::Files),
+            ..config
+        };
+        assert_eq!(names(&config), vec!["keep_inner.txt", "keep_top.txt"]);
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn library_entries_on_a_pseudo_filesystem_have_no_size() {
+        let config = Config {
+            target: String::from("/proc/self"),
+            ..Default::default()
+        };
+        let entries = list_entries(&config).unwrap();
+        assert!(entries.iter().any(|entry| entry.kind == EntryKind::File));
+        assert!(entries
+            .iter()
+            .filter(|entry| entry.kind == EntryKind::File)
+            .all(|entry| entry.size.is_none()));
+    }
+
+    #[test]
+    fn files_and_dirs_flags_list_one_kind_of_entry() {
+        let temp_dir = tempdir().unwrap();
+        fs::write(temp_dir.path().join("top.txt"), "").unwrap();
+        fs::create_dir(temp_dir.path().join("nested")).unwrap();
+        fs::write(temp_dir.path().join("nested/inner.txt"), "").unwrap();
+        let names = |flags: &[&str]| -> Vec<String> {
+            let args = ["./mini-ls"]
+                .iter()
+                .chain(flags)
+                .map(|arg| arg.to_string())
+                .chain([temp_dir.path().to_str().unwrap().to_string()])
+                .collect();
+            list_entries(&build(args).unwrap())
+                .unwrap()
+                .into_iter()
+                .map(|entry| entry.name)
+                .collect()
+        };
+        assert_eq!(names(&["--dirs"]), vec!["nested"]);
+        assert_eq!(names(&["--dirs", "--files"]), vec!["top.txt"]);
+        assert_eq!(names(&["-R", "--files"]), vec!["top.txt", "inner.txt"]);
+    }
+
+    #[test]
+    fn excluded_entries_are_neither_listed_nor_descended_into() {
+        let temp_dir = tempdir().unwrap();
+        fs::write(temp_dir.path().join("keep.rs"), "").unwrap();
+        fs::write(temp_dir.path().join("scratch.tmp"), "").unwrap();
+        fs::create_dir(temp_dir.path().join("target")).unwrap();
+        fs::write(temp_dir.path().join("target/build.rs"), "").unwrap();
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("-R"),
+            String::from("--exclude"),
+            String::from("target"),
+            String::from("--exclude=*.tmp"),
+            String::from(temp_dir.path().to_str().unwrap()),
+        ];
+        let config = build(args).unwrap();
+        let names: Vec<String> = list_entries(&config)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.name)
+            .collect();
+        assert_eq!(names, vec!["keep.rs"]);
+    }
+
+    #[test]
+    fn glob_lists_only_matching_entries() {
+        let (temp_dir, ..) = setup_basic_test();
+        File::create(temp_dir.path().join("notes.md")).unwrap();
+        File::create(temp_dir.path().join(".hidden.md")).unwrap();
+        let args = vec![
+            String::from("./mini-ls"),
+            format!("{}/*.md", temp_dir.path().display()),
+        ];
+        let config = build(args).unwrap();
+        let contents = list_contents(&config, 120).unwrap();
+        assert!(contents.contains("notes.md"));
+        assert!(!contents.contains(FILE_1_NAME));
+        assert!(!contents.contains(".hidden.md"));
+        let args = vec![
+            String::from("./mini-ls"),
+            String::from("--glob"),
+            String::from(".*"),
+            temp_dir.path().to_str().unwrap().to_string(),
+        ];
+        let config = build(args).unwrap();
+        let contents = list_contents(&config, 120).unwrap();
+        assert!(contents.contains(".hidden.md"));
+        assert!(!contents.contains("notes.md"));
     }
 }
@@ -0,0 +1,173 @@
+use crate::output_formatting::DATE_FORMAT;
+use serde::Serialize;
+use std::env;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DateOrder {
+    YearMonthDay,
+    DayMonthYear,
+    MonthDayYear,
+}
+
+/// How much of a timestamp is shown, from the full millisecond form down to just the day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DatePrecision {
+    Millis,
+    Seconds,
+    Minutes,
+    Day,
+}
+
+impl DatePrecision {
+    /// The most precise form whose rendering fits in `width` columns.
+    pub(crate) fn fitting(width: usize) -> DatePrecision {
+        match width {
+            23.. => DatePrecision::Millis,
+            19..=22 => DatePrecision::Seconds,
+            16..=18 => DatePrecision::Minutes,
+            _ => DatePrecision::Day,
+        }
+    }
+}
+
+/// Date and number conventions for the textual output. The default is the ISO style the
+/// listing has always used, machine readable formats ignore the locale entirely.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Locale {
+    date_order: DateOrder,
+    date_separator: char,
+    decimal_separator: char,
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Locale {
+            date_order: DateOrder::YearMonthDay,
+            date_separator: '-',
+            decimal_separator: '.',
+        }
+    }
+}
+
+impl Locale {
+    /// Accepts POSIX style names such as `de_DE.UTF-8` or `en_GB@euro`, falling back to the
+    /// language when the territory is not known.
+    pub fn parse(name: &str) -> Option<Locale> {
+        let name = name.split(['.', '@']).next().unwrap_or_default();
+        if name == "C" || name == "POSIX" {
+            return Some(Locale::default());
+        }
+        let (language, territory) = name.split_once(['_', '-']).unwrap_or((name, ""));
+        let (date_order, date_separator) = date_convention(language, territory)?;
+        Some(Locale {
+            date_order,
+            date_separator,
+            decimal_separator: decimal_separator(language)?,
+        })
+    }
+
+    /// Follows the usual precedence of `LC_ALL`, then the category variable, then `LANG`.
+    pub fn from_env() -> Locale {
+        Locale::from_variables(|name| env::var(name).ok())
+    }
+
+    /// As `from_env` with the variables looked up through `variable`.
+    pub(crate) fn from_variables(variable: impl Fn(&str) -> Option<String>) -> Locale {
+        let lookup = |category: &str| {
+            ["LC_ALL", category, "LANG"]
+                .iter()
+                .filter_map(|name| variable(name))
+                .find(|value| !value.is_empty())
+                .and_then(|value| Locale::parse(&value))
+                .unwrap_or_default()
+        };
+        let time = lookup("LC_TIME");
+        let numeric = lookup("LC_NUMERIC");
+        Locale {
+            decimal_separator: numeric.decimal_separator,
+            ..time
+        }
+    }
+
+    /// A chrono format string for a timestamp with millisecond precision.
+    pub(crate) fn date_format(&self) -> String {
+        self.date_format_with(DatePrecision::Millis)
+    }
+
+    pub(crate) fn date_format_with(&self, precision: DatePrecision) -> String {
+        if *self == Locale::default() && precision == DatePrecision::Millis {
+            return String::from(DATE_FORMAT);
+        }
+        let separator = self.date_separator;
+        let date = match self.date_order {
+            DateOrder::YearMonthDay => format!("%Y{0}%m{0}%d", separator),
+            DateOrder::DayMonthYear => format!("%d{0}%m{0}%Y", separator),
+            DateOrder::MonthDayYear => format!("%m{0}%d{0}%Y", separator),
+        };
+        match precision {
+            DatePrecision::Millis => format!("{} %H:%M:%S{}%3f", date, self.decimal_separator),
+            DatePrecision::Seconds => format!("{} %H:%M:%S", date),
+            DatePrecision::Minutes => format!("{} %H:%M", date),
+            DatePrecision::Day => date,
+        }
+    }
+
+    pub(crate) fn localise_decimal(&self, number: &str) -> String {
+        number.replace('.', &self.decimal_separator.to_string())
+    }
+}
+
+fn date_convention(language: &str, territory: &str) -> Option<(DateOrder, char)> {
+    Some(match (language, territory) {
+        ("en", "GB" | "AU" | "NZ" | "IE" | "IN" | "ZA") => (DateOrder::DayMonthYear, '/'),
+        ("en", "CA") | ("fr", "CA") => (DateOrder::YearMonthDay, '-'),
+        ("en", _) => (DateOrder::MonthDayYear, '/'),
+        ("fr" | "es" | "it" | "pt" | "el", _) => (DateOrder::DayMonthYear, '/'),
+        ("nl", _) => (DateOrder::DayMonthYear, '-'),
+        ("de" | "ru" | "pl" | "uk" | "cs" | "tr" | "fi" | "nb" | "da", _) => {
+            (DateOrder::DayMonthYear, '.')
+        }
+        ("sv" | "lt", _) => (DateOrder::YearMonthDay, '-'),
+        ("ja" | "zh" | "ko", _) => (DateOrder::YearMonthDay, '/'),
+        _ => return None,
+    })
+}
+
+fn decimal_separator(language: &str) -> Option<char> {
+    match language {
+        "en" | "ja" | "zh" | "ko" => Some('.'),
+        "fr" | "es" | "it" | "pt" | "el" | "nl" | "de" | "ru" | "pl" | "uk" | "cs" | "tr"
+        | "fi" | "nb" | "da" | "sv" | "lt" => Some(','),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_posix_locale_names() {
+        let german = Locale::parse("de_DE.UTF-8").unwrap();
+        assert_eq!(german.date_format(), "%d.%m.%Y %H:%M:%S,%3f");
+        assert_eq!(german.localise_decimal("1.5 KiB"), "1,5 KiB");
+        let american = Locale::parse("en_US").unwrap();
+        assert_eq!(american.date_format(), "%m/%d/%Y %H:%M:%S.%3f");
+        let british = Locale::parse("en_GB@euro").unwrap();
+        assert_eq!(british.date_format(), "%d/%m/%Y %H:%M:%S.%3f");
+        assert_eq!(Locale::parse("C.UTF-8"), Some(Locale::default()));
+        assert_eq!(Locale::parse("xx_YY"), None);
+    }
+
+    #[test]
+    fn shorter_precisions_fit_narrower_columns() {
+        let iso = Locale::default();
+        assert_eq!(DatePrecision::fitting(16), DatePrecision::Minutes);
+        assert_eq!(
+            iso.date_format_with(DatePrecision::Minutes),
+            "%Y-%m-%d %H:%M"
+        );
+        assert_eq!(iso.date_format_with(DatePrecision::Day), "%Y-%m-%d");
+    }
+}
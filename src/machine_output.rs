@@ -0,0 +1,279 @@
+use crate::columns::format_octal_mode;
+use crate::delimited::{self, CsvDialect};
+use crate::entries::FileEntry;
+use crate::html;
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::time::SystemTime;
+
+/// Bumped whenever a field is renamed, removed or changes meaning so parsers can detect it. CSV
+/// and TSV do not carry it, a comment row would become the column names for sqlite `.import` and
+/// a data row in spreadsheets. Their columns are the JSON entry fields, so `--version --json`
+/// tells a script which schema they follow.
+pub const SCHEMA_VERSION: u32 = 1;
+pub const TOOL_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MachineFormat {
+    Json,
+    Ndjson,
+    Csv(CsvDialect),
+    Tsv,
+    Html,
+}
+
+/// `--page-size` and `--cursor`. Pages are ordered by path so a cursor, which only records the
+/// last path returned, stays valid while entries are added or removed between calls.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Pagination {
+    pub page_size: usize,
+    pub after: Option<String>,
+}
+
+/// The continuation of a paginated listing, `next_cursor` is `None` on the last page.
+pub(crate) struct Page {
+    pub(crate) next_cursor: Option<String>,
+}
+
+pub(crate) fn paginate(
+    mut entries: Vec<FileEntry>,
+    pagination: &Pagination,
+) -> (Vec<FileEntry>, Page) {
+    let key = |entry: &FileEntry| entry.path.to_string_lossy().to_string();
+    entries.sort_by_key(key);
+    let start = match &pagination.after {
+        Some(after) => entries.partition_point(|entry| key(entry) <= *after),
+        None => 0,
+    };
+    let mut page: Vec<FileEntry> = entries.drain(start..).collect();
+    let more = page.len() > pagination.page_size;
+    page.truncate(pagination.page_size);
+    let next_cursor = page
+        .last()
+        .filter(|_| more)
+        .map(|entry| encode_cursor(&key(entry)));
+    (page, Page { next_cursor })
+}
+
+/// Cursors are hex so they survive shells and URLs untouched, callers should treat them as opaque.
+fn encode_cursor(path: &str) -> String {
+    path.bytes().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+pub(crate) fn decode_cursor(cursor: &str) -> Option<String> {
+    if !cursor.len().is_multiple_of(2) {
+        return None;
+    }
+    let bytes = (0..cursor.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(cursor.get(i..i + 2)?, 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+    String::from_utf8(bytes).ok()
+}
+
+pub(crate) fn render(
+    format: MachineFormat,
+    target: &str,
+    entries: &[FileEntry],
+    page: Option<&Page>,
+) -> String {
+    let records = entries.iter().map(entry_to_json);
+    match format {
+        MachineFormat::Json => {
+            let mut document = header(target, page);
+            document["entries"] = Value::Array(records.collect());
+            document.to_string()
+        }
+        MachineFormat::Ndjson => std::iter::once(header(target, page))
+            .chain(records)
+            .map(|record| record.to_string())
+            .collect::<Vec<String>>()
+            .join("\n"),
+        MachineFormat::Csv(dialect) => delimited::render_csv(entries, dialect),
+        MachineFormat::Tsv => delimited::render_tsv(entries),
+        MachineFormat::Html => html::render_html(target, entries),
+    }
+}
+
+/// Everything a wrapper script needs to feature-detect this build, printed by `--version --json`.
+pub(crate) fn version_info() -> Value {
+    let enabled = |pairs: &[(&'static str, bool)]| -> Vec<&'static str> {
+        pairs
+            .iter()
+            .filter(|(_, enabled)| *enabled)
+            .map(|(name, _)| *name)
+            .collect()
+    };
+    let features = enabled(&[
+        ("parquet", cfg!(feature = "parquet")),
+        ("arrow", cfg!(feature = "arrow")),
+    ]);
+    let mut output_formats = vec![
+        "text", "long", "dired", "json", "ndjson", "csv", "tsv", "html",
+    ];
+    output_formats.extend(features.iter());
+    let backends = enabled(&[
+        ("filesystem", true),
+        ("http-server", true),
+        ("inotify-index", cfg!(target_os = "linux")),
+    ]);
+    json!({
+        "tool": "mini-ls",
+        "version": TOOL_VERSION,
+        "schema_version": SCHEMA_VERSION,
+        "features": features,
+        "output_formats": output_formats,
+        "backends": backends,
+    })
+}
+
+fn header(target: &str, page: Option<&Page>) -> Value {
+    let mut header = json!({
+        "schema_version": SCHEMA_VERSION,
+        "tool": "mini-ls",
+        "tool_version": TOOL_VERSION,
+        "target": target,
+    });
+    if let Some(page) = page {
+        header["next_cursor"] = json!(page.next_cursor);
+    }
+    header
+}
+
+fn entry_to_json(entry: &FileEntry) -> Value {
+    json!({
+        "name": entry.name,
+        "path": entry.path.to_string_lossy(),
+        "kind": entry.kind.as_str(),
+        "size": entry.size,
+        "created": entry.created.map(format_timestamp),
+        "modified": entry.modified.map(format_timestamp),
+        "readonly": entry.readonly,
+        "mode": entry.mode.map(format_octal_mode),
+    })
+}
+
+pub(crate) fn format_timestamp(time: SystemTime) -> String {
+    DateTime::<Utc>::from(time).to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn entries_for(dir: &std::path::Path) -> Vec<FileEntry> {
+        fs::read_dir(dir)
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .map(|entry| FileEntry::from_dir_entry(&entry))
+            .collect()
+    }
+
+    #[test]
+    fn version_info_lists_formats_and_backends() {
+        let info = version_info();
+        assert_eq!(info["version"], TOOL_VERSION);
+        let formats = info["output_formats"].as_array().unwrap();
+        assert!(formats.contains(&json!("csv")));
+        assert_eq!(
+            formats.contains(&json!("parquet")),
+            cfg!(feature = "parquet")
+        );
+        assert!(info["backends"]
+            .as_array()
+            .unwrap()
+            .contains(&json!("filesystem")));
+    }
+
+    #[test]
+    fn cursors_walk_through_every_entry_once() {
+        let temp_dir = tempdir().unwrap();
+        for name in ["a", "b", "c", "d", "e"] {
+            fs::write(temp_dir.path().join(name), "").unwrap();
+        }
+        let mut pagination = Pagination {
+            page_size: 2,
+            after: None,
+        };
+        let mut seen = Vec::new();
+        loop {
+            let (page, next) = paginate(entries_for(temp_dir.path()), &pagination);
+            seen.extend(page.iter().map(|entry| entry.name.clone()));
+            let rendered = render(MachineFormat::Json, "dir", &page, Some(&next));
+            let document: Value = serde_json::from_str(&rendered).unwrap();
+            assert_eq!(document["next_cursor"], json!(next.next_cursor));
+            match next.next_cursor {
+                Some(cursor) => pagination.after = Some(decode_cursor(&cursor).unwrap()),
+                None => break,
+            }
+            // an entry created behind the cursor must not shift later pages
+            fs::write(temp_dir.path().join("0"), "").unwrap();
+        }
+        assert_eq!(seen, vec!["a", "b", "c", "d", "e"]);
+        assert_eq!(decode_cursor("zz"), None);
+    }
+
+    #[test]
+    fn json_document_carries_schema_and_tool_version() {
+        let temp_dir = tempdir().unwrap();
+        fs::write(temp_dir.path().join("file_1.txt"), "12345").unwrap();
+        let rendered = render(
+            MachineFormat::Json,
+            "dir",
+            &entries_for(temp_dir.path()),
+            None,
+        );
+        let document: Value = serde_json::from_str(&rendered).unwrap();
+        assert_eq!(document["schema_version"], SCHEMA_VERSION);
+        assert_eq!(document["tool_version"], TOOL_VERSION);
+        assert_eq!(document["entries"][0]["name"], "file_1.txt");
+        assert_eq!(document["entries"][0]["kind"], "file");
+        assert_eq!(document["entries"][0]["size"], 5);
+    }
+
+    #[test]
+    fn delimited_columns_are_the_versioned_json_fields() {
+        let temp_dir = tempdir().unwrap();
+        fs::write(temp_dir.path().join("file_1.txt"), "").unwrap();
+        let entries = entries_for(temp_dir.path());
+        let fields: Vec<String> = entry_to_json(&entries[0])
+            .as_object()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect();
+        let csv = render(
+            MachineFormat::Csv(CsvDialect::default()),
+            "dir",
+            &entries,
+            None,
+        );
+        assert_eq!(csv.lines().next().unwrap(), fields.join(","));
+        let tsv = render(MachineFormat::Tsv, "dir", &entries, None);
+        assert_eq!(tsv.lines().next().unwrap(), fields.join("\t"));
+    }
+
+    #[test]
+    fn ndjson_starts_with_a_header_record() {
+        let temp_dir = tempdir().unwrap();
+        fs::write(temp_dir.path().join("file_1.txt"), "").unwrap();
+        fs::create_dir(temp_dir.path().join("other")).unwrap();
+        let rendered = render(
+            MachineFormat::Ndjson,
+            "dir",
+            &entries_for(temp_dir.path()),
+            None,
+        );
+        let records: Vec<Value> = rendered
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0]["schema_version"], SCHEMA_VERSION);
+        assert!(records[1..].iter().all(|record| record["name"].is_string()));
+    }
+}
@@ -1,11 +1,10 @@
 use std::{env, process};
 use std::error::Error;
-use mini_ls::{manage_output};
+use mini_ls::{manage_output, FileEntryParsingError};
 use mini_ls::arg_processing::Config;
 
 fn main() -> Result<(), Box<dyn Error>> {
   let args: Vec<String> = env::args().collect();
-  println!("{}", args.get(0).expect("can't be missing"));
   let config = match Config::build(args) {
     Ok(config) => config,
     Err(error) => {
@@ -17,7 +16,11 @@ fn main() -> Result<(), Box<dyn Error>> {
   match result {
     Ok(()) => Ok(()),
     Err(error) => {
-      println!("Unable to read directory due to: {}", error);
+      let inner = error.get_ref().and_then(|inner| inner.downcast_ref::<FileEntryParsingError>());
+      match inner {
+        Some(narrow @ FileEntryParsingError::TerminalTooNarrow { .. }) => println!("{}", narrow),
+        _ => println!("Unable to read directory due to: {}", error),
+      }
       process::exit(1);
     }
   }
@@ -0,0 +1,121 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+#[derive(Debug, PartialEq, Eq)]
+struct Mount {
+    point: PathBuf,
+    fs_type: String,
+}
+
+/// The mount point an entry lives under, taken from the mount table so bind mounts show up as
+/// boundaries even though they share a device with their source.
+pub(crate) fn mount_point(path: &Path) -> Option<PathBuf> {
+    containing_mount(path).map(|mount| mount.point.clone())
+}
+
+/// The type named in the mount table, e.g. `devtmpfs`, for filesystems whose magic number is
+/// shared with others.
+pub(crate) fn filesystem_type(path: &Path) -> Option<&'static str> {
+    containing_mount(path).map(|mount| mount.fs_type.as_str())
+}
+
+fn containing_mount(path: &Path) -> Option<&'static Mount> {
+    // the entry itself may be a symlink, its own mount is the one holding its directory
+    let directory = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let resolved = match path.file_name() {
+        Some(name) => fs::canonicalize(directory).ok()?.join(name),
+        None => fs::canonicalize(path).ok()?,
+    };
+    // a later mount on the same point hides the earlier ones
+    mounts()
+        .iter()
+        .filter(|mount| resolved.starts_with(&mount.point))
+        .max_by_key(|mount| mount.point.components().count())
+}
+
+/// Read once per run, systems without `/proc/self/mountinfo` have no mount column values.
+fn mounts() -> &'static [Mount] {
+    static MOUNTS: OnceLock<Vec<Mount>> = OnceLock::new();
+    MOUNTS.get_or_init(|| {
+        fs::read_to_string("/proc/self/mountinfo")
+            .map(|table| parse_mountinfo(&table))
+            .unwrap_or_default()
+    })
+}
+
+/// The fifth field of each line is the mount point, with spaces and the like escaped in octal.
+/// The optional fields that follow end with a lone `-`, after which comes the filesystem type.
+fn parse_mountinfo(table: &str) -> Vec<Mount> {
+    table
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split(' ');
+            let point = fields.nth(4)?;
+            let fs_type = fields.skip_while(|field| *field != "-").nth(1)?;
+            Some(Mount {
+                point: PathBuf::from(unescape(point)),
+                fs_type: fs_type.to_string(),
+            })
+        })
+        .collect()
+}
+
+fn unescape(field: &str) -> String {
+    let mut unescaped = String::new();
+    let mut rest = field;
+    while let Some(index) = rest.find('\\') {
+        unescaped.push_str(&rest[..index]);
+        let code = rest
+            .get(index + 1..index + 4)
+            .and_then(|digits| u8::from_str_radix(digits, 8).ok());
+        match code {
+            Some(code) => {
+                unescaped.push(char::from(code));
+                rest = &rest[index + 4..];
+            }
+            None => {
+                unescaped.push('\\');
+                rest = &rest[index + 1..];
+            }
+        }
+    }
+    unescaped + rest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_mount_points_from_mountinfo() {
+        let table = "22 1 8:1 / / rw,relatime shared:1 - ext4 /dev/sda1 rw\n\
+                     36 22 0:5 / /mnt/my\\040share rw - cifs //server/share rw\n";
+        assert_eq!(
+            parse_mountinfo(table),
+            vec![
+                Mount {
+                    point: PathBuf::from("/"),
+                    fs_type: String::from("ext4"),
+                },
+                Mount {
+                    point: PathBuf::from("/mnt/my share"),
+                    fs_type: String::from("cifs"),
+                },
+            ]
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn every_entry_is_under_some_mount() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file = temp_dir.path().join("file.txt");
+        fs::write(&file, "").unwrap();
+        let mount_point = mount_point(&file).unwrap();
+        assert!(fs::canonicalize(&file).unwrap().starts_with(mount_point));
+    }
+}
@@ -0,0 +1,80 @@
+use unicode_normalization::{is_nfc, is_nfd};
+
+/// The Unicode normalisation form a name was stored in. Names that read the same in both forms,
+/// such as plain ASCII, have no form and never stand out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Form {
+    Nfc,
+    Nfd,
+    Mixed,
+}
+
+impl Form {
+    fn label(&self) -> &'static str {
+        match self {
+            Form::Nfc => "NFC",
+            Form::Nfd => "NFD",
+            Form::Mixed => "mixed normalisation",
+        }
+    }
+}
+
+pub(crate) fn form(name: &str) -> Option<Form> {
+    match (is_nfc(name), is_nfd(name)) {
+        (true, true) => None,
+        (true, false) => Some(Form::Nfc),
+        (false, true) => Some(Form::Nfd),
+        (false, false) => Some(Form::Mixed),
+    }
+}
+
+/// The form most siblings use, NFC when they are evenly split as that is what nearly every tool
+/// writes. macOS sync tools looking up a name in the other form report it missing.
+pub(crate) fn expected_form<'a>(names: impl Iterator<Item = &'a str>) -> Form {
+    let (mut composed, mut decomposed) = (0, 0);
+    for name in names {
+        match form(name) {
+            Some(Form::Nfc) => composed += 1,
+            Some(Form::Nfd) => decomposed += 1,
+            _ => {}
+        }
+    }
+    if decomposed > composed {
+        Form::Nfd
+    } else {
+        Form::Nfc
+    }
+}
+
+pub(crate) fn marker(name: &str, expected: Form) -> String {
+    match form(name) {
+        Some(form) if form != expected => format!("  [{}]", form.label()),
+        _ => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COMPOSED: &str = "caf\u{e9}.txt";
+    const DECOMPOSED: &str = "cafe\u{301}.txt";
+
+    #[test]
+    fn names_in_the_minority_form_are_marked() {
+        assert_eq!(form("plain.txt"), None);
+        assert_eq!(form(COMPOSED), Some(Form::Nfc));
+        assert_eq!(form(DECOMPOSED), Some(Form::Nfd));
+        assert_eq!(form("caf\u{e9}e\u{301}"), Some(Form::Mixed));
+        let names = ["plain.txt", COMPOSED, "r\u{e9}sum\u{e9}.pdf", DECOMPOSED];
+        let expected = expected_form(names.into_iter());
+        assert_eq!(expected, Form::Nfc);
+        assert_eq!(marker(DECOMPOSED, expected), "  [NFD]");
+        assert_eq!(marker(COMPOSED, expected), "");
+        assert_eq!(marker("plain.txt", expected), "");
+        assert_eq!(
+            expected_form([DECOMPOSED, DECOMPOSED, COMPOSED].into_iter()),
+            Form::Nfd
+        );
+    }
+}
@@ -1,249 +1,1005 @@
+use crate::color;
+use crate::columns::{ColumnLimits, OptionalColumn};
+use crate::entries::{EntryKind, FileEntry};
+use crate::highlight::Highlights;
+use crate::locale::{DatePrecision, Locale};
+use crate::normalisation::{self, Form};
+use crate::sorting::{self, SortKey};
+use crate::virtual_fs::VirtualFs;
 use crate::FileEntryParsingError::UnableToCalculatePathLengths;
 use crate::TimeOptions::{Created, Modified};
 use crate::{FileEntryParsingError, TimeOptions};
+use chrono::format::{Item, StrftimeItems};
 use chrono::{DateTime, Utc};
-use std::fs::{DirEntry, Metadata};
+use serde::Serialize;
+use std::ffi::OsStr;
+use std::fs::{self, Metadata};
+use std::io;
+use std::num::NonZeroUsize;
 use std::ops::Add;
-use std::path::PathBuf;
-use std::time::{Duration, UNIX_EPOCH};
+use std::path::{self, Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use unicode_segmentation::UnicodeSegmentation;
 
 pub const FLOPPY: &str = "\u{1F4BE}";
-const FOLDER: &str = "\u{1F4C1}";
+pub const FOLDER: &str = "\u{1F4C1}";
+pub const LINK: &str = "\u{1F517}";
 pub const RESERVED_LENGTH: usize = 66;
 pub const DATE_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.3f";
+const DATE_WIDTH: usize = 23;
+const DATE_HEADING_WIDTH: usize = "Date Modified".len();
+const PERMISSIONS_WIDTH: usize = 12;
+const MIN_NAME_WIDTH: usize = 20;
+/// Below room for an icon and the narrowest name column nothing can be lined up.
+pub(crate) const MIN_WIDTH: usize = 3 + MIN_NAME_WIDTH;
+const TWO_LINE_INDENT: &str = "    ";
+const GRID_GAP: usize = 2;
+const NOT_UTF8_MARKER: &str = "  [not UTF-8]";
+const MISSING_DATE: &str = "-";
+
+/// Where directories are placed relative to files in a textual listing.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DirGrouping {
+    First,
+    #[default]
+    Last,
+    Mixed,
+}
+
+/// How entries are named. Recursive listings default to `Relative` so nested entries stay
+/// unambiguous without repeating the target on every line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PathStyle {
+    Name,
+    Relative,
+    Absolute,
+}
+
+impl PathStyle {
+    pub(crate) fn label(&self, entry_path: &Path, root: &Path) -> PathBuf {
+        match self {
+            PathStyle::Name => entry_path
+                .file_name()
+                .map_or_else(|| entry_path.to_path_buf(), PathBuf::from),
+            PathStyle::Relative => match entry_path.strip_prefix(root) {
+                Ok(relative) => relative.to_path_buf(),
+                Err(_) => entry_path.to_path_buf(),
+            },
+            PathStyle::Absolute => {
+                path::absolute(entry_path).unwrap_or_else(|_| entry_path.to_path_buf())
+            }
+        }
+    }
+}
+
+/// Order in which names fill a multi-column grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GridOrder {
+    Down,
+    Across,
+}
+
+/// How entry types are marked before names. `Ascii` uses the type letters of `ls -l` for
+/// terminals and fonts without emoji, padded to the same two columns.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum IconStyle {
+    #[default]
+    Emoji,
+    Ascii,
+}
+
+impl IconStyle {
+    pub(crate) fn icon(self, emoji: &'static str) -> &'static str {
+        match (self, emoji) {
+            (IconStyle::Emoji, _) => emoji,
+            (IconStyle::Ascii, FOLDER) => "d ",
+            (IconStyle::Ascii, LINK) => "l ",
+            (IconStyle::Ascii, _) => "- ",
+        }
+    }
+}
 
 pub struct FormattingCommand {
     extended_attr: bool,
     width: usize,
-    files: Vec<DirEntry>,
-    directories: Vec<DirEntry>,
+    files: Vec<FileEntry>,
+    directories: Vec<FileEntry>,
+    path_style: Option<PathStyle>,
+    root: PathBuf,
+    optional_columns: Vec<OptionalColumn>,
+    dir_grouping: DirGrouping,
+    locale: Locale,
+    column_limits: ColumnLimits,
+    two_line: bool,
+    grid: Option<GridOrder>,
+    repeat_header: Option<NonZeroUsize>,
+    column_separator: Option<String>,
+    sort: SortKey,
+    reverse: bool,
+    dereference: bool,
+    color: bool,
+    bare: bool,
+    icons: IconStyle,
+    strict_utf8: bool,
+    relative_time: bool,
+    time_format: Option<String>,
+    highlights: Highlights,
+    virtual_fs: Option<VirtualFs>,
+    expected_form: Form,
+}
+
+/// Widths of the `-l` columns once limits and the terminal width have been taken into account.
+/// Columns set to `None` were dropped to leave room for the names, the optional columns are
+/// shown alongside permissions. A two line layout puts every column under the name instead.
+struct ExtendedLayout {
+    two_line: bool,
+    separator: String,
+    name: usize,
+    name_heading: usize,
+    created: Option<usize>,
+    permissions: Option<usize>,
+    modified: usize,
 }
 
 impl FormattingCommand {
     pub fn new(
         extended_attr: bool,
         width: usize,
-        files: Vec<DirEntry>,
-        directories: Vec<DirEntry>,
+        files: Vec<FileEntry>,
+        directories: Vec<FileEntry>,
     ) -> Self {
+        let names = files
+            .iter()
+            .chain(directories.iter())
+            .map(|entry| entry.name.as_str());
+        let expected_form = normalisation::expected_form(names);
         FormattingCommand {
             extended_attr,
             width,
             files,
             directories,
+            path_style: None,
+            root: PathBuf::new(),
+            optional_columns: Vec::new(),
+            dir_grouping: DirGrouping::default(),
+            locale: Locale::default(),
+            column_limits: ColumnLimits::default(),
+            two_line: false,
+            grid: None,
+            repeat_header: None,
+            column_separator: None,
+            sort: SortKey::Name,
+            reverse: false,
+            dereference: false,
+            color: false,
+            bare: false,
+            icons: IconStyle::default(),
+            strict_utf8: false,
+            relative_time: false,
+            time_format: None,
+            highlights: Highlights::default(),
+            virtual_fs: None,
+            expected_form,
+        }
+    }
+
+    pub fn with_path_style(mut self, path_style: PathStyle, root: &Path) -> Self {
+        self.path_style = Some(path_style);
+        self.root = root.to_path_buf();
+        self
+    }
+
+    pub fn with_highlights(mut self, highlights: &Highlights) -> Self {
+        self.highlights = highlights.clone();
+        self
+    }
+
+    /// Marks the listing as coming from a virtual filesystem, its files are labelled and their
+    /// meaningless sizes hidden.
+    pub(crate) fn with_virtual_fs(mut self, virtual_fs: VirtualFs) -> Self {
+        self.virtual_fs = Some(virtual_fs);
+        self
+    }
+
+    /// Notes trailing a row: crossed highlight thresholds, pseudo files, names stored in a
+    /// different Unicode normalisation form than their siblings, names that are not UTF-8 and
+    /// links that cannot be dereferenced.
+    fn row_marker(&self, entry: &FileEntry) -> String {
+        let mut marker = normalisation::marker(&entry.name, self.expected_form);
+        if entry.path.file_name().and_then(OsStr::to_str).is_none() {
+            marker += NOT_UTF8_MARKER;
+        }
+        if self.dereference
+            && entry.kind == EntryKind::Symlink
+            && fs::metadata(&entry.path).is_err()
+        {
+            marker += "  [broken link]";
+        }
+        if self.highlights.is_empty() && self.virtual_fs.is_none() {
+            return marker;
+        }
+        let Some(meta_data) = self.metadata(entry) else {
+            return marker;
+        };
+        marker += &self.highlights.marker(&meta_data, SystemTime::now());
+        if let Some(virtual_fs) = self.virtual_fs.filter(|_| meta_data.is_file()) {
+            marker += &format!("  [{}]", virtual_fs.name());
+        }
+        marker
+    }
+
+    /// Without a chosen style the `-l` view shows the path as given and the short view the name.
+    /// Symlinks in the `-l` view are followed by their target as `name -> target`, unless they
+    /// are being dereferenced.
+    fn entry_label(&self, entry: &FileEntry) -> PathBuf {
+        let label = match self.path_style {
+            Some(style) => style.label(&entry.path, &self.root),
+            None if self.extended_attr => entry.path.clone(),
+            None => PathStyle::Name.label(&entry.path, &self.root),
+        };
+        if !self.extended_attr || self.dereference || entry.kind != EntryKind::Symlink {
+            return label;
+        }
+        match fs::read_link(&entry.path) {
+            Ok(target) => {
+                let mut label = label.into_os_string();
+                label.push(" -> ");
+                label.push(target);
+                PathBuf::from(label)
+            }
+            Err(_) => label,
+        }
+    }
+
+    pub fn with_optional_columns(mut self, optional_columns: &[OptionalColumn]) -> Self {
+        self.optional_columns = optional_columns.to_vec();
+        self
+    }
+
+    pub fn with_dir_grouping(mut self, dir_grouping: DirGrouping) -> Self {
+        self.dir_grouping = dir_grouping;
+        self
+    }
+
+    pub fn with_locale(mut self, locale: Locale) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    pub fn with_two_line_layout(mut self) -> Self {
+        self.two_line = true;
+        self
+    }
+
+    pub fn with_grid(mut self, order: GridOrder) -> Self {
+        self.grid = Some(order);
+        self
+    }
+
+    /// Repeats the header before every `every` entries; zero leaves it printed once.
+    pub fn with_repeated_header(mut self, every: usize) -> Self {
+        self.repeat_header = NonZeroUsize::new(every);
+        self
+    }
+
+    pub fn with_column_separator(mut self, separator: &str) -> Self {
+        self.column_separator = Some(separator.to_string());
+        self
+    }
+
+    /// Only affects the mixed grouping, otherwise files and directories keep the order given.
+    pub fn with_sort(mut self, sort: SortKey) -> Self {
+        self.sort = sort;
+        self
+    }
+
+    pub fn with_reverse_sort(mut self) -> Self {
+        self.reverse = true;
+        self
+    }
+
+    pub fn with_dereference(mut self) -> Self {
+        self.dereference = true;
+        self
+    }
+
+    /// Names that are not valid UTF-8 fail the listing instead of being shown with replacement
+    /// characters and a marker.
+    pub fn with_strict_utf8(mut self) -> Self {
+        self.strict_utf8 = true;
+        self
+    }
+
+    /// Dates read as the time since, such as `3 minutes ago`, instead of the locale's format.
+    pub fn with_relative_time(mut self) -> Self {
+        self.relative_time = true;
+        self
+    }
+
+    /// A strftime format for the dates in place of the locale's, the date columns are as wide as
+    /// it renders.
+    pub fn with_time_format(mut self, time_format: String) -> Self {
+        self.time_format = Some(time_format);
+        self
+    }
+
+    /// Only the names, one per line, without icons, header or markers. Has no effect on `-l`.
+    pub fn with_bare_names(mut self) -> Self {
+        self.bare = true;
+        self
+    }
+
+    pub fn with_icons(mut self, icons: IconStyle) -> Self {
+        self.icons = icons;
+        self
+    }
+
+    pub fn with_color(mut self) -> Self {
+        self.color = true;
+        self
+    }
+
+    fn label_text(&self, entry: &FileEntry) -> Result<String, FileEntryParsingError> {
+        let label = self.entry_label(entry);
+        match label.to_str() {
+            Some(text) => Ok(text.to_string()),
+            None if self.strict_utf8 => Err(FileEntryParsingError::FileNameInvalidUnicode),
+            None => Ok(label.to_string_lossy().to_string()),
+        }
+    }
+
+    fn paint(&self, entry: &FileEntry, name: &str) -> String {
+        if self.color {
+            color::paint(entry, name)
+        } else {
+            name.to_string()
+        }
+    }
+
+    /// Dereferenced links take the icon of what they point at, dangling ones keep the link icon.
+    fn file_icon(&self, entry: &FileEntry) -> &'static str {
+        if entry.kind != EntryKind::Symlink {
+            return FLOPPY;
+        }
+        match fs::metadata(&entry.path) {
+            Ok(target) if self.dereference && target.is_dir() => FOLDER,
+            Ok(_) if self.dereference => FLOPPY,
+            _ => LINK,
+        }
+    }
+
+    /// The link's own metadata unless dereferencing, when a dangling link has none.
+    fn metadata(&self, entry: &FileEntry) -> Option<Metadata> {
+        if self.dereference {
+            fs::metadata(&entry.path).ok()
+        } else {
+            entry.metadata.clone()
+        }
+    }
+
+    pub fn with_column_limits(mut self, column_limits: ColumnLimits) -> Self {
+        self.column_limits = column_limits;
+        self
+    }
+
+    /// Narrow terminals drop the created date first and then the permissions so that names keep
+    /// at least `MIN_NAME_WIDTH` columns, when even that is not enough the two line layout is used.
+    fn extended_layout(&self, longest: usize) -> ExtendedLayout {
+        let limits = &self.column_limits;
+        let date_width = self.date_width();
+        let mut created = Some(limits.created.apply(date_width));
+        let mut permissions = Some(limits.permissions.apply(PERMISSIONS_WIDTH));
+        let modified = limits.modified.apply(date_width);
+        let separator = self.column_separator.as_deref().unwrap_or(" ");
+        let extra_separator_width = separator.graphemes(true).count().saturating_sub(1);
+        let reserved = |created: Option<usize>, permissions: Option<usize>| {
+            let separators = 1
+                + usize::from(created.is_some())
+                + permissions.map_or(0, |_| 1 + self.optional_columns.len());
+            RESERVED_LENGTH - 2 * DATE_WIDTH - PERMISSIONS_WIDTH - 3
+                + created.map_or(0, |width| width + 1)
+                + permissions.map_or(0, |width| width + 1 + self.optional_columns_width())
+                + modified
+                + 1
+                + separators * extra_separator_width
+        };
+        let wanted_name = longest.min(MIN_NAME_WIDTH);
+        if self.width.saturating_sub(reserved(created, permissions)) < wanted_name {
+            created = None;
+        }
+        if self.width.saturating_sub(reserved(created, permissions)) < wanted_name {
+            permissions = None;
+        }
+        if self.two_line || self.width.saturating_sub(reserved(created, permissions)) < wanted_name
+        {
+            return ExtendedLayout {
+                two_line: true,
+                separator: separator.to_string(),
+                name: longest.min(self.width.saturating_sub(3)),
+                name_heading: 0,
+                created: Some(limits.created.apply(date_width)),
+                permissions: Some(limits.permissions.apply(PERMISSIONS_WIDTH)),
+                modified,
+            };
+        }
+        let reserved = reserved(created, permissions);
+        let available = self.width.saturating_sub(reserved);
+        let heading_space = (longest + 4).min(self.width.saturating_sub(reserved - 6));
+        ExtendedLayout {
+            two_line: false,
+            separator: separator.to_string(),
+            name: limits.name.apply(longest.min(available)),
+            name_heading: limits.name.apply(heading_space.saturating_sub(4)) + 4,
+            created,
+            permissions,
+            modified,
+        }
+    }
+
+    /// Custom formats are measured on a date with the longest month and weekday names, and kept
+    /// wide enough for the column headings.
+    fn date_width(&self) -> usize {
+        let Some(time_format) = &self.time_format else {
+            return DATE_WIDTH;
+        };
+        let widest = UNIX_EPOCH + Duration::new(970_099_199, 999_999_999);
+        format_date_with(widest, time_format)
+            .graphemes(true)
+            .count()
+            .max(DATE_HEADING_WIDTH)
+    }
+
+    fn ordered_entries(&self) -> Vec<(&FileEntry, &'static str)> {
+        let files = self
+            .files
+            .iter()
+            .map(|entry| (entry, self.icons.icon(self.file_icon(entry))));
+        let directories = self
+            .directories
+            .iter()
+            .map(|entry| (entry, self.icons.icon(FOLDER)));
+        match self.dir_grouping {
+            DirGrouping::First => directories.chain(files).collect(),
+            DirGrouping::Last => files.chain(directories).collect(),
+            DirGrouping::Mixed => {
+                let mut entries: Vec<_> = files.chain(directories).collect();
+                entries.sort_by(|(left, _), (right, _)| {
+                    sorting::compare(*left, *right, self.sort, self.reverse)
+                });
+                entries
+            }
         }
     }
+
+    fn optional_columns_width(&self) -> usize {
+        self.optional_columns
+            .iter()
+            .map(|column| column.width())
+            .sum()
+    }
+}
+
+/// The mode bits as `ls -l` shows them, `rwxr-xr-x`. Windows only has a read only attribute to
+/// go on so it keeps the two state description.
+#[cfg(unix)]
+fn permissions_label(meta_data: &Metadata) -> String {
+    use std::os::unix::fs::PermissionsExt;
+    crate::columns::format_symbolic_mode(meta_data.permissions().mode())
+}
+
+#[cfg(not(unix))]
+fn permissions_label(meta_data: &Metadata) -> String {
+    if meta_data.permissions().readonly() {
+        String::from("read only")
+    } else {
+        String::from("writable")
+    }
 }
 
 pub fn generate_textual_display(
     command: FormattingCommand,
 ) -> Result<String, FileEntryParsingError> {
+    if command.bare && !command.extended_attr {
+        let names = command
+            .ordered_entries()
+            .into_iter()
+            .map(|(entry, _)| command.label_text(entry))
+            .collect::<Result<Vec<String>, FileEntryParsingError>>()?;
+        return Ok(names.join("\n"));
+    }
     let Some(longest) = analyse_longest(&command) else {
         return Err(UnableToCalculatePathLengths);
     };
-    let mut header_row = if command.extended_attr && command.width > 80 {
-        create_extended_attr_header(command.width, longest)
+    let mut header_row = if command.extended_attr {
+        create_extended_attr_header(&command, longest)
     } else {
         vec![
             String::from("Name:"),
             String::from("=").repeat(command.width),
         ]
     };
-    let mut string_list_of_files = orchestrate_formatting(&command, longest)?;
-    let mut string_list_of_dirs = format_each_entry(&command.directories, FOLDER)?;
-    header_row.append(&mut string_list_of_files);
-    header_row.append(&mut string_list_of_dirs);
-    Ok(header_row.join("\n"))
+    let string_list_of_entries =
+        orchestrate_formatting(&command, &command.ordered_entries(), longest)?;
+    let Some(every) = command.repeat_header else {
+        header_row.extend(string_list_of_entries);
+        return Ok(header_row.join("\n"));
+    };
+    let mut lines = Vec::new();
+    for chunk in string_list_of_entries.chunks(every.get()) {
+        lines.extend(header_row.iter().cloned());
+        lines.extend(chunk.iter().cloned());
+    }
+    if lines.is_empty() {
+        lines = header_row;
+    }
+    Ok(lines.join("\n"))
 }
 
 fn analyse_longest(command: &FormattingCommand) -> Option<usize> {
     let joined = [&command.files, &command.directories];
-    let full_list: Vec<&DirEntry> = joined.iter().flat_map(|vec| vec.iter()).collect();
+    let full_list: Vec<&FileEntry> = joined.iter().flat_map(|vec| vec.iter()).collect();
     full_list
         .into_iter()
-        .map(|dir_entry: &DirEntry| dir_entry.path())
-        .map(|path: PathBuf| {
-            let path_as_str_option = path.to_str();
-            let path_as_str = path_as_str_option.unwrap_or("");
-            String::from(path_as_str)
-        })
-        .map(|stringy| stringy.graphemes(true).count())
+        .map(|entry: &FileEntry| command.entry_label(entry))
+        .map(|path: PathBuf| path.to_string_lossy().graphemes(true).count())
         .max()
 }
 
-fn create_extended_attr_header(width: usize, longest: usize) -> Vec<String> {
-    let date_created_heading = create_heading_of_width(24usize, "Date Created");
-    let date_modified_heading = create_heading_of_width(24usize, "Date Modified");
-    let permissions_heading = create_heading_of_width(13usize, "Permissions");
-    let remaining_width = if longest + 4 <= width - 60 {
-        longest + 4
-    } else {
-        width - 60
-    };
-    let name_heading = create_heading_of_width(remaining_width, "Name");
-    let header = "".to_string();
-    vec![
-        header
-            + name_heading.as_str()
-            + date_created_heading.as_str()
-            + permissions_heading.as_str()
-            + date_modified_heading.as_str(),
-        String::from("=").repeat(width),
-    ]
+fn create_extended_attr_header(command: &FormattingCommand, longest: usize) -> Vec<String> {
+    let layout = command.extended_layout(longest);
+    if command.column_separator.is_some() && !layout.two_line {
+        return vec![
+            separated_heading(command, &layout),
+            String::from("=").repeat(command.width),
+        ];
+    }
+    if layout.two_line {
+        return vec![
+            String::from("Name"),
+            String::from(TWO_LINE_INDENT) + details_heading(command, &layout).trim_end(),
+            String::from("=").repeat(command.width),
+        ];
+    }
+    let mut header = create_heading_of_width(layout.name_heading, "Name");
+    if let Some(created) = layout.created {
+        header += create_heading_of_width(created + 1, "Date Created").as_str();
+    }
+    if let Some(permissions) = layout.permissions {
+        header += create_heading_of_width(permissions + 1, "Permissions").as_str();
+        for column in &command.optional_columns {
+            header += create_heading_of_width(column.width(), column.heading()).as_str();
+        }
+    }
+    header += create_heading_of_width(layout.modified + 1, "Date Modified").as_str();
+    vec![header, String::from("=").repeat(command.width)]
+}
+
+/// With an explicit separator each heading is padded to exactly its column's width so the
+/// separators line up between the header and the rows.
+fn separated_heading(command: &FormattingCommand, layout: &ExtendedLayout) -> String {
+    // the icon takes two columns followed by a space
+    let mut headings = vec![set_file_name_length(layout.name + 3, "Name")];
+    if let Some(created) = layout.created {
+        headings.push(set_file_name_length(created, "Date Created"));
+    }
+    if let Some(permissions) = layout.permissions {
+        headings.push(set_file_name_length(permissions, "Permissions"));
+        for column in &command.optional_columns {
+            headings.push(set_file_name_length(column.width() - 1, column.heading()));
+        }
+    }
+    headings.push(set_file_name_length(layout.modified, "Date Modified"));
+    headings.join(&layout.separator)
+}
+
+fn details_heading(command: &FormattingCommand, layout: &ExtendedLayout) -> String {
+    if command.column_separator.is_some() {
+        let mut headings = Vec::new();
+        if let Some(created) = layout.created {
+            headings.push(set_file_name_length(created, "Date Created"));
+        }
+        if let Some(permissions) = layout.permissions {
+            headings.push(set_file_name_length(permissions, "Permissions"));
+            for column in &command.optional_columns {
+                headings.push(set_file_name_length(column.width() - 1, column.heading()));
+            }
+        }
+        headings.push(set_file_name_length(layout.modified, "Date Modified"));
+        headings.push(String::from("Size"));
+        return headings.join(&layout.separator);
+    }
+    let mut heading = String::new();
+    if let Some(created) = layout.created {
+        heading += create_heading_of_width(created + 1, "Date Created").as_str();
+    }
+    if let Some(permissions) = layout.permissions {
+        heading += create_heading_of_width(permissions + 1, "Permissions").as_str();
+        for column in &command.optional_columns {
+            heading += create_heading_of_width(column.width(), column.heading()).as_str();
+        }
+    }
+    heading += create_heading_of_width(layout.modified + 1, "Date Modified").as_str();
+    heading + "Size"
 }
 
+/// Headings that do not fit are cut short so there is always a space before the next column.
 fn create_heading_of_width(head_width: usize, name: &str) -> String {
-    name.to_string().add(
-        " ".repeat(head_width - name.graphemes(true).count())
-            .as_str(),
-    )
+    let name: String = name
+        .graphemes(true)
+        .take(head_width.saturating_sub(1))
+        .collect();
+    let padding = head_width - name.graphemes(true).count();
+    name.add(" ".repeat(padding).as_str())
 }
 
 fn orchestrate_formatting(
     command: &FormattingCommand,
+    entries: &[(&FileEntry, &str)],
     longest: usize,
 ) -> Result<Vec<String>, FileEntryParsingError> {
-    Ok(if command.extended_attr && command.width > 80 {
-        let available_filename_space = command.width - RESERVED_LENGTH;
-        let file_name_target_length = if available_filename_space > longest {
-            longest
-        } else {
-            available_filename_space
-        };
-        format_each_ext_attr_entry(&command.files, file_name_target_length)?
-    } else if command.extended_attr && command.width <= 80 {
-        panic!("requires minimum console width of 80");
+    if command.width < MIN_WIDTH {
+        return Err(FileEntryParsingError::TerminalTooNarrow {
+            required: MIN_WIDTH,
+            actual: command.width,
+        });
+    }
+    Ok(if command.extended_attr {
+        format_each_ext_attr_entry(entries, command, &command.extended_layout(longest))?
     } else {
-        format_each_entry(&command.files, FLOPPY)?
+        let names = format_each_entry(entries, command)?;
+        match command.grid {
+            Some(order) => arrange_in_grid(&names, command.width, order),
+            None => names,
+        }
     })
 }
 
 fn format_each_ext_attr_entry(
-    files: &[DirEntry],
-    max_file_name_width: usize,
+    entries: &[(&FileEntry, &str)],
+    command: &FormattingCommand,
+    layout: &ExtendedLayout,
 ) -> Result<Vec<String>, FileEntryParsingError> {
-    files
+    entries
         .iter()
-        .map(|dir| format_file_entry_with_ext_attr(dir, max_file_name_width))
+        .map(|(dir, icon)| {
+            let label = command.label_text(dir)?;
+            format_file_entry_with_ext_attr(dir, &label, icon, layout, command)
+                .map(|row| row + &command.row_marker(dir))
+        })
         .collect()
 }
 
 fn format_file_entry_with_ext_attr(
-    dir: &DirEntry,
-    allowed_width: usize,
+    dir: &FileEntry,
+    label: &str,
+    icon: &str,
+    layout: &ExtendedLayout,
+    command: &FormattingCommand,
 ) -> Result<String, FileEntryParsingError> {
-    let file_name_as_path = dir.path();
-    let file_name = match file_name_as_path.to_str() {
-        Some(file_name) => set_file_name_length(allowed_width, file_name),
-        None => return Err(FileEntryParsingError::FileNameInvalidUnicode),
-    };
-    let meta_data = match dir.metadata() {
-        Ok(meta) => meta,
-        Err(error) => {
-            return Err(FileEntryParsingError::MissingMetaDataError {
-                original_error: error.kind(),
-            })
+    let (optional_columns, locale) = (&command.optional_columns, &command.locale);
+    let pseudo = command.virtual_fs.is_some();
+    let file_name_as_path = &dir.path;
+    let file_name = command.paint(dir, &set_file_name_length(layout.name, label));
+    let meta_data = match command.metadata(dir) {
+        Some(meta) => meta,
+        None => {
+            return Ok(format_placeholder_entry(
+                icon,
+                &file_name,
+                layout,
+                optional_columns,
+            ))
         }
     };
-    let date_created = get_formatted_date(&meta_data, Created);
-    let permissions = if meta_data.permissions().readonly() {
-        "read only   "
+    let mut columns = if layout.two_line {
+        Vec::new()
     } else {
-        "writable    "
+        vec![format!("{} {}", icon, file_name)]
     };
-    let date_modified = get_formatted_date(&meta_data, Modified);
-    Ok([
-        FLOPPY,
-        file_name.as_str(),
-        &date_created,
-        permissions,
-        &date_modified,
-    ]
-    .join(" "))
+    if let Some(created) = layout.created {
+        columns.push(set_file_name_length(
+            created,
+            &get_formatted_date(&meta_data, Created, command, created)?,
+        ));
+    }
+    if let Some(permissions) = layout.permissions {
+        let mut permissions = set_file_name_length(permissions, &permissions_label(&meta_data));
+        for column in optional_columns {
+            permissions += &layout.separator;
+            permissions += column.render(file_name_as_path, &meta_data).as_str();
+        }
+        columns.push(permissions);
+    }
+    columns.push(set_file_name_length(
+        layout.modified,
+        &get_formatted_date(&meta_data, Modified, command, layout.modified)?,
+    ));
+    if layout.two_line {
+        columns.push(if pseudo && meta_data.is_file() {
+            String::from("-")
+        } else {
+            format_localised_size(meta_data.len(), locale)
+        });
+        return Ok(format!(
+            "{} {}\n{}{}",
+            icon,
+            file_name.trim_end(),
+            TWO_LINE_INDENT,
+            columns.join(&layout.separator)
+        ));
+    }
+    Ok(columns.join(&layout.separator))
+}
+
+/// Row for an entry whose metadata could not be read so the listing still accounts for it.
+fn format_placeholder_entry(
+    icon: &str,
+    file_name: &str,
+    layout: &ExtendedLayout,
+    optional_columns: &[OptionalColumn],
+) -> String {
+    let unknown = |width: usize| format!("{:<width$}", "?", width = width);
+    let mut columns = if layout.two_line {
+        Vec::new()
+    } else {
+        vec![format!("{} {}", icon, file_name)]
+    };
+    if let Some(created) = layout.created {
+        columns.push(unknown(created));
+    }
+    if let Some(permissions) = layout.permissions {
+        let mut permissions = unknown(permissions);
+        for column in optional_columns {
+            permissions += &layout.separator;
+            permissions += unknown(column.width() - 1).as_str();
+        }
+        columns.push(permissions);
+    }
+    columns.push(unknown(layout.modified));
+    if layout.two_line {
+        columns.push(String::from("?"));
+        return format!(
+            "{} {}\n{}{}",
+            icon,
+            file_name.trim_end(),
+            TWO_LINE_INDENT,
+            columns.join(&layout.separator)
+        );
+    }
+    columns.join(&layout.separator)
 }
 
+/// Cuts or pads `file_name` to `allowed_width` columns, counting graphemes rather than bytes so
+/// accented names and formats line up.
 fn set_file_name_length(allowed_width: usize, file_name: &str) -> String {
-    if file_name.graphemes(true).count() >= allowed_width {
-        let file_name_strs = file_name
-            .graphemes(true)
-            .take(allowed_width)
-            .collect::<Vec<&str>>();
-        file_name_strs.join("")
+    let graphemes: Vec<&str> = file_name.graphemes(true).collect();
+    if graphemes.len() >= allowed_width {
+        graphemes[..allowed_width].concat()
     } else {
-        let spacer_length = allowed_width - file_name.len();
-        let spacer = " ".repeat(spacer_length);
+        let spacer = " ".repeat(allowed_width - graphemes.len());
         file_name.to_string() + spacer.as_str()
     }
 }
 
-fn get_formatted_date(meta_data: &Metadata, options: TimeOptions) -> String {
-    let since_epoch = match options {
-        Created => meta_data
-            .created()
-            .expect(
-                "Not anticipated to run on systems that do not implement date created for files",
-            )
-            .duration_since(UNIX_EPOCH)
-            .expect("Clock may have gone backwards"),
-        TimeOptions::Modified => meta_data
-            .modified()
-            .expect(
-                "Not anticipated to run on systems that do not implement date modified for files",
-            )
-            .duration_since(UNIX_EPOCH)
-            .expect("Clock may have gone backwards"),
+/// Drops the least significant parts of the timestamp until it fits in `width` columns.
+/// Many Linux filesystems record no creation time, those dates show as `-` like other values
+/// the filesystem does not have.
+fn get_formatted_date(
+    meta_data: &Metadata,
+    options: TimeOptions,
+    command: &FormattingCommand,
+    width: usize,
+) -> Result<String, FileEntryParsingError> {
+    let Some(time) = entry_time(meta_data, options)? else {
+        return Ok(String::from(MISSING_DATE));
     };
-    format_date(since_epoch)
+    if command.relative_time {
+        let age = SystemTime::now().duration_since(time).unwrap_or_default();
+        return Ok(format_age(age));
+    }
+    Ok(match &command.time_format {
+        Some(time_format) => format_date_with(time, time_format),
+        None => {
+            let precision = DatePrecision::fitting(width);
+            format_date_with(time, &command.locale.date_format_with(precision))
+        }
+    })
 }
 
-fn format_date(since_epoch: Duration) -> String {
-    DateTime::<Utc>::from_timestamp(
-        since_epoch.as_secs() as i64,
-        since_epoch.subsec_nanos(),
-    )
-      .expect(
-          "An invalid timestamp was provided, given this is from the system this should not happen",
-      )
-      .format(DATE_FORMAT)
-      .to_string()
+/// The largest whole unit of `age`, with anything under a minute, or in the future, as `just now`.
+fn format_age(age: Duration) -> String {
+    const UNITS: [(&str, u64); 6] = [
+        ("year", 365 * 24 * 60 * 60),
+        ("month", 30 * 24 * 60 * 60),
+        ("week", 7 * 24 * 60 * 60),
+        ("day", 24 * 60 * 60),
+        ("hour", 60 * 60),
+        ("minute", 60),
+    ];
+    let seconds = age.as_secs();
+    UNITS
+        .iter()
+        .find(|(_, length)| seconds >= *length)
+        .map(|(unit, length)| {
+            let count = seconds / length;
+            let plural = if count == 1 { "" } else { "s" };
+            format!("{} {}{} ago", count, unit, plural)
+        })
+        .unwrap_or_else(|| String::from("just now"))
+}
+
+/// `None` when the platform or filesystem does not record the time, any other failure to read
+/// it is an error.
+fn entry_time(
+    meta_data: &Metadata,
+    options: TimeOptions,
+) -> Result<Option<SystemTime>, FileEntryParsingError> {
+    let time = match options {
+        Created => meta_data.created(),
+        Modified => meta_data.modified(),
+    };
+    match time {
+        Ok(time) => Ok(Some(time)),
+        Err(error) if error.kind() == io::ErrorKind::Unsupported => Ok(None),
+        Err(error) => Err(FileEntryParsingError::MissingMetaDataError {
+            original_error: error.kind(),
+        }),
+    }
+}
+
+pub(crate) fn format_system_time(time: SystemTime, locale: &Locale) -> String {
+    format_date_with(time, &locale.date_format())
+}
+
+pub(crate) fn is_valid_time_format(time_format: &str) -> bool {
+    StrftimeItems::new(time_format).all(|item| item != Item::Error)
+}
+
+/// Times before 1970 are formatted like any other.
+fn format_date_with(time: SystemTime, date_format: &str) -> String {
+    DateTime::<Utc>::from(time).format(date_format).to_string()
+}
+
+pub(crate) fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["KiB", "MiB", "GiB", "TiB", "PiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+pub(crate) fn format_localised_size(bytes: u64, locale: &Locale) -> String {
+    locale.localise_decimal(&format_size(bytes))
 }
 
 fn format_each_entry(
-    dir_entries: &[DirEntry],
-    icon: &str,
+    dir_entries: &[(&FileEntry, &str)],
+    command: &FormattingCommand,
 ) -> Result<Vec<String>, FileEntryParsingError> {
-    Ok(dir_entries
+    dir_entries
         .iter()
-        .filter_map(|entry| convert_dir_entry_to_str(entry).ok())
-        .map(|file_name| icon.to_owned() + " " + &file_name)
-        .collect())
+        .map(|(entry, icon)| {
+            command.label_text(entry).map(|file_name| {
+                icon.to_string()
+                    + " "
+                    + &command.paint(entry, &file_name)
+                    + &command.row_marker(entry)
+            })
+        })
+        .collect()
 }
 
-fn convert_dir_entry_to_str(dir_entry: &DirEntry) -> Result<String, FileEntryParsingError> {
-    let file_name = dir_entry.file_name();
-    let normal_str = match file_name.to_str() {
-        Some(name) => name,
-        None => return Err(FileEntryParsingError::FileNameInvalidUnicode),
+/// Fills a grid with as many columns as fit in `width`, each column only as wide as its longest
+/// cell. `Down` fills each column before moving right like `ls -C`, `Across` fills rows like `ls -x`.
+/// Only column counts that could fit if every cell were as narrow as the narrowest are tried, so
+/// a huge directory costs a few dozen layouts rather than one per entry.
+fn arrange_in_grid(cells: &[String], width: usize, order: GridOrder) -> Vec<String> {
+    let widths: Vec<usize> = cells.iter().map(|cell| cell_width(cell)).collect();
+    let narrowest = widths.iter().min().copied().unwrap_or(0).max(1);
+    let most_columns = ((width + GRID_GAP) / (narrowest + GRID_GAP)).clamp(1, cells.len().max(1));
+    let layout = (1..=most_columns)
+        .rev()
+        .map(|columns| grid_column_widths(&widths, columns, order))
+        .find(|(_, column_widths)| {
+            column_widths.iter().sum::<usize>() + GRID_GAP * (column_widths.len() - 1) <= width
+        });
+    let Some((rows, column_widths)) = layout else {
+        return cells.to_vec();
     };
-    Ok(String::from(normal_str))
+    (0..rows)
+        .map(|row| {
+            let mut line = String::new();
+            for (column, column_width) in column_widths.iter().enumerate() {
+                let index = grid_index(row, column, rows, column_widths.len(), order);
+                let Some(cell) = cells.get(index) else {
+                    continue;
+                };
+                if column > 0 {
+                    line += " ".repeat(GRID_GAP).as_str();
+                }
+                line += cell;
+                line += " ".repeat(column_width - widths[index]).as_str();
+            }
+            line.trim_end().to_string()
+        })
+        .collect()
+}
+
+fn grid_column_widths(widths: &[usize], columns: usize, order: GridOrder) -> (usize, Vec<usize>) {
+    let rows = widths.len().div_ceil(columns).max(1);
+    let columns = match order {
+        GridOrder::Down => widths.len().div_ceil(rows).max(1),
+        GridOrder::Across => columns,
+    };
+    let column_widths = (0..columns)
+        .map(|column| {
+            (0..rows)
+                .map(|row| grid_index(row, column, rows, columns, order))
+                .filter_map(|index| widths.get(index))
+                .max()
+                .copied()
+                .unwrap_or(0)
+        })
+        .collect();
+    (rows, column_widths)
+}
+
+fn grid_index(row: usize, column: usize, rows: usize, columns: usize, order: GridOrder) -> usize {
+    match order {
+        GridOrder::Down => column * rows + row,
+        GridOrder::Across => row * columns + column,
+    }
+}
+
+/// The icons are a single grapheme but take up two terminal columns, colour codes take none.
+fn cell_width(cell: &str) -> usize {
+    color::strip(cell)
+        .graphemes(true)
+        .map(|grapheme| match grapheme {
+            FLOPPY | FOLDER | LINK => 2,
+            _ => 1,
+        })
+        .sum()
 }
 
 #[cfg(test)]
+#[allow(clippy::get_first, clippy::ptr_arg)]
 mod tests {
+    use crate::columns::{ColumnLimits, OptionalColumn, WidthLimit};
+    use crate::entries::FileEntry;
+    use crate::locale::Locale;
     use crate::output_formatting::{
-        generate_textual_display, FormattingCommand, FOLDER, RESERVED_LENGTH,
+        arrange_in_grid, format_age, format_size, format_system_time, generate_textual_display,
+        get_formatted_date, DirGrouping, FormattingCommand, GridOrder, PathStyle, TimeOptions,
+        FLOPPY, FOLDER, LINK, MIN_WIDTH, MISSING_DATE, RESERVED_LENGTH,
     };
     use crate::tests::calc_expected_date_string;
+    use crate::FileEntryParsingError;
+    use chrono::{DateTime, Utc};
     use std::fs;
     use std::fs::{DirEntry, File};
     use std::path::PathBuf;
+    use std::time::{Duration, UNIX_EPOCH};
     use tempfile::{tempdir, TempDir};
     use unicode_segmentation::UnicodeSegmentation;
 
     const FILE_1_NAME: &str = "file_1.txt";
     const FILE_2_NAME: &str = "file_2.txt";
 
+    fn listed(entries: Vec<DirEntry>) -> Vec<FileEntry> {
+        entries.iter().map(FileEntry::from_dir_entry).collect()
+    }
+
     fn setup_test() -> (TempDir, Vec<DirEntry>, Vec<DirEntry>) {
         let temp_dir = tempdir().unwrap();
         let file_1 = temp_dir.path().join(FILE_1_NAME);
@@ -262,7 +1018,7 @@ mod tests {
     #[test]
     fn non_extended_output_contains_header_row() {
         let (_tempdir, file_entries, directories) = setup_test();
-        let command = FormattingCommand::new(false, 200, file_entries, directories);
+        let command = FormattingCommand::new(false, 200, listed(file_entries), listed(directories));
         let content = generate_textual_display(command).unwrap();
         let lines_of_content = content.split('\n').collect::<Vec<&str>>();
         let header_row = lines_of_content.get(0).unwrap();
@@ -275,7 +1031,7 @@ mod tests {
     #[test]
     fn includes_folder_icon_for_sub_folders() {
         let (_tempdir, file_entries, directories) = setup_test();
-        let command = FormattingCommand::new(false, 100, file_entries, directories);
+        let command = FormattingCommand::new(false, 100, listed(file_entries), listed(directories));
         let content = generate_textual_display(command).unwrap();
         assert_eq!(
             content
@@ -289,7 +1045,7 @@ mod tests {
     #[test]
     fn contains_seperator_row() {
         let (_tempdir, file_entries, directories) = setup_test();
-        let command = FormattingCommand::new(false, 100, file_entries, directories);
+        let command = FormattingCommand::new(false, 100, listed(file_entries), listed(directories));
         let content = generate_textual_display(command).unwrap();
         let expected_row = "=".repeat(100);
         assert!(content.contains(&expected_row));
@@ -298,7 +1054,7 @@ mod tests {
     #[test]
     fn contains_a_header_for_extra_attributes_when_configured() {
         let (_tempdir, file_entries, directories) = setup_test();
-        let command = FormattingCommand::new(true, 100, file_entries, directories);
+        let command = FormattingCommand::new(true, 100, listed(file_entries), listed(directories));
         let content = generate_textual_display(command).unwrap();
         assert!(content.starts_with("Name"));
         assert!(content.contains("Date Created"));
@@ -309,7 +1065,7 @@ mod tests {
     #[test]
     fn does_not_contain_ext_attrs_headers_when_not_set() {
         let (_tempdir, file_entries, directories) = setup_test();
-        let command = FormattingCommand::new(false, 400, file_entries, directories);
+        let command = FormattingCommand::new(false, 400, listed(file_entries), listed(directories));
         let contents = generate_textual_display(command).unwrap();
         assert!(!contents.contains("Date Created"));
         assert!(!contents.contains("Date Modified"));
@@ -320,7 +1076,8 @@ mod tests {
     fn file_names_shortened_for_small_terminals_when_ext_attr_set() {
         let (_temp_dir, file_1_full_path, compressed_width, files, directories) =
             setup_long_name_test();
-        let command = FormattingCommand::new(true, compressed_width, files, directories);
+        let command =
+            FormattingCommand::new(true, compressed_width, listed(files), listed(directories));
         let contents = generate_textual_display(command).unwrap();
         let lines_of_content: Vec<&str> = contents.split('\n').collect();
         let first_file_line = lines_of_content.get(2).unwrap();
@@ -381,7 +1138,8 @@ mod tests {
     fn there_is_always_space_between_fields() {
         let (_temp_dir, _file_1_full_path, compressed_width, files, directories) =
             setup_long_name_test();
-        let command = FormattingCommand::new(true, compressed_width, files, directories);
+        let command =
+            FormattingCommand::new(true, compressed_width, listed(files), listed(directories));
         let contents = generate_textual_display(command).unwrap();
         let lines_of_content: Vec<&str> = contents.split('\n').collect();
         let first_file_line = lines_of_content.get(2).unwrap();
@@ -408,7 +1166,7 @@ mod tests {
         let (files, directories) = dir_read
             .filter_map(|entry| entry.ok())
             .partition(|entry| entry.metadata().unwrap().is_file());
-        let command = FormattingCommand::new(true, 200, files, directories);
+        let command = FormattingCommand::new(true, 200, listed(files), listed(directories));
         let contents = generate_textual_display(command).unwrap();
 
         let lines: Vec<&str> = contents.split('\n').collect();
@@ -451,7 +1209,12 @@ mod tests {
         let file_2_full_path = file_2.to_str().unwrap().to_string();
         let max_name_width = file_2_full_path.graphemes(true).count();
         let always_sufficient_length = max_name_width + 70; //so always file path is smaller that console
-        let command = FormattingCommand::new(true, always_sufficient_length, files, directories);
+        let command = FormattingCommand::new(
+            true,
+            always_sufficient_length,
+            listed(files),
+            listed(directories),
+        );
         let contents = generate_textual_display(command).unwrap();
 
         let contents_as_lines: Vec<&str> = contents.split('\n').collect();
@@ -478,6 +1241,354 @@ mod tests {
         );
     }
 
+    #[test]
+    #[cfg(unix)]
+    fn octal_mode_column_shows_permission_bits() {
+        use std::os::unix::fs::PermissionsExt;
+        let (temp_dir, files, directories) = setup_test();
+        let file_1 = temp_dir.path().join(FILE_1_NAME);
+        fs::set_permissions(&file_1, fs::Permissions::from_mode(0o4755)).unwrap();
+        let command = FormattingCommand::new(true, 200, listed(files), listed(directories))
+            .with_optional_columns(&[OptionalColumn::Octal]);
+        let contents = generate_textual_display(command).unwrap();
+        let lines: Vec<&str> = contents.split('\n').collect();
+        assert!(lines[0].contains("Permissions  Mode  Date Modified"));
+        let file_1_line = lines
+            .iter()
+            .find(|line| line.contains(FILE_1_NAME))
+            .unwrap();
+        assert!(file_1_line.contains("rwsr-xr-x    4755  "));
+    }
+
+    #[test]
+    fn mixed_grouping_sorts_files_and_directories_together() {
+        let temp_dir = tempdir().unwrap();
+        fs::create_dir(temp_dir.path().join("a")).unwrap();
+        File::create(temp_dir.path().join("b.txt")).unwrap();
+        fs::create_dir(temp_dir.path().join("c")).unwrap();
+        let (files, directories) = fs::read_dir(temp_dir.path())
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .partition(|entry| entry.metadata().unwrap().is_file());
+        let command = FormattingCommand::new(false, 200, listed(files), listed(directories))
+            .with_dir_grouping(DirGrouping::Mixed);
+        let contents = generate_textual_display(command).unwrap();
+        let rows: Vec<&str> = contents.lines().skip(2).collect();
+        assert_eq!(
+            rows,
+            vec![
+                format!("{} a", FOLDER),
+                format!("{} b.txt", FLOPPY),
+                format!("{} c", FOLDER),
+            ]
+        );
+    }
+
+    #[test]
+    fn first_grouping_lists_directories_before_files() {
+        let (_tempdir, files, directories) = setup_test();
+        let command = FormattingCommand::new(false, 200, listed(files), listed(directories))
+            .with_dir_grouping(DirGrouping::First);
+        let contents = generate_textual_display(command).unwrap();
+        assert!(contents.lines().nth(2).unwrap().starts_with(FOLDER));
+    }
+
+    #[test]
+    fn column_limits_cap_names_and_shorten_dates() {
+        let (temp_dir, files, directories) = setup_test();
+        let limits = ColumnLimits {
+            name: WidthLimit {
+                min: None,
+                max: Some(10),
+            },
+            created: WidthLimit {
+                min: None,
+                max: Some(16),
+            },
+            ..Default::default()
+        };
+        let command = FormattingCommand::new(true, 200, listed(files), listed(directories))
+            .with_column_limits(limits);
+        let contents = generate_textual_display(command).unwrap();
+        let header = contents.lines().next().unwrap();
+        assert!(header.starts_with(&format!("{:<14}Date Created     Permissions", "Name")));
+        let created = fs::metadata(temp_dir.path().join(FILE_1_NAME))
+            .unwrap()
+            .created()
+            .unwrap();
+        let expected_created = &calc_expected_date_string(&created)[..16];
+        let file_line = contents
+            .lines()
+            .find(|line| line.contains(expected_created))
+            .unwrap();
+        let name_column = file_line.split(expected_created).next().unwrap();
+        assert_eq!(name_column.graphemes(true).count(), 13);
+    }
+
+    #[test]
+    fn dates_the_filesystem_does_not_record_are_shown_as_missing() {
+        let (temp_dir, _, _) = setup_test();
+        let meta_data = fs::metadata(temp_dir.path().join(FILE_1_NAME)).unwrap();
+        let command = FormattingCommand::new(true, 200, Vec::new(), Vec::new());
+        let created = get_formatted_date(&meta_data, TimeOptions::Created, &command, 200).unwrap();
+        match meta_data.created() {
+            Ok(time) => assert_eq!(created, calc_expected_date_string(&time)),
+            Err(_) => assert_eq!(created, MISSING_DATE),
+        }
+        let path = temp_dir.path().join(FILE_1_NAME);
+        let in_1960 = UNIX_EPOCH - Duration::from_secs(10 * 365 * 24 * 60 * 60);
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(in_1960)
+            .unwrap();
+        let meta_data = fs::metadata(path).unwrap();
+        let modified = get_formatted_date(&meta_data, TimeOptions::Modified, &command, 200);
+        assert!(modified.unwrap().starts_with("1960-"));
+    }
+
+    #[test]
+    fn times_before_1970_are_formatted() {
+        let in_1960 = UNIX_EPOCH - Duration::from_secs(10 * 365 * 24 * 60 * 60);
+        assert!(format_system_time(in_1960, &Locale::default()).starts_with("1960-"));
+    }
+
+    #[test]
+    fn relative_time_shows_how_long_ago_entries_changed() {
+        assert_eq!(format_age(Duration::from_secs(59)), "just now");
+        assert_eq!(format_age(Duration::from_secs(60)), "1 minute ago");
+        assert_eq!(format_age(Duration::from_secs(3 * 60 + 5)), "3 minutes ago");
+        assert_eq!(
+            format_age(Duration::from_secs(2 * 24 * 60 * 60)),
+            "2 days ago"
+        );
+        assert_eq!(
+            format_age(Duration::from_secs(400 * 24 * 60 * 60)),
+            "1 year ago"
+        );
+        let (_tempdir, files, directories) = setup_test();
+        let command = FormattingCommand::new(true, 200, listed(files), listed(directories))
+            .with_relative_time();
+        let contents = generate_textual_display(command).unwrap();
+        let rows: Vec<&str> = contents.lines().skip(2).collect();
+        assert!(!rows.is_empty());
+        assert!(rows.iter().all(|row| row.contains("just now")));
+    }
+
+    #[test]
+    fn date_columns_are_as_wide_as_the_time_format() {
+        let (temp_dir, files, directories) = setup_test();
+        let command = FormattingCommand::new(true, 200, listed(files), listed(directories))
+            .with_time_format(String::from("%A %d %B %Y"));
+        let contents = generate_textual_display(command).unwrap();
+        let header = contents.lines().next().unwrap();
+        let created_heading = format!("{:<28}Permissions", "Date Created");
+        assert!(header.contains(&created_heading));
+        let modified = fs::metadata(temp_dir.path().join(FILE_1_NAME))
+            .unwrap()
+            .modified()
+            .unwrap();
+        let expected = DateTime::<Utc>::from(modified)
+            .format("%A %d %B %Y")
+            .to_string();
+        assert!(contents.contains(&expected));
+    }
+
+    #[test]
+    fn multibyte_names_and_time_formats_are_padded_by_display_width() {
+        let temp_dir = tempdir().unwrap();
+        let files: Vec<FileEntry> = ["\u{e9}".repeat(15), "\u{e9}".repeat(17)]
+            .iter()
+            .map(|name| {
+                let path = temp_dir.path().join(name);
+                File::create(&path).unwrap();
+                FileEntry::from_path(&path).unwrap()
+            })
+            .collect();
+        let command = FormattingCommand::new(true, 200, files, Vec::new())
+            .with_path_style(PathStyle::Name, temp_dir.path())
+            .with_time_format(String::from("\u{2192}\u{2192}\u{2192}\u{2192} %Y"));
+        let contents = generate_textual_display(command).unwrap();
+        let widths: Vec<usize> = contents
+            .lines()
+            .skip(2)
+            .map(|row| row.graphemes(true).count())
+            .collect();
+        assert_eq!(widths.len(), 2);
+        assert_eq!(widths[0], widths[1]);
+        assert!(contents.contains("\u{2192}\u{2192}\u{2192}\u{2192} "));
+    }
+
+    #[test]
+    fn two_line_layout_puts_details_under_each_name() {
+        let (temp_dir, files, directories) = setup_test();
+        let command = FormattingCommand::new(true, 200, listed(files), listed(directories))
+            .with_two_line_layout();
+        let contents = generate_textual_display(command).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(lines[0], "Name");
+        assert!(lines[1].starts_with("    Date Created"));
+        assert!(lines[1].ends_with("Size"));
+        let name_row = lines
+            .iter()
+            .position(|line| line.contains(FILE_1_NAME))
+            .unwrap();
+        let created = fs::metadata(temp_dir.path().join(FILE_1_NAME))
+            .unwrap()
+            .created()
+            .unwrap();
+        let details = lines[name_row + 1];
+        assert!(details.starts_with(&format!("    {}", calc_expected_date_string(&created))));
+        assert!(details.ends_with("0 B"));
+    }
+
+    #[test]
+    fn tiny_terminals_fall_back_to_two_lines() {
+        let (_temp_dir, files, directories) = setup_test();
+        let command = FormattingCommand::new(true, 30, listed(files), listed(directories));
+        let contents = generate_textual_display(command).unwrap();
+        assert!(contents.starts_with("Name\n    Date Created"));
+    }
+
+    #[test]
+    fn directories_include_extended_attributes() {
+        let (temp_dir, files, directories) = setup_test();
+        let command = FormattingCommand::new(true, 200, listed(files), listed(directories));
+        let contents = generate_textual_display(command).unwrap();
+        let dir_line = contents
+            .lines()
+            .find(|line| line.starts_with(FOLDER))
+            .unwrap();
+        let file_line = contents
+            .lines()
+            .find(|line| line.contains(FILE_1_NAME))
+            .unwrap();
+        let modified = fs::metadata(temp_dir.path().join("other"))
+            .unwrap()
+            .modified()
+            .unwrap();
+        assert!(dir_line.contains(&calc_expected_date_string(&modified)));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(temp_dir.path().join("other"))
+                .unwrap()
+                .permissions()
+                .mode();
+            assert!(dir_line.contains(&crate::columns::format_symbolic_mode(mode)));
+        }
+        #[cfg(not(unix))]
+        assert!(dir_line.contains("writable"));
+        assert_eq!(
+            dir_line.graphemes(true).count(),
+            file_line.graphemes(true).count()
+        );
+    }
+
+    #[test]
+    fn entries_without_metadata_are_rendered_as_placeholders() {
+        let (temp_dir, files, directories) = setup_test();
+        fs::remove_file(temp_dir.path().join(FILE_1_NAME)).unwrap();
+        let command = FormattingCommand::new(true, 200, listed(files), listed(directories));
+        let contents = generate_textual_display(command).unwrap();
+        let file_1_line = contents
+            .lines()
+            .find(|line| line.contains(FILE_1_NAME))
+            .unwrap();
+        let file_2_line = contents
+            .lines()
+            .find(|line| line.contains(FILE_2_NAME))
+            .unwrap();
+        assert_eq!(
+            file_1_line
+                .split_whitespace()
+                .skip(2)
+                .collect::<Vec<&str>>(),
+            vec!["?", "?", "?"]
+        );
+        assert_eq!(file_1_line.len(), file_2_line.len());
+    }
+
+    #[test]
+    fn grid_fills_down_or_across() {
+        let cells: Vec<String> = ["a", "bb", "c", "dd", "e"]
+            .iter()
+            .map(|name| name.to_string())
+            .collect();
+        assert_eq!(
+            arrange_in_grid(&cells, 8, GridOrder::Down),
+            vec!["a   dd", "bb  e", "c"]
+        );
+        assert_eq!(
+            arrange_in_grid(&cells, 8, GridOrder::Across),
+            vec!["a  bb", "c  dd", "e"]
+        );
+        assert_eq!(arrange_in_grid(&cells, 2, GridOrder::Across), cells);
+    }
+
+    #[test]
+    fn grid_of_many_narrow_cells_uses_every_column_that_fits() {
+        let cells = vec!["a".to_string(); 20_000];
+        let lines = arrange_in_grid(&cells, 80, GridOrder::Across);
+        assert_eq!(lines.len(), 20_000usize.div_ceil(27));
+        assert_eq!(lines[0].split_whitespace().count(), 27);
+    }
+
+    #[test]
+    fn header_is_repeated_every_n_entries() {
+        let (_tempdir, file_entries, directories) = setup_test();
+        let command = FormattingCommand::new(false, 40, listed(file_entries), listed(directories))
+            .with_repeated_header(2);
+        let content = generate_textual_display(command).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 7);
+        assert_eq!(lines[0], "Name:");
+        assert_eq!(lines[4], "Name:");
+        assert!(lines[6].starts_with(FOLDER));
+    }
+
+    #[test]
+    fn header_repeated_every_zero_entries_is_printed_once() {
+        let (_tempdir, file_entries, directories) = setup_test();
+        let command = FormattingCommand::new(false, 40, listed(file_entries), listed(directories))
+            .with_repeated_header(0);
+        let content = generate_textual_display(command).unwrap();
+        assert_eq!(content.matches("Name:").count(), 1);
+    }
+
+    #[test]
+    fn separator_is_placed_between_every_column() {
+        let (_tempdir, file_entries, directories) = setup_test();
+        let command = FormattingCommand::new(true, 200, listed(file_entries), listed(directories))
+            .with_optional_columns(&[OptionalColumn::Octal])
+            .with_column_separator(" | ");
+        let content = generate_textual_display(command).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert!(lines[0].starts_with("Name"));
+        assert_eq!(lines[0].matches(" | ").count(), 4);
+        let header_positions: Vec<usize> = lines[0].match_indices(" | ").map(|(i, _)| i).collect();
+        for row in &lines[2..] {
+            assert_eq!(row.matches(" | ").count(), 4);
+            let row_positions: Vec<usize> = row
+                .match_indices(" | ")
+                .map(|(i, _)| row[..i].graphemes(true).count() + 1)
+                .collect();
+            let expected: Vec<usize> = header_positions
+                .iter()
+                .map(|i| lines[0][..*i].graphemes(true).count())
+                .collect();
+            assert_eq!(row_positions, expected);
+        }
+    }
+
+    #[test]
+    fn sizes_are_rendered_in_binary_units() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(5 * 1024 * 1024 * 1024), "5.0 GiB");
+    }
+
     #[test]
     fn spaces_out_columns() {
         // let (_tempdir, file_entries, directories) = setup_test();
@@ -495,7 +1606,7 @@ mod tests {
         let (files, directories) = dir_read
             .filter_map(|entry| entry.ok())
             .partition(|entry| entry.metadata().unwrap().is_file());
-        let command = FormattingCommand::new(true, 100, files, directories);
+        let command = FormattingCommand::new(true, 100, listed(files), listed(directories));
         let contents = generate_textual_display(command).unwrap();
         // Date Created and Date Modified = 24 each, rest Name
         let expected_header = "Name                                    Date Created            Permissions  Date Modified           ";
@@ -503,4 +1614,126 @@ mod tests {
         let header = lines_of_content[0];
         assert_eq!(expected_header, header);
     }
+
+    #[test]
+    #[cfg(unix)]
+    fn symlinks_get_their_own_icon_and_show_the_target() {
+        let temp_dir = tempdir().unwrap();
+        File::create(temp_dir.path().join(FILE_1_NAME)).unwrap();
+        std::os::unix::fs::symlink(FILE_1_NAME, temp_dir.path().join("link")).unwrap();
+        std::os::unix::fs::symlink("missing", temp_dir.path().join("dangling")).unwrap();
+        let entries = || -> Vec<DirEntry> {
+            fs::read_dir(temp_dir.path())
+                .unwrap()
+                .map(|entry| entry.unwrap())
+                .collect()
+        };
+        let command = FormattingCommand::new(false, 200, listed(entries()), Vec::new());
+        let contents = generate_textual_display(command).unwrap();
+        assert!(contents.contains(&format!("{} link", LINK)));
+        assert!(contents.contains(&format!("{} dangling", LINK)));
+        assert!(contents.contains(&format!("{} {}", FLOPPY, FILE_1_NAME)));
+        assert!(!contents.contains("->"));
+        let command = FormattingCommand::new(true, 200, listed(entries()), Vec::new())
+            .with_path_style(PathStyle::Name, temp_dir.path());
+        let contents = generate_textual_display(command).unwrap();
+        assert!(contents.contains(&format!("link -> {}", FILE_1_NAME)));
+        assert!(contents.contains("dangling -> missing"));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn dereferencing_describes_the_target_and_flags_dangling_links() {
+        use std::os::unix::fs::PermissionsExt;
+        let temp_dir = tempdir().unwrap();
+        let target = temp_dir.path().join(FILE_1_NAME);
+        File::create(&target).unwrap();
+        fs::set_permissions(&target, fs::Permissions::from_mode(0o600)).unwrap();
+        std::os::unix::fs::symlink(FILE_1_NAME, temp_dir.path().join("link")).unwrap();
+        std::os::unix::fs::symlink("missing", temp_dir.path().join("dangling")).unwrap();
+        let entries: Vec<DirEntry> = fs::read_dir(temp_dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap())
+            .collect();
+        let command = FormattingCommand::new(true, 200, listed(entries), Vec::new())
+            .with_path_style(PathStyle::Name, temp_dir.path())
+            .with_dereference();
+        let contents = generate_textual_display(command).unwrap();
+        let link_line = contents
+            .lines()
+            .find(|line| line.contains("link") && !line.contains("dangling"))
+            .unwrap();
+        assert!(link_line.starts_with(FLOPPY));
+        assert!(link_line.contains("rw-------"));
+        assert!(!link_line.contains("->"));
+        let dangling_line = contents
+            .lines()
+            .find(|line| line.contains("dangling"))
+            .unwrap();
+        assert!(dangling_line.starts_with(LINK));
+        assert!(dangling_line.contains('?'));
+        assert!(dangling_line.ends_with("  [broken link]"));
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn names_that_are_not_utf8_are_shown_lossily_unless_strict() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+        let temp_dir = tempdir().unwrap();
+        File::create(temp_dir.path().join(OsStr::from_bytes(b"bad\xffname"))).unwrap();
+        File::create(temp_dir.path().join("good")).unwrap();
+        let entries = || -> Vec<DirEntry> {
+            fs::read_dir(temp_dir.path())
+                .unwrap()
+                .map(|entry| entry.unwrap())
+                .collect()
+        };
+        for extended in [false, true] {
+            let command = FormattingCommand::new(extended, 200, listed(entries()), Vec::new())
+                .with_path_style(PathStyle::Name, temp_dir.path());
+            let contents = generate_textual_display(command).unwrap();
+            let row = contents
+                .lines()
+                .find(|line| line.contains("bad\u{FFFD}name"))
+                .unwrap();
+            assert!(row.ends_with("[not UTF-8]"));
+            assert!(!contents
+                .lines()
+                .find(|line| line.contains("good"))
+                .unwrap()
+                .contains("[not UTF-8]"));
+            let command = FormattingCommand::new(extended, 200, listed(entries()), Vec::new())
+                .with_strict_utf8();
+            assert!(matches!(
+                generate_textual_display(command),
+                Err(FileEntryParsingError::FileNameInvalidUnicode)
+            ));
+        }
+    }
+
+    #[test]
+    fn narrow_terminals_are_an_error_rather_than_a_panic() {
+        let (_temp_dir, files, directories) = setup_test();
+        let command = FormattingCommand::new(true, 10, listed(files), listed(directories));
+        assert!(matches!(
+            generate_textual_display(command),
+            Err(FileEntryParsingError::TerminalTooNarrow {
+                required: MIN_WIDTH,
+                actual: 10
+            })
+        ));
+    }
+
+    #[test]
+    fn bare_names_have_no_icons_or_header() {
+        let (_temp_dir, files, directories) = setup_test();
+        let command = FormattingCommand::new(false, 200, listed(files), listed(directories))
+            .with_dir_grouping(DirGrouping::Mixed)
+            .with_bare_names();
+        let contents = generate_textual_display(command).unwrap();
+        assert_eq!(contents, "file_1.txt\nfile_2.txt\nother");
+        let command = FormattingCommand::new(false, 200, Vec::new(), Vec::new()).with_bare_names();
+        assert_eq!(generate_textual_display(command).unwrap(), "");
+    }
 }
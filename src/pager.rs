@@ -0,0 +1,96 @@
+use crate::hooks;
+use std::env;
+use std::io::{self, ErrorKind, Write};
+use std::process::{Command, Stdio};
+
+const PAGER_VARIABLE: &str = "PAGER";
+
+/// Whether `contents` needs scrolling on a terminal `height` rows tall, the last row is kept for
+/// the shell prompt.
+fn is_taller_than(contents: &[u8], height: usize) -> bool {
+    contents.iter().filter(|byte| **byte == b'\n').count() >= height
+}
+
+/// The pager the user asked for in `$PAGER`, if any.
+pub(crate) fn configured() -> Option<String> {
+    env::var(PAGER_VARIABLE).ok()
+}
+
+/// `pager` run through the shell, or `less -R` so colours survive when none is configured. An
+/// empty `pager` turns paging off.
+fn pager_command(pager: Option<&str>) -> Option<Command> {
+    match pager {
+        Some(pager) if pager.trim().is_empty() => None,
+        Some(pager) => Some(hooks::shell(pager)),
+        None => {
+            let mut less = Command::new("less");
+            less.arg("-R");
+            Some(less)
+        }
+    }
+}
+
+/// Pipes `contents` through `pager`, as `configured` returns it, when it does not fit in `height`
+/// rows, otherwise or when there is no pager to start it is written to `out` as it is.
+pub(crate) fn page(
+    contents: &[u8],
+    height: Option<usize>,
+    pager: Option<&str>,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    let command = match height {
+        Some(height) if is_taller_than(contents, height) => pager_command(pager),
+        _ => None,
+    };
+    let Some(mut command) = command else {
+        return out.write_all(contents);
+    };
+    let mut pager = match command.stdin(Stdio::piped()).spawn() {
+        Ok(pager) => pager,
+        Err(error) if error.kind() == ErrorKind::NotFound => return out.write_all(contents),
+        Err(error) => return Err(error),
+    };
+    let written = match pager.stdin.take() {
+        // quitting the pager before the end closes the pipe, that is not a failure
+        Some(mut stdin) => match stdin.write_all(contents) {
+            Err(error) if error.kind() == ErrorKind::BrokenPipe => Ok(()),
+            written => written,
+        },
+        None => Ok(()),
+    };
+    pager.wait()?;
+    written
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn only_listings_taller_than_the_terminal_are_paged() {
+        assert!(!is_taller_than(b"a\nb\n", 3));
+        assert!(is_taller_than(b"a\nb\nc\n", 3));
+        let mut out = Vec::new();
+        page(b"a\nb\nc\n", None, None, &mut out).unwrap();
+        assert_eq!(out, b"a\nb\nc\n");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn long_listings_go_through_the_pager_variable() {
+        let temp_dir = tempdir().unwrap();
+        let paged = temp_dir.path().join("paged.txt");
+        let pager = format!("cat > {}", paged.display());
+        let mut out = Vec::new();
+        page(b"a\nb\n", Some(10), Some(&pager), &mut out).unwrap();
+        assert_eq!(out, b"a\nb\n");
+        out.clear();
+        page(b"a\nb\nc\n", Some(2), Some(&pager), &mut out).unwrap();
+        assert!(out.is_empty());
+        assert_eq!(fs::read_to_string(paged).unwrap(), "a\nb\nc\n");
+        page(b"a\nb\nc\n", Some(2), Some(" "), &mut out).unwrap();
+        assert_eq!(out, b"a\nb\nc\n");
+    }
+}
@@ -0,0 +1,167 @@
+use crate::entries::FileEntry;
+use crate::export::millis_since_epoch;
+use crate::machine_output::SCHEMA_VERSION;
+use crate::FileEntryParsingError;
+use parquet::data_type::{BoolType, ByteArray, ByteArrayType, Int32Type, Int64Type};
+use parquet::errors::ParquetError;
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::SerializedFileWriter;
+use parquet::format::KeyValue;
+use parquet::schema::parser::parse_message_type;
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+
+const PARQUET_SCHEMA: &str = "
+message listing {
+    REQUIRED BYTE_ARRAY name (UTF8);
+    REQUIRED BYTE_ARRAY path (UTF8);
+    REQUIRED BYTE_ARRAY kind (UTF8);
+    OPTIONAL INT64 size;
+    OPTIONAL INT64 created (TIMESTAMP(MILLIS,true));
+    OPTIONAL INT64 modified (TIMESTAMP(MILLIS,true));
+    OPTIONAL BOOLEAN readonly;
+    OPTIONAL INT32 mode;
+}
+";
+
+pub(crate) fn export(entries: &[FileEntry], target: &Path) -> Result<(), FileEntryParsingError> {
+    write_entries(entries, target).map_err(|error| FileEntryParsingError::ExportFailed {
+        target: target.to_string_lossy().to_string(),
+        reason: error.to_string(),
+    })
+}
+
+fn write_entries(entries: &[FileEntry], target: &Path) -> Result<(), ParquetError> {
+    let schema = Arc::new(parse_message_type(PARQUET_SCHEMA)?);
+    let properties = WriterProperties::builder()
+        .set_key_value_metadata(Some(vec![KeyValue::new(
+            String::from("schema_version"),
+            SCHEMA_VERSION.to_string(),
+        )]))
+        .build();
+    let file = File::create(target)?;
+    let mut writer = SerializedFileWriter::new(file, schema, Arc::new(properties))?;
+    let mut row_group = writer.next_row_group()?;
+    let mut column_index = 0;
+    while let Some(mut column) = row_group.next_column()? {
+        match column_index {
+            0 => write_strings(&mut column, entries.iter().map(|entry| entry.name.clone()))?,
+            1 => write_strings(
+                &mut column,
+                entries
+                    .iter()
+                    .map(|entry| entry.path.to_string_lossy().to_string()),
+            )?,
+            2 => write_strings(
+                &mut column,
+                entries.iter().map(|entry| entry.kind.as_str().to_string()),
+            )?,
+            3 => write_optional::<Int64Type>(
+                &mut column,
+                entries
+                    .iter()
+                    .map(|entry| entry.size.map(|size| size as i64)),
+            )?,
+            4 => write_optional::<Int64Type>(
+                &mut column,
+                entries
+                    .iter()
+                    .map(|entry| entry.created.map(millis_since_epoch)),
+            )?,
+            5 => write_optional::<Int64Type>(
+                &mut column,
+                entries
+                    .iter()
+                    .map(|entry| entry.modified.map(millis_since_epoch)),
+            )?,
+            6 => {
+                write_optional::<BoolType>(&mut column, entries.iter().map(|entry| entry.readonly))?
+            }
+            _ => write_optional::<Int32Type>(
+                &mut column,
+                entries
+                    .iter()
+                    .map(|entry| entry.mode.map(|mode| mode as i32)),
+            )?,
+        };
+        column.close()?;
+        column_index += 1;
+    }
+    row_group.close()?;
+    writer.close()?;
+    Ok(())
+}
+
+type ColumnWriter<'a> = parquet::file::writer::SerializedColumnWriter<'a>;
+
+fn write_strings(
+    column: &mut ColumnWriter,
+    values: impl Iterator<Item = String>,
+) -> Result<(), ParquetError> {
+    let values: Vec<ByteArray> = values
+        .map(|value| ByteArray::from(value.as_str()))
+        .collect();
+    column
+        .typed::<ByteArrayType>()
+        .write_batch(&values, None, None)?;
+    Ok(())
+}
+
+fn write_optional<T>(
+    column: &mut ColumnWriter,
+    values: impl Iterator<Item = Option<T::T>>,
+) -> Result<(), ParquetError>
+where
+    T: parquet::data_type::DataType,
+{
+    let mut present = Vec::new();
+    let mut definition_levels = Vec::new();
+    for value in values {
+        match value {
+            Some(value) => {
+                present.push(value);
+                definition_levels.push(1);
+            }
+            None => definition_levels.push(0),
+        }
+    }
+    column
+        .typed::<T>()
+        .write_batch(&present, Some(&definition_levels), None)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn exported_file_contains_a_row_per_entry_and_the_schema_version() {
+        let temp_dir = tempdir().unwrap();
+        fs::write(temp_dir.path().join("file_1.txt"), "").unwrap();
+        fs::create_dir(temp_dir.path().join("other")).unwrap();
+        let entries: Vec<FileEntry> = fs::read_dir(temp_dir.path())
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .map(|entry| FileEntry::from_dir_entry(&entry))
+            .collect();
+        let output = temp_dir.path().join("listing.parquet");
+
+        export(&entries, &output).unwrap();
+
+        let reader = SerializedFileReader::new(File::open(&output).unwrap()).unwrap();
+        let file_metadata = reader.metadata().file_metadata();
+        assert_eq!(file_metadata.num_rows(), 2);
+        let schema_version = file_metadata
+            .key_value_metadata()
+            .unwrap()
+            .iter()
+            .find(|pair| pair.key == "schema_version")
+            .and_then(|pair| pair.value.clone());
+        assert_eq!(schema_version, Some(SCHEMA_VERSION.to_string()));
+    }
+}
@@ -0,0 +1,321 @@
+use std::collections::BTreeSet;
+use std::io::{self, ErrorKind};
+
+const PROMPT: &str = "> ";
+const MAX_VISIBLE: usize = 15;
+
+/// Scores `candidate` against `query` when every query character appears in order, ignoring
+/// case. Lower is better: matches spread over fewer characters and starting earlier win.
+fn fuzzy_score(query: &str, candidate: &str) -> Option<usize> {
+    let candidate: Vec<char> = candidate.to_lowercase().chars().collect();
+    let mut start = None;
+    let mut position = 0;
+    for wanted in query.to_lowercase().chars() {
+        let offset = candidate[position..]
+            .iter()
+            .position(|letter| *letter == wanted)?;
+        start.get_or_insert(position + offset);
+        position += offset + 1;
+    }
+    let start = start.unwrap_or(0);
+    Some((position - start) * candidate.len() + start)
+}
+
+/// Indices of the candidates matching `query`, best first, ties kept in listing order.
+fn filter(query: &str, candidates: &[String]) -> Vec<usize> {
+    let mut matches: Vec<(usize, usize)> = candidates
+        .iter()
+        .enumerate()
+        .filter_map(|(i, candidate)| fuzzy_score(query, candidate).map(|score| (score, i)))
+        .collect();
+    matches.sort();
+    matches.into_iter().map(|(_, i)| i).collect()
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Key {
+    Char(char),
+    Backspace,
+    ClearLine,
+    Up,
+    Down,
+    Toggle,
+    Enter,
+    Cancel,
+}
+
+/// Picker state kept apart from the terminal so the key handling can be tested.
+struct Picker<'a> {
+    candidates: &'a [String],
+    query: String,
+    matches: Vec<usize>,
+    cursor: usize,
+    selected: BTreeSet<usize>,
+}
+
+impl<'a> Picker<'a> {
+    fn new(candidates: &'a [String]) -> Self {
+        Picker {
+            candidates,
+            query: String::new(),
+            matches: (0..candidates.len()).collect(),
+            cursor: 0,
+            selected: BTreeSet::new(),
+        }
+    }
+
+    /// Applies a key press, returning the picked indices once the selection is finished. Enter
+    /// picks the toggled entries in listing order, or the one under the cursor if none are.
+    fn press(&mut self, key: Key) -> Option<Option<Vec<usize>>> {
+        match key {
+            Key::Char(letter) => self.set_query(self.query.clone() + &letter.to_string()),
+            Key::Backspace => {
+                let mut query = self.query.clone();
+                query.pop();
+                self.set_query(query);
+            }
+            Key::ClearLine => self.set_query(String::new()),
+            Key::Up => self.cursor = self.cursor.saturating_sub(1),
+            Key::Down => {
+                if self.cursor + 1 < self.matches.len() {
+                    self.cursor += 1;
+                }
+            }
+            Key::Toggle => {
+                if let Some(index) = self.matches.get(self.cursor) {
+                    if !self.selected.remove(index) {
+                        self.selected.insert(*index);
+                    }
+                }
+                if self.cursor + 1 < self.matches.len() {
+                    self.cursor += 1;
+                }
+            }
+            Key::Enter if !self.selected.is_empty() => {
+                return Some(Some(self.selected.iter().copied().collect()))
+            }
+            Key::Enter => return Some(self.matches.get(self.cursor).map(|index| vec![*index])),
+            Key::Cancel => return Some(None),
+        }
+        None
+    }
+
+    fn set_query(&mut self, query: String) {
+        self.matches = filter(&query, self.candidates);
+        self.query = query;
+        self.cursor = 0;
+    }
+
+    fn frame(&self, width: usize) -> Vec<String> {
+        let first = self.cursor.saturating_sub(MAX_VISIBLE - 1);
+        let selected = match self.selected.len() {
+            0 => String::new(),
+            count => format!(", {} selected", count),
+        };
+        let mut lines = vec![format!(
+            "{}{}  ({}/{}{})",
+            PROMPT,
+            self.query,
+            self.matches.len(),
+            self.candidates.len(),
+            selected
+        )];
+        for (row, index) in self
+            .matches
+            .iter()
+            .enumerate()
+            .skip(first)
+            .take(MAX_VISIBLE)
+        {
+            let marker = if row == self.cursor { ">" } else { " " };
+            let toggled = if self.selected.contains(index) {
+                "*"
+            } else {
+                " "
+            };
+            let line: String = format!("{}{} {}", marker, toggled, self.candidates[*index])
+                .chars()
+                .take(width.saturating_sub(1))
+                .collect();
+            lines.push(line);
+        }
+        lines
+    }
+}
+
+/// Opens a fuzzy filter over `candidates` on the controlling terminal, so stdout stays free for
+/// the result even inside `$(...)`. Returns the indices picked or `Interrupted` when cancelled.
+pub(crate) fn pick(candidates: &[String]) -> io::Result<Vec<usize>> {
+    terminal::run(Picker::new(candidates))?
+        .ok_or_else(|| io::Error::new(ErrorKind::Interrupted, "nothing was picked"))
+}
+
+#[cfg(unix)]
+mod terminal {
+    use super::{Key, Picker};
+    use std::fs::{File, OpenOptions};
+    use std::io::{self, Read, Write};
+    use std::os::unix::io::AsRawFd;
+
+    const ESCAPE: u8 = 0x1b;
+
+    /// Puts the terminal in non-canonical mode without echo and restores it when dropped.
+    struct RawMode {
+        tty: File,
+        original: libc::termios,
+    }
+
+    impl RawMode {
+        fn enable(tty: File) -> io::Result<RawMode> {
+            let mut original: libc::termios = unsafe { std::mem::zeroed() };
+            if unsafe { libc::tcgetattr(tty.as_raw_fd(), &mut original) } != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let mut raw = original;
+            raw.c_lflag &= !(libc::ICANON | libc::ECHO | libc::ISIG);
+            // wake up every 100ms so a lone Escape can be told apart from an arrow key
+            raw.c_cc[libc::VMIN] = 0;
+            raw.c_cc[libc::VTIME] = 1;
+            if unsafe { libc::tcsetattr(tty.as_raw_fd(), libc::TCSANOW, &raw) } != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(RawMode { tty, original })
+        }
+
+        fn read_byte(&mut self) -> io::Result<Option<u8>> {
+            let mut byte = [0u8; 1];
+            match self.tty.read(&mut byte)? {
+                0 => Ok(None),
+                _ => Ok(Some(byte[0])),
+            }
+        }
+
+        fn read_key(&mut self) -> io::Result<Key> {
+            let mut pending = Vec::new();
+            loop {
+                let Some(byte) = self.read_byte()? else {
+                    continue;
+                };
+                let key = match byte {
+                    b'\r' | b'\n' => Key::Enter,
+                    0x7f | 0x08 => Key::Backspace,
+                    0x15 => Key::ClearLine,
+                    0x10 => Key::Up,
+                    0x0e => Key::Down,
+                    0x03 | 0x07 => Key::Cancel,
+                    b' ' => Key::Toggle,
+                    ESCAPE => match (self.read_byte()?, self.read_byte()?) {
+                        (None, _) => Key::Cancel,
+                        (Some(b'[' | b'O'), Some(b'A')) => Key::Up,
+                        (Some(b'[' | b'O'), Some(b'B')) => Key::Down,
+                        _ => continue,
+                    },
+                    byte if byte < 0x20 => continue,
+                    byte => {
+                        pending.push(byte);
+                        match std::str::from_utf8(&pending) {
+                            Ok(text) => Key::Char(text.chars().next().unwrap_or(' ')),
+                            Err(error) if error.error_len().is_none() => continue,
+                            Err(_) => {
+                                pending.clear();
+                                continue;
+                            }
+                        }
+                    }
+                };
+                return Ok(key);
+            }
+        }
+    }
+
+    impl Drop for RawMode {
+        fn drop(&mut self) {
+            unsafe { libc::tcsetattr(self.tty.as_raw_fd(), libc::TCSANOW, &self.original) };
+        }
+    }
+
+    pub(super) fn run(mut picker: Picker) -> io::Result<Option<Vec<usize>>> {
+        let tty = OpenOptions::new().read(true).write(true).open("/dev/tty")?;
+        let mut screen = tty.try_clone()?;
+        let mut raw = RawMode::enable(tty)?;
+        let width = term_size::dimensions().map_or(80, |(width, _)| width);
+        loop {
+            let lines = picker.frame(width);
+            write!(screen, "\r\x1b[J{}", lines.join("\r\n"))?;
+            // park the cursor at the end of the prompt line
+            if lines.len() > 1 {
+                write!(screen, "\x1b[{}A", lines.len() - 1)?;
+            }
+            write!(screen, "\r\x1b[{}C", lines[0].chars().count())?;
+            screen.flush()?;
+            if let Some(picked) = picker.press(raw.read_key()?) {
+                write!(screen, "\r\x1b[J")?;
+                screen.flush()?;
+                return Ok(picked);
+            }
+        }
+    }
+}
+
+#[cfg(not(unix))]
+mod terminal {
+    use super::Picker;
+    use std::io::{self, ErrorKind};
+
+    pub(super) fn run(_picker: Picker) -> io::Result<Option<Vec<usize>>> {
+        Err(io::Error::new(
+            ErrorKind::Unsupported,
+            "--pick needs a Unix terminal",
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuzzy_matches_prefer_tight_early_matches() {
+        let candidates: Vec<String> = ["src/main.rs", "README.md", "src/arg_processing.rs"]
+            .map(String::from)
+            .to_vec();
+        assert_eq!(filter("main", &candidates), vec![0]);
+        assert_eq!(filter("rs", &candidates), vec![0, 2]);
+        assert_eq!(filter("RdMe", &candidates), vec![1]);
+        assert!(filter("xyz", &candidates).is_empty());
+        assert_eq!(filter("", &candidates), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn keys_narrow_move_and_pick() {
+        let candidates: Vec<String> = ["alpha", "beta", "gamma"].map(String::from).to_vec();
+        let mut picker = Picker::new(&candidates);
+        assert_eq!(picker.press(Key::Char('a')), None);
+        assert_eq!(picker.matches.len(), 3);
+        assert_eq!(picker.press(Key::Char('m')), None);
+        assert_eq!(picker.matches, vec![2]);
+        picker.press(Key::Backspace);
+        picker.press(Key::Down);
+        picker.press(Key::Down);
+        assert_eq!(
+            picker.press(Key::Enter),
+            Some(Some(vec![picker.matches[2]]))
+        );
+        assert_eq!(picker.press(Key::Cancel), Some(None));
+        assert!(picker.frame(80)[0].starts_with("> a  (3/3)"));
+    }
+
+    #[test]
+    fn space_toggles_entries_for_a_multi_pick() {
+        let candidates: Vec<String> = ["alpha", "beta", "gamma"].map(String::from).to_vec();
+        let mut picker = Picker::new(&candidates);
+        picker.press(Key::Down);
+        picker.press(Key::Toggle);
+        picker.press(Key::Toggle);
+        assert_eq!(picker.cursor, 2);
+        assert!(picker.frame(80)[0].ends_with("(3/3, 2 selected)"));
+        picker.press(Key::Up);
+        picker.press(Key::Toggle);
+        assert_eq!(picker.press(Key::Enter), Some(Some(vec![2])));
+    }
+}
@@ -0,0 +1,858 @@
+use crate::checksum::{manifest_line, normalise_path, read_manifest, sha256_file};
+use crate::locale::Locale;
+use crate::output_formatting::{format_localised_size, format_system_time};
+use crate::search::NamePattern;
+use crate::walker::{walk, walk_to_depth, WalkedEntry};
+use crate::FileEntryParsingError;
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap, HashMap};
+use std::fs::FileType;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Report {
+    Largest(usize),
+    Oldest(usize),
+    Newest(usize),
+    ByExtension,
+    Histogram,
+    Stats,
+    Verify(PathBuf),
+    Checksums,
+    Collisions,
+}
+
+pub(crate) fn generate_report(
+    report: &Report,
+    root: &Path,
+    exclude: &[NamePattern],
+    locale: &Locale,
+) -> Result<String, FileEntryParsingError> {
+    match report {
+        Report::Largest(count) => largest_files(root, exclude, *count, locale),
+        Report::Oldest(count) => entries_by_age(root, exclude, *count, true, locale),
+        Report::Newest(count) => entries_by_age(root, exclude, *count, false, locale),
+        Report::ByExtension => usage_by_extension(root, exclude, locale),
+        Report::Histogram => size_histogram(root, exclude),
+        Report::Stats => tree_stats(root, exclude, locale),
+        Report::Verify(manifest) => verify_manifest(root, exclude, manifest),
+        Report::Checksums => checksum_manifest(root, exclude),
+        Report::Collisions => name_collisions(root, exclude),
+    }
+}
+
+fn largest_files(
+    root: &Path,
+    exclude: &[NamePattern],
+    count: usize,
+    locale: &Locale,
+) -> Result<String, FileEntryParsingError> {
+    let largest = top_entries(root, exclude, count, |entry| {
+        entry.metadata.is_file().then_some(entry.metadata.len())
+    })?;
+    let mut lines = report_header(format!("Largest {} files in {}:", count, root.display()));
+    lines.extend(largest.into_iter().map(|(size, path)| {
+        format!(
+            "{:>10}  {}",
+            format_localised_size(size, locale),
+            path.display()
+        )
+    }));
+    Ok(lines.join("\n"))
+}
+
+fn entries_by_age(
+    root: &Path,
+    exclude: &[NamePattern],
+    count: usize,
+    oldest: bool,
+    locale: &Locale,
+) -> Result<String, FileEntryParsingError> {
+    let modified = |entry: &WalkedEntry| entry.metadata.modified().ok();
+    let (title, entries) = if oldest {
+        let entries = top_entries(root, exclude, count, |entry| modified(entry).map(Reverse))?;
+        let entries = entries
+            .into_iter()
+            .map(|(Reverse(time), path)| (time, path));
+        ("Oldest", entries.collect::<Vec<(SystemTime, PathBuf)>>())
+    } else {
+        ("Newest", top_entries(root, exclude, count, modified)?)
+    };
+    let mut lines = report_header(format!(
+        "{} {} entries in {}:",
+        title,
+        count,
+        root.display()
+    ));
+    lines.extend(
+        entries.into_iter().map(|(time, path)| {
+            format!("{}  {}", format_system_time(time, locale), path.display())
+        }),
+    );
+    Ok(lines.join("\n"))
+}
+
+fn usage_by_extension(
+    root: &Path,
+    exclude: &[NamePattern],
+    locale: &Locale,
+) -> Result<String, FileEntryParsingError> {
+    let mut usage: HashMap<String, (u64, usize)> = HashMap::new();
+    walk(root, exclude, &mut |entry: WalkedEntry| {
+        if !entry.metadata.is_file() || entry.pseudo {
+            return;
+        }
+        let extension = entry
+            .path
+            .extension()
+            .map(|extension| format!(".{}", extension.to_string_lossy().to_lowercase()))
+            .unwrap_or_else(|| String::from("(none)"));
+        let (size, count) = usage.entry(extension).or_insert((0, 0));
+        *size += entry.metadata.len();
+        *count += 1;
+    })?;
+    let total_size: u64 = usage.values().map(|(size, _)| size).sum();
+    let mut rows: Vec<(String, (u64, usize))> = usage.into_iter().collect();
+    rows.sort_by(|(a_ext, (a_size, _)), (b_ext, (b_size, _))| {
+        b_size.cmp(a_size).then_with(|| a_ext.cmp(b_ext))
+    });
+    let mut lines = report_header(format!("Disk usage by extension in {}:", root.display()));
+    lines.push(format!(
+        "{:<16}{:>10}{:>14}{:>9}",
+        "Extension", "Files", "Total Size", "Share"
+    ));
+    lines.extend(rows.into_iter().map(|(extension, (size, count))| {
+        let share = if total_size == 0 {
+            0.0
+        } else {
+            size as f64 * 100.0 / total_size as f64
+        };
+        format!(
+            "{:<16}{:>10}{:>14}{:>9}",
+            extension,
+            count,
+            format_localised_size(size, locale),
+            locale.localise_decimal(&format!("{:.1}%", share))
+        )
+    }));
+    Ok(lines.join("\n"))
+}
+
+const HISTOGRAM_BUCKETS: [(u64, &str); 8] = [
+    (1 << 10, "< 1 KiB"),
+    (10 << 10, "1-10 KiB"),
+    (100 << 10, "10-100 KiB"),
+    (1 << 20, "100 KiB-1 MiB"),
+    (10 << 20, "1-10 MiB"),
+    (100 << 20, "10-100 MiB"),
+    (1 << 30, "100 MiB-1 GiB"),
+    (u64::MAX, ">= 1 GiB"),
+];
+const HISTOGRAM_BAR_WIDTH: usize = 40;
+
+fn size_histogram(root: &Path, exclude: &[NamePattern]) -> Result<String, FileEntryParsingError> {
+    let mut counts = [0usize; HISTOGRAM_BUCKETS.len()];
+    walk(root, exclude, &mut |entry: WalkedEntry| {
+        if !entry.metadata.is_file() || entry.pseudo {
+            return;
+        }
+        let size = entry.metadata.len();
+        let bucket = HISTOGRAM_BUCKETS
+            .iter()
+            .position(|(upper_bound, _)| size < *upper_bound)
+            .unwrap_or(HISTOGRAM_BUCKETS.len() - 1);
+        counts[bucket] += 1;
+    })?;
+    let most = counts.iter().copied().max().unwrap_or(0);
+    let mut lines = report_header(format!("File size distribution in {}:", root.display()));
+    lines.extend(
+        HISTOGRAM_BUCKETS
+            .iter()
+            .zip(counts)
+            .map(|((_, label), count)| {
+                let bar_length = if most == 0 {
+                    0
+                } else {
+                    (count * HISTOGRAM_BAR_WIDTH).div_ceil(most)
+                };
+                format!("{:<14}{:>8}  {}", label, count, "#".repeat(bar_length))
+                    .trim_end()
+                    .to_string()
+            }),
+    );
+    Ok(lines.join("\n"))
+}
+
+#[derive(Default)]
+struct TreeStats {
+    files: usize,
+    directories: usize,
+    symlinks: usize,
+    total_size: u64,
+    deepest: Option<(usize, PathBuf)>,
+    longest_name: Option<(usize, PathBuf)>,
+}
+
+fn tree_stats(
+    root: &Path,
+    exclude: &[NamePattern],
+    locale: &Locale,
+) -> Result<String, FileEntryParsingError> {
+    let mut stats = TreeStats::default();
+    walk(root, exclude, &mut |entry: WalkedEntry| {
+        let file_type = entry.metadata.file_type();
+        if file_type.is_symlink() {
+            stats.symlinks += 1;
+        } else if file_type.is_dir() {
+            stats.directories += 1;
+        } else if file_type.is_file() {
+            stats.files += 1;
+            stats.total_size += entry.metadata.len();
+        }
+        if stats
+            .deepest
+            .as_ref()
+            .is_none_or(|(depth, _)| entry.depth > *depth)
+        {
+            stats.deepest = Some((entry.depth, entry.path.clone()));
+        }
+        let name_length = entry
+            .path
+            .file_name()
+            .map_or(0, |name| name.to_string_lossy().graphemes(true).count());
+        if stats
+            .longest_name
+            .as_ref()
+            .is_none_or(|(length, _)| name_length > *length)
+        {
+            stats.longest_name = Some((name_length, entry.path.clone()));
+        }
+    })?;
+    let average_size = if stats.files == 0 {
+        0
+    } else {
+        stats.total_size / stats.files as u64
+    };
+    let describe = |ranked: &Option<(usize, PathBuf)>| match ranked {
+        Some((value, path)) => format!("{} ({})", path.display(), value),
+        None => String::from("-"),
+    };
+    let mut lines = report_header(format!("Statistics for {}:", root.display()));
+    lines.extend([
+        format!("{:<22}{}", "Files:", stats.files),
+        format!("{:<22}{}", "Directories:", stats.directories),
+        format!("{:<22}{}", "Symlinks:", stats.symlinks),
+        format!(
+            "{:<22}{}",
+            "Total size:",
+            format_localised_size(stats.total_size, locale)
+        ),
+        format!(
+            "{:<22}{}",
+            "Average file size:",
+            format_localised_size(average_size, locale)
+        ),
+        format!("{:<22}{}", "Deepest path:", describe(&stats.deepest)),
+        format!("{:<22}{}", "Longest name:", describe(&stats.longest_name)),
+    ]);
+    Ok(lines.join("\n"))
+}
+
+/// Emits sha256sum compatible lines with no header so the output can be fed to `sha256sum -c`.
+/// Pseudo files are left out as reading one such as `/proc/kmsg` never finishes. Files that cannot
+/// be read are named on stderr, like sha256sum does, so the manifest stays parseable.
+fn checksum_manifest(
+    root: &Path,
+    exclude: &[NamePattern],
+) -> Result<String, FileEntryParsingError> {
+    let mut files: Vec<PathBuf> = Vec::new();
+    walk(root, exclude, &mut |entry: WalkedEntry| {
+        if entry.metadata.is_file() && !entry.pseudo {
+            files.push(entry.path.clone());
+        }
+    })?;
+    files.sort();
+    Ok(files
+        .into_iter()
+        .filter_map(|path| match sha256_file(&path) {
+            Ok(hash) => Some(manifest_line(&hash, &normalise_path(&path))),
+            Err(error) => {
+                eprintln!("Unable to hash {} due to: {}", path.display(), error);
+                None
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("\n"))
+}
+
+fn verify_manifest(
+    root: &Path,
+    exclude: &[NamePattern],
+    manifest: &Path,
+) -> Result<String, FileEntryParsingError> {
+    let mut expected = read_manifest(manifest)?;
+    let mut rows: Vec<(PathBuf, &str)> = Vec::new();
+    walk(root, exclude, &mut |entry: WalkedEntry| {
+        if !entry.metadata.is_file() || entry.pseudo {
+            return;
+        }
+        let path = normalise_path(&entry.path);
+        let status = match expected.remove(&path) {
+            None => "NEW",
+            Some(expected_hash) => match sha256_file(&entry.path) {
+                Ok(actual_hash) if actual_hash == expected_hash => "OK",
+                Ok(_) => "CHANGED",
+                Err(_) => "UNREADABLE",
+            },
+        };
+        rows.push((path, status));
+    })?;
+    let normalised_root = normalise_path(root);
+    rows.extend(
+        expected
+            .into_keys()
+            .filter(|path| path.starts_with(&normalised_root))
+            .map(|path| (path, "MISSING")),
+    );
+    rows.sort();
+    let tally = |status: &str| rows.iter().filter(|(_, row)| *row == status).count();
+    let summary = format!(
+        "{} OK, {} CHANGED, {} MISSING, {} NEW",
+        tally("OK"),
+        tally("CHANGED"),
+        tally("MISSING"),
+        tally("NEW")
+    );
+    let mut lines = report_header(format!(
+        "Verification of {} against {}:",
+        root.display(),
+        manifest.display()
+    ));
+    lines.extend(
+        rows.iter()
+            .map(|(path, status)| format!("{:<11}{}", status, path.display())),
+    );
+    lines.push(summary);
+    Ok(lines.join("\n"))
+}
+
+/// Groups siblings whose names only differ by case or by Unicode normalisation, such as `é` typed
+/// as one code point or as `e` plus a combining accent. They are distinct on most Linux
+/// filesystems but clash once the tree is copied to Windows or macOS.
+fn name_collisions(root: &Path, exclude: &[NamePattern]) -> Result<String, FileEntryParsingError> {
+    let mut siblings: BTreeMap<(PathBuf, String), Vec<PathBuf>> = BTreeMap::new();
+    walk(root, exclude, &mut |entry: WalkedEntry| {
+        let name = entry.dir_entry.file_name().to_string_lossy().to_string();
+        let folded = name.nfc().collect::<String>().to_lowercase();
+        let parent = entry.path.parent().unwrap_or(root).to_path_buf();
+        siblings
+            .entry((parent, folded))
+            .or_default()
+            .push(entry.path);
+    })?;
+    let mut lines = report_header(format!("Name collisions in {}:", root.display()));
+    let mut collisions = 0;
+    for mut paths in siblings.into_values().filter(|paths| paths.len() > 1) {
+        paths.sort();
+        let normalised = |path: &PathBuf| {
+            path.file_name()
+                .map(|name| name.to_string_lossy().nfc().collect::<String>())
+        };
+        let kind = if paths
+            .iter()
+            .all(|path| normalised(path) == normalised(&paths[0]))
+        {
+            "normalisation"
+        } else {
+            "case"
+        };
+        lines.extend(
+            paths
+                .iter()
+                .map(|path| format!("{:<15}{}", kind, path.display())),
+        );
+        collisions += 1;
+    }
+    lines.push(format!("{} collisions found", collisions));
+    Ok(lines.join("\n"))
+}
+
+const SUMMARY_TOP_EXTENSIONS: usize = 5;
+
+/// Footer for listings counting each kind of entry traversed and the most common extensions.
+pub(crate) fn traversal_summary(
+    root: &Path,
+    exclude: &[NamePattern],
+    max_depth: Option<usize>,
+) -> Result<String, FileEntryParsingError> {
+    let kinds = [
+        "Regular files",
+        "Directories",
+        "Symlinks",
+        "Sockets",
+        "Devices",
+        "FIFOs",
+    ];
+    let mut kind_counts = [0usize; 6];
+    let mut extension_counts: HashMap<String, usize> = HashMap::new();
+    walk_to_depth(root, max_depth, exclude, &mut |entry: WalkedEntry| {
+        let kind = kinds
+            .iter()
+            .position(|kind| *kind == kind_of(&entry.metadata.file_type()))
+            .expect("kind_of only returns listed kinds");
+        kind_counts[kind] += 1;
+        if let (true, Some(extension)) = (entry.metadata.is_file(), entry.path.extension()) {
+            let extension = format!(".{}", extension.to_string_lossy().to_lowercase());
+            *extension_counts.entry(extension).or_insert(0) += 1;
+        }
+    })?;
+    let mut extensions: Vec<(String, usize)> = extension_counts.into_iter().collect();
+    extensions.sort_by(|(a_ext, a_count), (b_ext, b_count)| {
+        b_count.cmp(a_count).then_with(|| a_ext.cmp(b_ext))
+    });
+    let top_extensions = extensions
+        .into_iter()
+        .take(SUMMARY_TOP_EXTENSIONS)
+        .map(|(extension, count)| format!("{} ({})", extension, count))
+        .collect::<Vec<String>>();
+    let mut lines = report_header(String::from("Summary:"));
+    lines.extend(
+        kinds
+            .iter()
+            .zip(kind_counts)
+            .map(|(kind, count)| format!("{:<22}{}", format!("{}:", kind), count)),
+    );
+    lines.push(format!(
+        "{:<22}{}",
+        "Top extensions:",
+        if top_extensions.is_empty() {
+            String::from("-")
+        } else {
+            top_extensions.join(", ")
+        }
+    ));
+    Ok(lines.join("\n"))
+}
+
+#[cfg(unix)]
+fn kind_of(file_type: &FileType) -> &'static str {
+    use std::os::unix::fs::FileTypeExt;
+    if file_type.is_socket() {
+        "Sockets"
+    } else if file_type.is_block_device() || file_type.is_char_device() {
+        "Devices"
+    } else if file_type.is_fifo() {
+        "FIFOs"
+    } else {
+        portable_kind_of(file_type)
+    }
+}
+
+#[cfg(not(unix))]
+fn kind_of(file_type: &FileType) -> &'static str {
+    portable_kind_of(file_type)
+}
+
+fn portable_kind_of(file_type: &FileType) -> &'static str {
+    if file_type.is_symlink() {
+        "Symlinks"
+    } else if file_type.is_dir() {
+        "Directories"
+    } else {
+        "Regular files"
+    }
+}
+
+fn report_header(title: String) -> Vec<String> {
+    vec![title, String::from("=").repeat(40)]
+}
+
+/// Keeps only the `count` entries with the greatest key while walking, returned greatest first.
+fn top_entries<K, F>(
+    root: &Path,
+    exclude: &[NamePattern],
+    count: usize,
+    key: F,
+) -> Result<Vec<(K, PathBuf)>, FileEntryParsingError>
+where
+    K: Ord,
+    F: Fn(&WalkedEntry) -> Option<K>,
+{
+    let mut heap: BinaryHeap<Reverse<(K, PathBuf)>> = BinaryHeap::with_capacity(count + 1);
+    walk(root, exclude, &mut |entry: WalkedEntry| {
+        let Some(value) = key(&entry) else {
+            return;
+        };
+        heap.push(Reverse((value, entry.path.clone())));
+        if heap.len() > count {
+            heap.pop();
+        }
+    })?;
+    Ok(heap
+        .into_sorted_vec()
+        .into_iter()
+        .map(|Reverse(ranked)| ranked)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::time::Duration;
+    use tempfile::tempdir;
+
+    #[test]
+    fn largest_lists_biggest_files_across_nested_dirs_in_descending_order() {
+        let temp_dir = tempdir().unwrap();
+        let nested = temp_dir.path().join("nested");
+        fs::create_dir(&nested).unwrap();
+        fs::write(temp_dir.path().join("small.txt"), vec![0u8; 10]).unwrap();
+        fs::write(temp_dir.path().join("medium.txt"), vec![0u8; 500]).unwrap();
+        fs::write(nested.join("big.txt"), vec![0u8; 2000]).unwrap();
+
+        let contents = generate_report(
+            &Report::Largest(2),
+            temp_dir.path(),
+            &[],
+            &Locale::default(),
+        )
+        .unwrap();
+        let lines: Vec<&str> = contents.lines().skip(2).collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].contains("big.txt"));
+        assert!(lines[1].contains("medium.txt"));
+        assert!(!contents.contains("small.txt"));
+        let exclude = [NamePattern::parse("nested").unwrap()];
+        let contents = generate_report(
+            &Report::Largest(2),
+            temp_dir.path(),
+            &exclude,
+            &Locale::default(),
+        )
+        .unwrap();
+        assert!(!contents.contains("big.txt"));
+        assert!(contents.contains("small.txt"));
+    }
+
+    #[test]
+    fn sizes_follow_the_locale_decimal_separator() {
+        let temp_dir = tempdir().unwrap();
+        fs::write(temp_dir.path().join("big.txt"), vec![0u8; 2048]).unwrap();
+        let german = Locale::parse("de_DE.UTF-8").unwrap();
+        let contents = generate_report(&Report::Largest(1), temp_dir.path(), &[], &german).unwrap();
+        assert!(contents.contains("2,0 KiB"));
+    }
+
+    #[test]
+    fn oldest_and_newest_order_entries_by_modification_time() {
+        let temp_dir = tempdir().unwrap();
+        let old_file = temp_dir.path().join("old.txt");
+        let new_file = temp_dir.path().join("new.txt");
+        fs::write(&old_file, "old").unwrap();
+        fs::write(&new_file, "new").unwrap();
+        let an_hour_ago = SystemTime::now() - Duration::from_secs(3600);
+        let two_hours_ago = SystemTime::now() - Duration::from_secs(7200);
+        fs::File::options()
+            .write(true)
+            .open(&old_file)
+            .unwrap()
+            .set_modified(two_hours_ago)
+            .unwrap();
+        fs::File::options()
+            .write(true)
+            .open(&new_file)
+            .unwrap()
+            .set_modified(an_hour_ago)
+            .unwrap();
+
+        let oldest =
+            generate_report(&Report::Oldest(1), temp_dir.path(), &[], &Locale::default()).unwrap();
+        assert!(oldest.contains("old.txt"));
+        assert!(!oldest.contains("new.txt"));
+        let newest =
+            generate_report(&Report::Newest(3), temp_dir.path(), &[], &Locale::default()).unwrap();
+        let lines: Vec<&str> = newest.lines().skip(2).collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].contains("new.txt"));
+        assert!(lines[1].contains("old.txt"));
+    }
+
+    #[test]
+    fn extension_breakdown_sorts_by_total_size() {
+        let temp_dir = tempdir().unwrap();
+        let nested = temp_dir.path().join("nested");
+        fs::create_dir(&nested).unwrap();
+        fs::write(temp_dir.path().join("clip.mp4"), vec![0u8; 3000]).unwrap();
+        fs::write(nested.join("other.MP4"), vec![0u8; 1000]).unwrap();
+        fs::write(temp_dir.path().join("notes.txt"), vec![0u8; 100]).unwrap();
+        fs::write(temp_dir.path().join("Makefile"), vec![0u8; 10]).unwrap();
+
+        let contents = generate_report(
+            &Report::ByExtension,
+            temp_dir.path(),
+            &[],
+            &Locale::default(),
+        )
+        .unwrap();
+        let rows: Vec<&str> = contents.lines().skip(3).collect();
+        assert_eq!(rows.len(), 3);
+        assert!(rows[0].starts_with(".mp4"));
+        assert!(rows[0].contains(" 2 "));
+        assert!(rows[1].starts_with(".txt"));
+        assert!(rows[2].starts_with("(none)"));
+    }
+
+    #[test]
+    fn stats_summarise_counts_sizes_and_extremes() {
+        let temp_dir = tempdir().unwrap();
+        let deep = temp_dir.path().join("a").join("b");
+        fs::create_dir_all(&deep).unwrap();
+        fs::write(temp_dir.path().join("one.txt"), vec![0u8; 100]).unwrap();
+        fs::write(deep.join("a_rather_long_name.txt"), vec![0u8; 300]).unwrap();
+
+        let contents =
+            generate_report(&Report::Stats, temp_dir.path(), &[], &Locale::default()).unwrap();
+        assert!(contents.contains("Files:                2"));
+        assert!(contents.contains("Directories:          2"));
+        assert!(contents.contains("Symlinks:             0"));
+        assert!(contents.contains("Total size:           400 B"));
+        assert!(contents.contains("Average file size:    200 B"));
+        let deepest_line = contents
+            .lines()
+            .find(|line| line.starts_with("Deepest path:"))
+            .unwrap();
+        assert!(deepest_line.contains("a_rather_long_name.txt (3)"));
+        let longest_line = contents
+            .lines()
+            .find(|line| line.starts_with("Longest name:"))
+            .unwrap();
+        assert!(longest_line.ends_with("a_rather_long_name.txt (22)"));
+    }
+
+    #[test]
+    fn verify_marks_entries_against_manifest() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().join("tree");
+        fs::create_dir(&root).unwrap();
+        fs::write(root.join("same.txt"), "unchanged").unwrap();
+        fs::write(root.join("edited.txt"), "edited").unwrap();
+        fs::write(root.join("added.txt"), "added").unwrap();
+        let manifest = temp_dir.path().join("manifest.sha256");
+        let manifest_line =
+            |hash: String, name: &str| format!("{}  {}\n", hash, root.join(name).display());
+        let manifest_contents = [
+            manifest_line(sha256_file(&root.join("same.txt")).unwrap(), "same.txt"),
+            manifest_line("0".repeat(64), "edited.txt"),
+            manifest_line("1".repeat(64), "removed.txt"),
+        ]
+        .concat();
+        fs::write(&manifest, manifest_contents).unwrap();
+
+        let contents =
+            generate_report(&Report::Verify(manifest), &root, &[], &Locale::default()).unwrap();
+        let status_of = |name: &str| {
+            contents
+                .lines()
+                .find(|line| line.ends_with(name))
+                .unwrap()
+                .split_whitespace()
+                .next()
+                .unwrap()
+                .to_string()
+        };
+        assert_eq!(status_of("same.txt"), "OK");
+        assert_eq!(status_of("edited.txt"), "CHANGED");
+        assert_eq!(status_of("removed.txt"), "MISSING");
+        assert_eq!(status_of("added.txt"), "NEW");
+        assert!(contents.ends_with("1 OK, 1 CHANGED, 1 MISSING, 1 NEW"));
+    }
+
+    #[test]
+    fn checksum_manifest_round_trips_through_verify() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().join("tree");
+        fs::create_dir_all(root.join("nested")).unwrap();
+        fs::write(root.join("top.txt"), "top").unwrap();
+        fs::write(root.join("nested").join("inner.txt"), "inner").unwrap();
+
+        let contents = generate_report(&Report::Checksums, &root, &[], &Locale::default()).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with("nested/inner.txt"));
+        assert_eq!(
+            lines[1],
+            format!(
+                "{}  {}",
+                sha256_file(&root.join("top.txt")).unwrap(),
+                root.join("top.txt").display()
+            )
+        );
+
+        let manifest = temp_dir.path().join("manifest.sha256");
+        fs::write(&manifest, contents).unwrap();
+        let verification =
+            generate_report(&Report::Verify(manifest), &root, &[], &Locale::default()).unwrap();
+        assert!(verification.ends_with("2 OK, 0 CHANGED, 0 MISSING, 0 NEW"));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn checksum_manifest_escapes_names_like_sha256sum() {
+        let temp_dir = tempdir().unwrap();
+        fs::write(temp_dir.path().join("a\nb"), "split").unwrap();
+        fs::write(temp_dir.path().join("plain.txt"), "plain").unwrap();
+        let contents =
+            generate_report(&Report::Checksums, temp_dir.path(), &[], &Locale::default()).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            lines[0],
+            format!(
+                "\\{}  {}/a\\nb",
+                sha256_file(&temp_dir.path().join("a\nb")).unwrap(),
+                temp_dir.path().display()
+            )
+        );
+        assert!(!lines[1].starts_with('\\'));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn verify_reads_escaped_manifest_names() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().join("tree");
+        fs::create_dir(&root).unwrap();
+        fs::write(root.join("back\\slash.txt"), "back").unwrap();
+        fs::write(root.join("line\nbreak.txt"), "line").unwrap();
+        let manifest_line = |name: &str, escaped: &str| {
+            let hash = sha256_file(&root.join(name)).unwrap();
+            format!("\\{}  {}/{}\n", hash, root.display(), escaped)
+        };
+        let manifest = temp_dir.path().join("manifest.sha256");
+        let manifest_contents = [
+            manifest_line("back\\slash.txt", "back\\\\slash.txt"),
+            manifest_line("line\nbreak.txt", "line\\nbreak.txt"),
+        ]
+        .concat();
+        fs::write(&manifest, manifest_contents).unwrap();
+        let verification =
+            generate_report(&Report::Verify(manifest), &root, &[], &Locale::default()).unwrap();
+        assert!(verification.ends_with("2 OK, 0 CHANGED, 0 MISSING, 0 NEW"));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn escaped_names_round_trip_from_checksums_to_verify() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().join("tree");
+        fs::create_dir(&root).unwrap();
+        for name in [
+            "line\nbreak.txt",
+            "back\\slash.txt",
+            "carriage\rreturn.txt",
+            "plain.txt",
+        ] {
+            fs::write(root.join(name), name).unwrap();
+        }
+        let contents = generate_report(&Report::Checksums, &root, &[], &Locale::default()).unwrap();
+        assert_eq!(contents.lines().count(), 4);
+        let manifest = temp_dir.path().join("manifest.sha256");
+        fs::write(&manifest, contents).unwrap();
+        fs::write(root.join("line\nbreak.txt"), "edited").unwrap();
+        let verification =
+            generate_report(&Report::Verify(manifest), &root, &[], &Locale::default()).unwrap();
+        assert!(verification.ends_with("3 OK, 1 CHANGED, 0 MISSING, 0 NEW"));
+    }
+
+    #[test]
+    fn verify_rejects_malformed_manifests() {
+        let temp_dir = tempdir().unwrap();
+        let manifest = temp_dir.path().join("manifest.sha256");
+        fs::write(&manifest, "not a checksum line\n").unwrap();
+        let error = generate_report(
+            &Report::Verify(manifest),
+            temp_dir.path(),
+            &[],
+            &Locale::default(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "manifest line 1 is not in sha256sum format"
+        );
+    }
+
+    #[test]
+    fn traversal_summary_counts_kinds_and_extensions_within_depth() {
+        let temp_dir = tempdir().unwrap();
+        let nested = temp_dir.path().join("nested");
+        fs::create_dir(&nested).unwrap();
+        fs::write(temp_dir.path().join("a.rs"), "").unwrap();
+        fs::write(temp_dir.path().join("b.rs"), "").unwrap();
+        fs::write(temp_dir.path().join("c.md"), "").unwrap();
+        fs::write(nested.join("d.md"), "").unwrap();
+
+        let flat = traversal_summary(temp_dir.path(), &[], Some(1)).unwrap();
+        assert!(flat.contains("Regular files:        3"));
+        assert!(flat.contains("Directories:          1"));
+        assert!(flat.contains("Top extensions:       .rs (2), .md (1)"));
+        let full = traversal_summary(temp_dir.path(), &[], None).unwrap();
+        assert!(full.contains("Regular files:        4"));
+        assert!(full.contains("Top extensions:       .md (2), .rs (2)"));
+    }
+
+    #[test]
+    fn histogram_counts_files_into_logarithmic_buckets() {
+        let temp_dir = tempdir().unwrap();
+        fs::write(temp_dir.path().join("tiny_1.txt"), vec![0u8; 10]).unwrap();
+        fs::write(temp_dir.path().join("tiny_2.txt"), vec![0u8; 20]).unwrap();
+        fs::write(temp_dir.path().join("small.txt"), vec![0u8; 5 * 1024]).unwrap();
+
+        let contents =
+            generate_report(&Report::Histogram, temp_dir.path(), &[], &Locale::default()).unwrap();
+        let rows: Vec<&str> = contents.lines().skip(2).collect();
+        assert_eq!(rows.len(), 8);
+        assert!(rows[0].starts_with("< 1 KiB"));
+        assert!(rows[0].ends_with(&"#".repeat(40)));
+        assert!(rows[1].starts_with("1-10 KiB"));
+        assert!(rows[1].ends_with(&format!(" {}", "#".repeat(20))));
+        assert!(rows[2].ends_with('0'));
+    }
+
+    #[test]
+    fn collisions_group_names_differing_by_case_or_normalisation() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir(root.join("docs")).unwrap();
+        fs::write(root.join("docs").join("README.md"), "").unwrap();
+        fs::write(root.join("docs").join("readme.md"), "").unwrap();
+        fs::write(root.join("caf\u{e9}.txt"), "").unwrap();
+        fs::write(root.join("cafe\u{301}.txt"), "").unwrap();
+        fs::write(root.join("Readme.md"), "").unwrap();
+        let report = name_collisions(root, &[]).unwrap();
+        let lines: Vec<&str> = report.lines().collect();
+        assert_eq!(lines.len(), 7);
+        assert_eq!(
+            lines
+                .iter()
+                .filter(|line| line.starts_with("case "))
+                .count(),
+            2
+        );
+        assert_eq!(
+            lines
+                .iter()
+                .filter(|line| line.starts_with("normalisation"))
+                .count(),
+            2
+        );
+        assert!(!report.contains(&root.join("Readme.md").display().to_string()));
+        assert_eq!(lines[6], "2 collisions found");
+    }
+}
@@ -0,0 +1,125 @@
+use crate::entries::{EntryKind, FileEntry};
+use crate::walker::{walk, WalkedEntry};
+use crate::FileEntryParsingError;
+use glob::Pattern;
+use regex::Regex;
+use serde::{Serialize, Serializer};
+use std::path::Path;
+
+pub enum NamePattern {
+    Glob(Pattern),
+    Substring(String),
+    Regex(Regex),
+}
+
+/// Serialised as the pattern the user typed.
+impl Serialize for NamePattern {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            NamePattern::Glob(pattern) => serializer.serialize_str(pattern.as_str()),
+            NamePattern::Substring(text) => serializer.serialize_str(text),
+            NamePattern::Regex(regex) => serializer.serialize_str(regex.as_str()),
+        }
+    }
+}
+
+impl NamePattern {
+    /// Patterns containing glob syntax are matched as globs, anything else as a plain substring.
+    pub fn parse(pattern: &str) -> Result<NamePattern, glob::PatternError> {
+        if pattern.contains(['*', '?', '[']) {
+            Ok(NamePattern::Glob(Pattern::new(pattern)?))
+        } else {
+            Ok(NamePattern::Substring(pattern.to_string()))
+        }
+    }
+
+    pub fn matches(&self, name: &str) -> bool {
+        match self {
+            NamePattern::Glob(pattern) => pattern.matches(name),
+            NamePattern::Substring(text) => name.contains(text.as_str()),
+            NamePattern::Regex(regex) => regex.is_match(name),
+        }
+    }
+}
+
+/// Everything below `root` whose name matches `pattern`, leaving out and not descending into
+/// anything matching one of the `exclude` patterns.
+pub(crate) fn find_matching_entries(
+    root: &Path,
+    pattern: &NamePattern,
+    exclude: &[NamePattern],
+) -> Result<(Vec<FileEntry>, Vec<FileEntry>), FileEntryParsingError> {
+    let mut directories = Vec::new();
+    let mut files = Vec::new();
+    walk(root, exclude, &mut |entry: WalkedEntry| {
+        if !pattern.matches(&entry.dir_entry.file_name().to_string_lossy()) {
+            return;
+        }
+        let (is_dir, pseudo) = (entry.metadata.is_dir(), entry.pseudo);
+        let mut entry = FileEntry::from_metadata(entry.path, entry.metadata);
+        // as in the listing, pseudo files get no size
+        if pseudo && entry.kind == EntryKind::File {
+            entry.size = None;
+        }
+        if is_dir {
+            directories.push(entry);
+        } else {
+            files.push(entry);
+        }
+    })?;
+    Ok((directories, files))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn glob_and_substring_patterns_match_names() {
+        let glob = NamePattern::parse("*.rs").unwrap();
+        assert!(glob.matches("lib.rs"));
+        assert!(!glob.matches("lib.rs.bak"));
+        let substring = NamePattern::parse("lib").unwrap();
+        assert!(substring.matches("my_library.txt"));
+        assert!(!substring.matches("main.rs"));
+        let regex = NamePattern::Regex(Regex::new(r"^v\d+\.txt$").unwrap());
+        assert!(regex.matches("v12.txt"));
+        assert!(!regex.matches("v12.txt.bak"));
+    }
+
+    #[test]
+    fn finds_matching_entries_in_nested_directories() {
+        let temp_dir = tempdir().unwrap();
+        let nested = temp_dir.path().join("src_dir");
+        fs::create_dir(&nested).unwrap();
+        fs::write(temp_dir.path().join("top.rs"), "").unwrap();
+        fs::write(temp_dir.path().join("notes.txt"), "").unwrap();
+        fs::write(nested.join("inner.rs"), "").unwrap();
+
+        let pattern = NamePattern::parse("*.rs").unwrap();
+        let (directories, files) = find_matching_entries(temp_dir.path(), &pattern, &[]).unwrap();
+        assert!(directories.is_empty());
+        let mut names: Vec<String> = files.iter().map(|entry| entry.name.clone()).collect();
+        names.sort();
+        assert_eq!(names, vec!["inner.rs", "top.rs"]);
+    }
+
+    #[test]
+    fn excluded_names_are_skipped_and_not_searched() {
+        let temp_dir = tempdir().unwrap();
+        fs::create_dir(temp_dir.path().join("target")).unwrap();
+        fs::write(temp_dir.path().join("target/built.rs"), "").unwrap();
+        fs::write(temp_dir.path().join("kept.rs"), "").unwrap();
+        fs::write(temp_dir.path().join("skipped.rs"), "").unwrap();
+        let pattern = NamePattern::parse("*.rs").unwrap();
+        let exclude = [
+            NamePattern::parse("target").unwrap(),
+            NamePattern::parse("skip*").unwrap(),
+        ];
+        let (_, files) = find_matching_entries(temp_dir.path(), &pattern, &exclude).unwrap();
+        let names: Vec<&str> = files.iter().map(|entry| entry.name.as_str()).collect();
+        assert_eq!(names, vec!["kept.rs"]);
+    }
+}
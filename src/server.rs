@@ -0,0 +1,243 @@
+use crate::entries::FileEntry;
+use crate::html;
+use crate::machine_output::{self, MachineFormat};
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+
+pub(crate) const DEFAULT_PORT: u16 = 8080;
+/// Only this machine can connect unless `--bind` opens the listing up to the network.
+pub(crate) const DEFAULT_ADDRESS: IpAddr = IpAddr::V4(Ipv4Addr::LOCALHOST);
+/// Connections are answered one at a time, so a client that never sends its request is dropped
+/// rather than holding up everyone else.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+struct Response {
+    status: &'static str,
+    content_type: &'static str,
+    body: Vec<u8>,
+}
+
+impl Response {
+    fn error(status: &'static str) -> Self {
+        Response {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            body: status.as_bytes().to_vec(),
+        }
+    }
+}
+
+/// Serves read only listings of `root` on `address` until the process is interrupted. Only `GET`
+/// and `HEAD` are answered and requests can never reach outside of `root`, not even through
+/// symlinks.
+pub(crate) fn serve(root: &Path, address: IpAddr, port: u16) -> io::Result<()> {
+    let address = SocketAddr::new(address, port);
+    let listener = TcpListener::bind(address)?;
+    println!("Serving {} on http://{}/", root.display(), address);
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                if let Err(error) = handle_connection(stream, root) {
+                    eprintln!("Unable to answer request due to: {}", error);
+                }
+            }
+            Err(error) => eprintln!("Unable to accept connection due to: {}", error),
+        }
+    }
+    Ok(())
+}
+
+fn handle_connection(mut stream: TcpStream, root: &Path) -> io::Result<()> {
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+    let head_only = request_line.starts_with("HEAD ");
+    let response = respond(root, request_line.trim_end());
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.status,
+        response.content_type,
+        response.body.len()
+    )?;
+    if !head_only {
+        stream.write_all(&response.body)?;
+    }
+    stream.flush()
+}
+
+fn respond(root: &Path, request_line: &str) -> Response {
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Response::error("400 Bad Request");
+    };
+    if method != "GET" && method != "HEAD" {
+        return Response::error("405 Method Not Allowed");
+    }
+    let (raw_path, query) = target.split_once('?').unwrap_or((target, ""));
+    let Some(relative) = decode_path(raw_path) else {
+        return Response::error("400 Bad Request");
+    };
+    let resolved = match resolve(root, &relative) {
+        Ok(resolved) => resolved,
+        Err(response) => return response,
+    };
+    if resolved.is_file() {
+        return match fs::read(&resolved) {
+            Ok(body) => Response {
+                status: "200 OK",
+                content_type: "application/octet-stream",
+                body,
+            },
+            Err(_) => Response::error("403 Forbidden"),
+        };
+    }
+    let entries = match read_entries(&resolved) {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => {
+            return Response::error("404 Not Found")
+        }
+        Err(_) => return Response::error("403 Forbidden"),
+    };
+    let display_path = format!("/{}", relative.to_string_lossy());
+    if query.split('&').any(|pair| pair == "format=json") {
+        Response {
+            status: "200 OK",
+            content_type: "application/json",
+            body: machine_output::render(MachineFormat::Json, &display_path, &entries, None)
+                .into_bytes(),
+        }
+    } else {
+        let base = display_path.trim_end_matches('/');
+        let parent = base
+            .rsplit_once('/')
+            .map(|(parent, _)| format!("{}/", parent));
+        Response {
+            status: "200 OK",
+            content_type: "text/html; charset=utf-8",
+            body: html::render_index(&display_path, &entries, base, parent.as_deref()).into_bytes(),
+        }
+    }
+}
+
+/// Symlinks are followed as long as they lead somewhere inside `root`.
+fn resolve(root: &Path, relative: &Path) -> Result<PathBuf, Response> {
+    let only_normal = relative
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)));
+    if !only_normal {
+        return Err(Response::error("403 Forbidden"));
+    }
+    let root = fs::canonicalize(root).map_err(|_| Response::error("404 Not Found"))?;
+    match fs::canonicalize(root.join(relative)) {
+        Ok(resolved) if resolved.starts_with(&root) => Ok(resolved),
+        Ok(_) => Err(Response::error("403 Forbidden")),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => {
+            Err(Response::error("404 Not Found"))
+        }
+        Err(_) => Err(Response::error("403 Forbidden")),
+    }
+}
+
+fn read_entries(dir: &Path) -> io::Result<Vec<FileEntry>> {
+    let mut entries: Vec<FileEntry> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| FileEntry::from_dir_entry(&entry))
+        .collect();
+    entries.sort_by(|left, right| left.name.cmp(&right.name));
+    Ok(entries)
+}
+
+fn decode_path(raw_path: &str) -> Option<PathBuf> {
+    let bytes = raw_path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == b'%' {
+            let hex = std::str::from_utf8(bytes.get(index + 1..index + 3)?).ok()?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            index += 3;
+        } else {
+            decoded.push(bytes[index]);
+            index += 1;
+        }
+    }
+    let decoded = String::from_utf8(decoded).ok()?;
+    Some(PathBuf::from(decoded.trim_start_matches('/')))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+    use tempfile::tempdir;
+
+    #[test]
+    fn html_index_links_each_entry() {
+        let temp_dir = tempdir().unwrap();
+        fs::write(temp_dir.path().join("a file.txt"), "12345").unwrap();
+        fs::create_dir(temp_dir.path().join("nested")).unwrap();
+        let response = respond(temp_dir.path(), "GET / HTTP/1.1");
+        let body = String::from_utf8(response.body).unwrap();
+        assert_eq!(response.status, "200 OK");
+        assert!(body.contains("<a href=\"/a%20file.txt\">a file.txt</a>"));
+        assert!(body.contains("<a href=\"/nested/\">nested</a>"));
+        assert!(!body.contains("../"));
+        fs::write(temp_dir.path().join("nested/inner.txt"), "1").unwrap();
+        let response = respond(temp_dir.path(), "GET /nested HTTP/1.1");
+        let body = String::from_utf8(response.body).unwrap();
+        assert!(body.contains("<p><a href=\"/\">../</a></p>"));
+        assert!(body.contains("<a href=\"/nested/inner.txt\">inner.txt</a>"));
+    }
+
+    #[test]
+    fn json_format_reuses_the_machine_readable_renderer() {
+        let temp_dir = tempdir().unwrap();
+        fs::create_dir(temp_dir.path().join("nested")).unwrap();
+        fs::write(temp_dir.path().join("nested").join("inner.txt"), "1").unwrap();
+        let response = respond(temp_dir.path(), "GET /nested/?format=json HTTP/1.1");
+        let document: Value = serde_json::from_slice(&response.body).unwrap();
+        assert_eq!(response.content_type, "application/json");
+        assert_eq!(document["target"], "/nested/");
+        assert_eq!(document["entries"][0]["name"], "inner.txt");
+    }
+
+    #[test]
+    fn requests_cannot_escape_the_served_root() {
+        let temp_dir = tempdir().unwrap();
+        let response = respond(temp_dir.path(), "GET /../ HTTP/1.1");
+        assert_eq!(response.status, "403 Forbidden");
+        let response = respond(temp_dir.path(), "GET /%2E%2E/etc HTTP/1.1");
+        assert_eq!(response.status, "403 Forbidden");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn symlinks_cannot_lead_outside_the_served_root() {
+        let outside = tempdir().unwrap();
+        fs::write(outside.path().join("secret.txt"), "secret").unwrap();
+        let temp_dir = tempdir().unwrap();
+        fs::write(temp_dir.path().join("inside.txt"), "inside").unwrap();
+        std::os::unix::fs::symlink(outside.path(), temp_dir.path().join("escape")).unwrap();
+        std::os::unix::fs::symlink("inside.txt", temp_dir.path().join("alias.txt")).unwrap();
+        let response = respond(temp_dir.path(), "GET /escape/secret.txt HTTP/1.1");
+        assert_eq!(response.status, "403 Forbidden");
+        let response = respond(temp_dir.path(), "GET /escape/ HTTP/1.1");
+        assert_eq!(response.status, "403 Forbidden");
+        let response = respond(temp_dir.path(), "GET /alias.txt HTTP/1.1");
+        assert_eq!(response.body, b"inside");
+    }
+
+    #[test]
+    fn only_read_methods_are_allowed() {
+        let temp_dir = tempdir().unwrap();
+        let response = respond(temp_dir.path(), "DELETE / HTTP/1.1");
+        assert_eq!(response.status, "405 Method Not Allowed");
+        let response = respond(temp_dir.path(), "GET /missing/ HTTP/1.1");
+        assert_eq!(response.status, "404 Not Found");
+    }
+}
@@ -0,0 +1,196 @@
+use crate::entries::FileEntry;
+use serde::Serialize;
+use std::cmp::{Ordering, Reverse};
+use std::ffi::OsString;
+use std::iter::Peekable;
+use std::str::Chars;
+use std::time::SystemTime;
+
+/// Order of entries within a listing. As with `ls -t` and `ls -S` the newest and largest
+/// entries come first, `Version` is the natural order of `ls -v`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SortKey {
+    Name,
+    Size,
+    Modified,
+    Created,
+    Version,
+}
+
+/// What entries are sorted on. Only entries whose metadata has already been read are sortable, so
+/// a sort never stats anything itself.
+pub(crate) trait Sortable {
+    fn name(&self) -> OsString;
+    fn size(&self) -> Option<u64>;
+    fn modified(&self) -> Option<SystemTime>;
+    fn created(&self) -> Option<SystemTime>;
+}
+
+impl Sortable for FileEntry {
+    fn name(&self) -> OsString {
+        self.path
+            .file_name()
+            .map_or_else(|| OsString::from(&self.name), OsString::from)
+    }
+
+    fn size(&self) -> Option<u64> {
+        self.size
+    }
+
+    fn modified(&self) -> Option<SystemTime> {
+        self.modified
+    }
+
+    fn created(&self) -> Option<SystemTime> {
+        self.created
+    }
+}
+
+/// Entries whose metadata cannot be read sort after the rest, ties fall back to the name.
+/// Reversing flips the whole order, tie breaks included, as `ls -r` does. Filesystems without
+/// birth times give no creation time, those entries sort last.
+pub(crate) fn compare<T: Sortable>(left: &T, right: &T, key: SortKey, reverse: bool) -> Ordering {
+    // as with `ls -t` and `ls -S` the largest and newest come first
+    let by_key = match key {
+        SortKey::Name => Ordering::Equal,
+        SortKey::Size => Reverse(left.size()).cmp(&Reverse(right.size())),
+        SortKey::Modified => Reverse(left.modified()).cmp(&Reverse(right.modified())),
+        SortKey::Created => Reverse(left.created()).cmp(&Reverse(right.created())),
+        SortKey::Version => compare_naturally(
+            &left.name().to_string_lossy(),
+            &right.name().to_string_lossy(),
+        ),
+    };
+    let ordering = by_key.then_with(|| left.name().cmp(&right.name()));
+    if reverse {
+        ordering.reverse()
+    } else {
+        ordering
+    }
+}
+
+/// Runs of digits compare by their value, so `file2.txt` comes before `file10.txt` and `v1.9`
+/// before `v1.10`, everything else character by character. Numbers differing only in leading
+/// zeros are equal here, the plain name comparison settles them.
+fn compare_naturally(left: &str, right: &str) -> Ordering {
+    let mut left = left.chars().peekable();
+    let mut right = right.chars().peekable();
+    loop {
+        let ordering = match (left.peek(), right.peek()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(l), Some(r)) if l.is_ascii_digit() && r.is_ascii_digit() => {
+                let (l, r) = (take_number(&mut left), take_number(&mut right));
+                let (l, r) = (l.trim_start_matches('0'), r.trim_start_matches('0'));
+                l.len().cmp(&r.len()).then_with(|| l.cmp(r))
+            }
+            (Some(l), Some(r)) => {
+                let ordering = l.cmp(r);
+                left.next();
+                right.next();
+                ordering
+            }
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+fn take_number(chars: &mut Peekable<Chars>) -> String {
+    let mut number = String::new();
+    while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+        number.push(digit);
+    }
+    number
+}
+
+pub(crate) fn sort_entries<T: Sortable>(entries: &mut [T], key: SortKey, reverse: bool) {
+    entries.sort_by(|left, right| compare(left, right, key, reverse));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::time::Duration;
+    use tempfile::tempdir;
+
+    fn names(entries: &[FileEntry]) -> Vec<String> {
+        entries.iter().map(|entry| entry.name.clone()).collect()
+    }
+
+    #[test]
+    fn sorts_largest_and_newest_first() {
+        let temp_dir = tempdir().unwrap();
+        fs::write(temp_dir.path().join("b_small"), "1").unwrap();
+        std::thread::sleep(Duration::from_millis(20));
+        fs::write(temp_dir.path().join("a_large"), "12345").unwrap();
+        std::thread::sleep(Duration::from_millis(20));
+        fs::write(temp_dir.path().join("c_medium"), "123").unwrap();
+        let mut entries: Vec<FileEntry> = fs::read_dir(temp_dir.path())
+            .unwrap()
+            .map(|entry| FileEntry::from_dir_entry(&entry.unwrap()))
+            .collect();
+        sort_entries(&mut entries, SortKey::Name, false);
+        assert_eq!(names(&entries), vec!["a_large", "b_small", "c_medium"]);
+        sort_entries(&mut entries, SortKey::Size, false);
+        assert_eq!(names(&entries), vec!["a_large", "c_medium", "b_small"]);
+        sort_entries(&mut entries, SortKey::Modified, false);
+        assert_eq!(names(&entries), vec!["c_medium", "a_large", "b_small"]);
+        if entries.iter().all(|entry| entry.created.is_some()) {
+            sort_entries(&mut entries, SortKey::Name, false);
+            sort_entries(&mut entries, SortKey::Created, false);
+            assert_eq!(names(&entries), vec!["c_medium", "a_large", "b_small"]);
+        }
+    }
+
+    #[test]
+    fn version_sort_compares_embedded_numbers_by_value() {
+        let temp_dir = tempdir().unwrap();
+        for name in [
+            "file10.txt",
+            "file2.txt",
+            "file1.txt",
+            "v1.10",
+            "v1.9",
+            "file02.txt",
+        ] {
+            fs::write(temp_dir.path().join(name), "").unwrap();
+        }
+        let mut entries: Vec<FileEntry> = fs::read_dir(temp_dir.path())
+            .unwrap()
+            .map(|entry| FileEntry::from_dir_entry(&entry.unwrap()))
+            .collect();
+        sort_entries(&mut entries, SortKey::Version, false);
+        assert_eq!(
+            names(&entries),
+            vec![
+                "file1.txt",
+                "file02.txt",
+                "file2.txt",
+                "file10.txt",
+                "v1.9",
+                "v1.10"
+            ]
+        );
+    }
+
+    #[test]
+    fn reversing_flips_every_key() {
+        let temp_dir = tempdir().unwrap();
+        fs::write(temp_dir.path().join("b_small"), "1").unwrap();
+        fs::write(temp_dir.path().join("a_large"), "12345").unwrap();
+        fs::write(temp_dir.path().join("c_same"), "1").unwrap();
+        let mut entries: Vec<FileEntry> = fs::read_dir(temp_dir.path())
+            .unwrap()
+            .map(|entry| FileEntry::from_dir_entry(&entry.unwrap()))
+            .collect();
+        sort_entries(&mut entries, SortKey::Name, true);
+        assert_eq!(names(&entries), vec!["c_same", "b_small", "a_large"]);
+        sort_entries(&mut entries, SortKey::Size, true);
+        assert_eq!(names(&entries), vec!["c_same", "b_small", "a_large"]);
+    }
+}
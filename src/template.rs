@@ -0,0 +1,172 @@
+use crate::entries::FileEntry;
+use crate::machine_output::format_timestamp;
+use chrono::{DateTime, Utc};
+use serde::{Serialize, Serializer};
+
+const PLACEHOLDERS: [(&str, Field); 8] = [
+    ("name", Field::Name),
+    ("path", Field::Path),
+    ("kind", Field::Kind),
+    ("size", Field::Size),
+    ("modified", Field::Modified),
+    ("created", Field::Created),
+    ("mode", Field::Mode),
+    ("readonly", Field::Readonly),
+];
+const MISSING: &str = "-";
+
+#[derive(Clone, Copy)]
+enum Field {
+    Name,
+    Path,
+    Kind,
+    Size,
+    Modified,
+    Created,
+    Mode,
+    Readonly,
+}
+
+enum Part {
+    Text(String),
+    Placeholder(Field),
+}
+
+/// A `--format` pattern such as `{name}\t{size}`, rendered once per entry in place of the
+/// table. `\t`, `\n` and `\\` are unescaped so the pattern can be typed in a shell, `{{` and
+/// `}}` print literal braces.
+pub(crate) struct Template {
+    source: String,
+    parts: Vec<Part>,
+}
+
+/// Serialised as the pattern the user typed.
+impl Serialize for Template {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.source)
+    }
+}
+
+impl Template {
+    /// Fails with the reason on unknown placeholders and unbalanced braces.
+    pub(crate) fn parse(source: &str) -> Result<Template, String> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut chars = source.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some('t') => text.push('\t'),
+                    Some('n') => text.push('\n'),
+                    Some(other) => {
+                        if other != '\\' {
+                            text.push('\\');
+                        }
+                        text.push(other);
+                    }
+                    None => text.push('\\'),
+                },
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => return Err(format!("{{{} is missing its closing }}", name)),
+                        }
+                    }
+                    let (_, field) = PLACEHOLDERS
+                        .iter()
+                        .find(|(placeholder, _)| *placeholder == name)
+                        .ok_or_else(|| {
+                            format!(
+                                "unknown placeholder {{{}}}, expected one of {}",
+                                name,
+                                PLACEHOLDERS
+                                    .map(|(name, _)| format!("{{{}}}", name))
+                                    .join(", ")
+                            )
+                        })?;
+                    parts.push(Part::Text(std::mem::take(&mut text)));
+                    parts.push(Part::Placeholder(*field));
+                }
+                '}' => return Err(String::from("unmatched }, write }} for a literal brace")),
+                _ => text.push(c),
+            }
+        }
+        parts.push(Part::Text(text));
+        Ok(Template {
+            source: source.to_string(),
+            parts,
+        })
+    }
+
+    /// Dates use `time_format` when given and RFC 3339 otherwise, values the entry does not have
+    /// are printed as `-`.
+    pub(crate) fn render(&self, entry: &FileEntry, time_format: Option<&str>) -> String {
+        let date = |time: Option<std::time::SystemTime>| match (time, time_format) {
+            (None, _) => String::from(MISSING),
+            (Some(time), None) => format_timestamp(time),
+            (Some(time), Some(time_format)) => {
+                DateTime::<Utc>::from(time).format(time_format).to_string()
+            }
+        };
+        self.parts
+            .iter()
+            .map(|part| match part {
+                Part::Text(text) => text.clone(),
+                Part::Placeholder(Field::Name) => entry.name.clone(),
+                Part::Placeholder(Field::Path) => entry.path.to_string_lossy().to_string(),
+                Part::Placeholder(Field::Kind) => entry.kind.as_str().to_string(),
+                Part::Placeholder(Field::Size) => entry
+                    .size
+                    .map_or(String::from(MISSING), |size| size.to_string()),
+                Part::Placeholder(Field::Modified) => date(entry.modified),
+                Part::Placeholder(Field::Created) => date(entry.created),
+                Part::Placeholder(Field::Mode) => entry
+                    .mode
+                    .map_or(String::from(MISSING), |mode| format!("{:o}", mode & 0o7777)),
+                Part::Placeholder(Field::Readonly) => entry
+                    .readonly
+                    .map_or(String::from(MISSING), |readonly| readonly.to_string()),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn placeholders_are_filled_from_the_entry() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("notes.txt");
+        fs::write(&path, "hello").unwrap();
+        let entry = FileEntry::from_path(&path).unwrap();
+        let template = Template::parse("{name}\\t{size}\\t{kind} {{literal}}").unwrap();
+        assert_eq!(
+            template.render(&entry, None),
+            "notes.txt\t5\tfile {literal}"
+        );
+        let template = Template::parse("{modified}").unwrap();
+        assert_eq!(template.render(&entry, Some("%Y")).len(), 4);
+    }
+
+    #[test]
+    fn unknown_placeholders_and_stray_braces_are_rejected() {
+        assert!(Template::parse("{nme}").err().unwrap().contains("{name}"));
+        assert!(Template::parse("size}").is_err());
+        assert!(Template::parse("{size").is_err());
+    }
+}
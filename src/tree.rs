@@ -0,0 +1,130 @@
+use crate::entries::{EntryKind, FileEntry};
+use crate::output_formatting::{IconStyle, FLOPPY, FOLDER, LINK};
+use std::io;
+use std::path::Path;
+
+const BRANCH: &str = "├── ";
+const LAST_BRANCH: &str = "└── ";
+const PIPE: &str = "│   ";
+const GAP: &str = "    ";
+
+/// Draws `root` and everything below it with branch characters like `tree`, stopping after
+/// `depth` levels when given. `children` lists a directory in display order. Symlinked
+/// directories are drawn but not followed, a nested directory that cannot be read gets the
+/// reason after its name.
+pub(crate) fn render(
+    root: &Path,
+    depth: Option<usize>,
+    icons: IconStyle,
+    mut children: impl FnMut(&Path) -> io::Result<Vec<FileEntry>>,
+) -> io::Result<String> {
+    let entries = children(root)?;
+    let mut lines = vec![root.display().to_string()];
+    let mut counts = (0, 0);
+    let mut walk = Walk {
+        depth,
+        icons,
+        children: &mut children,
+    };
+    draw(&mut walk, &entries, "", 1, &mut lines, &mut counts);
+    lines.push(String::new());
+    lines.push(format!(
+        "{} {}, {} {}",
+        counts.0,
+        if counts.0 == 1 {
+            "directory"
+        } else {
+            "directories"
+        },
+        counts.1,
+        if counts.1 == 1 { "file" } else { "files" }
+    ));
+    Ok(lines.join("\n"))
+}
+
+/// What stays the same for every level of the tree.
+struct Walk<'a, F: FnMut(&Path) -> io::Result<Vec<FileEntry>>> {
+    depth: Option<usize>,
+    icons: IconStyle,
+    children: &'a mut F,
+}
+
+fn draw<F: FnMut(&Path) -> io::Result<Vec<FileEntry>>>(
+    walk: &mut Walk<F>,
+    entries: &[FileEntry],
+    prefix: &str,
+    level: usize,
+    lines: &mut Vec<String>,
+    counts: &mut (usize, usize),
+) {
+    for (i, entry) in entries.iter().enumerate() {
+        let last = i + 1 == entries.len();
+        let is_dir = entry.kind == EntryKind::Directory;
+        let icon = walk.icons.icon(match entry.kind {
+            EntryKind::Directory => FOLDER,
+            EntryKind::Symlink => LINK,
+            _ => FLOPPY,
+        });
+        let branch = if last { LAST_BRANCH } else { BRANCH };
+        let line = format!("{}{}{} {}", prefix, branch, icon, entry.name);
+        if !is_dir {
+            counts.1 += 1;
+            lines.push(line);
+            continue;
+        }
+        counts.0 += 1;
+        if walk.depth.is_some_and(|depth| level >= depth) {
+            lines.push(line);
+            continue;
+        }
+        match (walk.children)(&entry.path) {
+            Ok(nested) => {
+                lines.push(line);
+                let prefix = format!("{}{}", prefix, if last { GAP } else { PIPE });
+                draw(walk, &nested, &prefix, level + 1, lines, counts);
+            }
+            Err(error) => lines.push(format!("{}  [{}]", line, error)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{self, File};
+    use tempfile::tempdir;
+
+    fn sorted_children(directory: &Path) -> io::Result<Vec<FileEntry>> {
+        let mut entries = fs::read_dir(directory)?
+            .map(|entry| entry.map(|entry| FileEntry::from_dir_entry(&entry)))
+            .collect::<io::Result<Vec<_>>>()?;
+        entries.sort_by(|left, right| left.name.cmp(&right.name));
+        Ok(entries)
+    }
+
+    #[test]
+    fn draws_branches_and_stops_at_the_depth() {
+        let temp_dir = tempdir().unwrap();
+        fs::create_dir_all(temp_dir.path().join("a/deeper")).unwrap();
+        File::create(temp_dir.path().join("a/inner.txt")).unwrap();
+        File::create(temp_dir.path().join("a/deeper/deepest.txt")).unwrap();
+        File::create(temp_dir.path().join("b.txt")).unwrap();
+        let tree = render(temp_dir.path(), None, IconStyle::Emoji, sorted_children).unwrap();
+        let expected = [
+            temp_dir.path().display().to_string(),
+            format!("├── {} a", FOLDER),
+            format!("│   ├── {} deeper", FOLDER),
+            format!("│   │   └── {} deepest.txt", FLOPPY),
+            format!("│   └── {} inner.txt", FLOPPY),
+            format!("└── {} b.txt", FLOPPY),
+            String::new(),
+            String::from("2 directories, 3 files"),
+        ]
+        .join("\n");
+        assert_eq!(tree, expected);
+        let shallow = render(temp_dir.path(), Some(1), IconStyle::Ascii, sorted_children).unwrap();
+        assert!(!shallow.contains("inner.txt"));
+        assert!(shallow.contains("├── d  a\n└── -  b.txt"));
+        assert!(shallow.ends_with("1 directory, 1 file"));
+    }
+}
@@ -0,0 +1,107 @@
+use std::ffi::CStr;
+
+const LOOKUP_BUFFER_SIZE: usize = 16 * 1024;
+
+pub(crate) fn user_name(uid: u32) -> Option<String> {
+    with_passwd(uid, |passwd| unsafe { CStr::from_ptr(passwd.pw_name) })
+}
+
+/// The real name from the GECOS field, which holds comma separated details after the name.
+pub(crate) fn user_full_name(uid: u32) -> Option<String> {
+    real_name_from_gecos(with_passwd(uid, |passwd| {
+        if passwd.pw_gecos.is_null() {
+            return c"";
+        }
+        unsafe { CStr::from_ptr(passwd.pw_gecos) }
+    })?)
+}
+
+fn real_name_from_gecos(gecos: String) -> Option<String> {
+    let name = gecos.split(',').next().unwrap_or_default().trim();
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+fn with_passwd<F>(uid: u32, field: F) -> Option<String>
+where
+    F: for<'a> Fn(&'a libc::passwd) -> &'a CStr,
+{
+    let mut passwd: libc::passwd = unsafe { std::mem::zeroed() };
+    let mut buffer = vec![0 as libc::c_char; LOOKUP_BUFFER_SIZE];
+    let mut result: *mut libc::passwd = std::ptr::null_mut();
+    let status = unsafe {
+        libc::getpwuid_r(
+            uid,
+            &mut passwd,
+            buffer.as_mut_ptr(),
+            buffer.len(),
+            &mut result,
+        )
+    };
+    if status != 0 || result.is_null() {
+        return None;
+    }
+    Some(field(&passwd).to_string_lossy().to_string())
+}
+
+pub(crate) fn group_name(gid: u32) -> Option<String> {
+    let mut group: libc::group = unsafe { std::mem::zeroed() };
+    let mut buffer = vec![0 as libc::c_char; LOOKUP_BUFFER_SIZE];
+    let mut result: *mut libc::group = std::ptr::null_mut();
+    let status = unsafe {
+        libc::getgrgid_r(
+            gid,
+            &mut group,
+            buffer.as_mut_ptr(),
+            buffer.len(),
+            &mut result,
+        )
+    };
+    if status != 0 || result.is_null() {
+        return None;
+    }
+    Some(
+        unsafe { CStr::from_ptr(group.gr_name) }
+            .to_string_lossy()
+            .to_string(),
+    )
+}
+
+pub(crate) fn effective_ids() -> (u32, u32) {
+    unsafe { (libc::geteuid(), libc::getegid()) }
+}
+
+pub(crate) fn is_member_of(gid: u32) -> bool {
+    let (_, egid) = effective_ids();
+    if gid == egid {
+        return true;
+    }
+    let count = unsafe { libc::getgroups(0, std::ptr::null_mut()) };
+    if count <= 0 {
+        return false;
+    }
+    let mut groups = vec![0 as libc::gid_t; count as usize];
+    let count = unsafe { libc::getgroups(count, groups.as_mut_ptr()) };
+    count > 0 && groups[..count as usize].contains(&gid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_names_for_the_current_user() {
+        let (euid, egid) = effective_ids();
+        assert!(user_name(euid).is_some());
+        assert!(group_name(egid).is_some());
+        assert!(is_member_of(egid));
+    }
+
+    #[test]
+    fn full_name_is_the_first_gecos_field() {
+        assert_eq!(
+            real_name_from_gecos(String::from("Ada Lovelace,Room 12,555-0100")),
+            Some(String::from("Ada Lovelace"))
+        );
+        assert_eq!(real_name_from_gecos(String::from(",,,")), None);
+    }
+}
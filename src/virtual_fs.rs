@@ -0,0 +1,100 @@
+use std::fs::Metadata;
+use std::path::Path;
+
+/// Kernel backed filesystems whose files are generated on demand. Their sizes are meaningless,
+/// usually zero or a page, and reading some of them blocks until the kernel has something to say.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum VirtualFs {
+    Proc,
+    Sys,
+    Dev,
+}
+
+impl VirtualFs {
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            VirtualFs::Proc => "procfs",
+            VirtualFs::Sys => "sysfs",
+            VirtualFs::Dev => "devfs",
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub(crate) fn detect(path: &Path) -> Option<VirtualFs> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+    const PROC_SUPER_MAGIC: u32 = 0x9fa0;
+    const SYSFS_MAGIC: u32 = 0x6265_6572;
+    const CGROUP_SUPER_MAGIC: u32 = 0x0027_e0eb;
+    const CGROUP2_SUPER_MAGIC: u32 = 0x6367_7270;
+    const DEBUGFS_MAGIC: u32 = 0x6462_6720;
+    const TRACEFS_MAGIC: u32 = 0x7472_6163;
+    const SECURITYFS_MAGIC: u32 = 0x7363_6673;
+    const DEVPTS_SUPER_MAGIC: u32 = 0x1cd1;
+    const TMPFS_MAGIC: u32 = 0x0102_1994;
+    let c_path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stats: libc::statfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statfs(c_path.as_ptr(), &mut stats) } != 0 {
+        return None;
+    }
+    // the field is signed on some targets, every magic number fits in 32 bits
+    match stats.f_type as u32 {
+        PROC_SUPER_MAGIC => Some(VirtualFs::Proc),
+        SYSFS_MAGIC | CGROUP_SUPER_MAGIC | CGROUP2_SUPER_MAGIC | DEBUGFS_MAGIC | TRACEFS_MAGIC
+        | SECURITYFS_MAGIC => Some(VirtualFs::Sys),
+        DEVPTS_SUPER_MAGIC => Some(VirtualFs::Dev),
+        // devtmpfs reports the tmpfs magic, only the mount table tells `/dev` from `/tmp`
+        TMPFS_MAGIC => {
+            (crate::mounts::filesystem_type(path) == Some("devtmpfs")).then_some(VirtualFs::Dev)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn detect(_path: &Path) -> Option<VirtualFs> {
+    None
+}
+
+/// Identifies the filesystem holding an entry so mount points can be spotted cheaply while
+/// walking, only entries on a different device than their parent need `detect`.
+#[cfg(unix)]
+pub(crate) fn device(meta_data: &Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    meta_data.dev()
+}
+
+#[cfg(not(unix))]
+pub(crate) fn device(_meta_data: &Metadata) -> u64 {
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn ordinary_directories_are_not_virtual() {
+        let temp_dir = tempdir().unwrap();
+        assert_eq!(detect(temp_dir.path()), None);
+        assert_eq!(detect(&temp_dir.path().join("missing")), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn recognises_procfs() {
+        if Path::new("/proc/self").exists() {
+            assert_eq!(detect(Path::new("/proc")), Some(VirtualFs::Proc));
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn recognises_devtmpfs() {
+        if crate::mounts::filesystem_type(Path::new("/dev")) == Some("devtmpfs") {
+            assert_eq!(detect(Path::new("/dev")), Some(VirtualFs::Dev));
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use crate::search::NamePattern;
+use crate::virtual_fs;
+use crate::FileEntryParsingError;
+use std::fs;
+use std::fs::{DirEntry, Metadata};
+use std::path::{Path, PathBuf};
+
+pub(crate) struct WalkedEntry {
+    pub(crate) path: PathBuf,
+    pub(crate) metadata: Metadata,
+    pub(crate) depth: usize,
+    pub(crate) dir_entry: DirEntry,
+    /// Set below a procfs or sysfs style mount, whose sizes mean nothing and whose files may
+    /// block when read.
+    pub(crate) pseudo: bool,
+}
+
+/// Visits every entry below `root` depth first. Symlinks are reported but never followed and
+/// nested directories that cannot be read are skipped, only an unreadable root is an error.
+/// Virtual filesystems mounted below the root are not entered, though they can be walked directly.
+/// Names matching an `exclude` pattern are neither visited nor, for directories, entered.
+pub(crate) fn walk<F>(
+    root: &Path,
+    exclude: &[NamePattern],
+    visitor: &mut F,
+) -> Result<(), FileEntryParsingError>
+where
+    F: FnMut(WalkedEntry),
+{
+    walk_to_depth(root, None, exclude, visitor)
+}
+
+/// As `walk` but does not descend past `max_depth`, where depth 1 is the root's own entries.
+pub(crate) fn walk_to_depth<F>(
+    root: &Path,
+    max_depth: Option<usize>,
+    exclude: &[NamePattern],
+    visitor: &mut F,
+) -> Result<(), FileEntryParsingError>
+where
+    F: FnMut(WalkedEntry),
+{
+    let root_contents = match fs::read_dir(root) {
+        Ok(contents) => contents,
+        Err(original_error) => {
+            return Err(FileEntryParsingError::unreadable_dir(root, &original_error))
+        }
+    };
+    let root_device = fs::metadata(root).map_or(0, |meta_data| virtual_fs::device(&meta_data));
+    let root_pseudo = virtual_fs::detect(root).is_some();
+    let mut pending = vec![(root_contents, 1usize, root_device, root_pseudo)];
+    while let Some((mut contents, depth, device, pseudo)) = pending.pop() {
+        let Some(dir_entry) = contents.next() else {
+            continue;
+        };
+        pending.push((contents, depth, device, pseudo));
+        let Ok(dir_entry) = dir_entry else {
+            continue;
+        };
+        let name = dir_entry.file_name();
+        if exclude
+            .iter()
+            .any(|pattern| pattern.matches(&name.to_string_lossy()))
+        {
+            continue;
+        }
+        let Ok(metadata) = fs::symlink_metadata(dir_entry.path()) else {
+            continue;
+        };
+        let path = dir_entry.path();
+        let nested_device = virtual_fs::device(&metadata);
+        let nested_pseudo = if nested_device == device {
+            pseudo
+        } else {
+            virtual_fs::detect(&path).is_some()
+        };
+        let nested = if metadata.is_dir()
+            && max_depth.is_none_or(|max_depth| depth < max_depth)
+            && (pseudo || !nested_pseudo)
+        {
+            fs::read_dir(&path).ok()
+        } else {
+            None
+        };
+        visitor(WalkedEntry {
+            path,
+            metadata,
+            depth,
+            dir_entry,
+            pseudo,
+        });
+        if let Some(nested) = nested {
+            pending.push((nested, depth + 1, nested_device, nested_pseudo));
+        }
+    }
+    Ok(())
+}